@@ -0,0 +1,71 @@
+//! 多数の小さなファイルを含むツリーの再帰コピーを、逐次走査と並列走査（`--jobs`）で比較する。
+//!
+//! `cargo bench --bench copy_tree` で実行する。
+
+use clap::Parser;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use safecmd::commands::cp::{self, args::Args};
+use safecmd::config::{AdditionalAllowedDirectories, Config};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const DIRECTORIES: usize = 40;
+const FILES_PER_DIRECTORY: usize = 50;
+
+/// 2階層のディレクトリに小さなファイルを並べたコピー元ツリーを作成する。
+fn build_source_tree(root: &Path) {
+    for dir in 0..DIRECTORIES {
+        let nested = root.join(format!("dir{dir:02}")).join("nested");
+        fs::create_dir_all(&nested).expect("create source dirs");
+        for file in 0..FILES_PER_DIRECTORY {
+            fs::write(
+                nested.join(format!("file{file:03}.txt")),
+                format!("{dir}:{file}").repeat(64),
+            )
+            .expect("write source file");
+        }
+    }
+}
+
+/// 許可範囲チェックでベンチマークが失敗しないよう、全体を許可した設定を返す。
+fn allow_all_config() -> Config {
+    Config {
+        additional_allowed_directories: AdditionalAllowedDirectories {
+            paths: vec!["/".into()],
+        },
+        ..Config::default()
+    }
+}
+
+fn copy_tree(c: &mut Criterion) {
+    let source = TempDir::new().expect("create source dir");
+    let source_root = source.path().join("tree");
+    build_source_tree(&source_root);
+
+    let mut group = c.benchmark_group("copy_tree");
+    group.sample_size(10);
+    for jobs in [1, 2, 4, 8] {
+        group.bench_function(format!("jobs={jobs}"), |b| {
+            b.iter_batched(
+                || TempDir::new().expect("create target dir"),
+                |target| {
+                    let args = Args::parse_from([
+                        "cp".as_ref(),
+                        "-r".as_ref(),
+                        format!("--jobs={jobs}").as_ref(),
+                        source_root.as_os_str(),
+                        target.path().join("copy").as_os_str(),
+                    ]);
+                    assert_eq!(cp::run(args, allow_all_config()), 0);
+                    target
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, copy_tree);
+criterion_main!(benches);
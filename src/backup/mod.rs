@@ -0,0 +1,348 @@
+use crate::config::{BackupConfig, BackupScopes, ScopeSet};
+use crate::dirfd::{Dir, RenameMode};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 単純バックアップの既定の接尾辞。
+pub const DEFAULT_SUFFIX: &str = "~";
+
+/// 番号付きバックアップの番号を確保し直す回数の上限。
+const MAX_NUMBERED_ATTEMPTS: u64 = 64;
+
+/// GNU の `--backup` と同じバックアップ方式を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupControl {
+    /// バックアップを作らず、既存ターゲットはゴミ箱へ移動する
+    #[value(alias = "off")]
+    #[serde(alias = "off", alias = "trash")]
+    None,
+    /// 常に番号付きバックアップ（`name.~N~`）を作る
+    #[value(alias = "t")]
+    #[serde(alias = "t")]
+    Numbered,
+    /// 番号付きバックアップが既にあれば番号付き、なければ単純バックアップを作る
+    #[value(alias = "nil")]
+    #[serde(alias = "nil")]
+    Existing,
+    /// 常に単純バックアップ（`name` + 接尾辞）を作る
+    #[value(alias = "never")]
+    #[serde(alias = "never")]
+    Simple,
+}
+
+/// 既存ターゲットごとに解決したバックアップ方針。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupPolicy {
+    pub control: BackupControl,
+    pub suffix: String,
+}
+
+impl BackupPolicy {
+    /// コマンドラインの指定と設定から、`target` の上書きに適用する方針を決める。
+    ///
+    /// 方式と接尾辞はそれぞれコマンドライン、`target` を含む最も深い `[[backup.scopes]]`、
+    /// `[backup]` の順に優先し、どれも未設定ならゴミ箱へ移動する。
+    /// `scopes` は `config.resolve_scopes()` で実行開始時に構築したものを渡す。
+    pub fn resolve(
+        cli_control: Option<BackupControl>,
+        cli_suffix: Option<&str>,
+        config: &BackupConfig,
+        scopes: &BackupScopes,
+        target: &Path,
+    ) -> Self {
+        let scope = scopes.scope_for(target);
+        let control = cli_control
+            .or_else(|| scope.map(|scope| scope.control))
+            .or(config.control)
+            .unwrap_or(BackupControl::None);
+        let suffix = cli_suffix
+            .or_else(|| scope.and_then(|scope| scope.suffix.as_deref()))
+            .or(config.suffix.as_deref())
+            .unwrap_or(DEFAULT_SUFFIX);
+        Self {
+            control,
+            suffix: suffix.to_string(),
+        }
+    }
+
+    /// ゴミ箱の代わりにバックアップを作るかを返す。
+    pub fn makes_backup(&self) -> bool {
+        self.control != BackupControl::None
+    }
+}
+
+/// 作成したバックアップを表す。
+#[derive(Debug)]
+pub struct Backup {
+    /// 同じディレクトリ内のバックアップのエントリ名。
+    pub name: OsString,
+    /// ハードリンクで作成し、元のエントリも残っている。
+    pub linked: bool,
+}
+
+/// 接尾辞がバックアップ名に使えるかを検証する。
+pub fn validate_suffix(suffix: &str) -> Result<(), String> {
+    if suffix.is_empty() || suffix.contains('/') {
+        return Err(format!("invalid backup suffix '{suffix}'"));
+    }
+    Ok(())
+}
+
+/// `-S`/`--suffix` の値を検証して受け付ける。
+pub fn parse_suffix(suffix: &str) -> Result<String, String> {
+    validate_suffix(suffix)?;
+    Ok(suffix.to_string())
+}
+
+/// `dir` 直下の既存エントリ `name` を、同じディレクトリのバックアップとして残す。
+///
+/// 可能な限りハードリンクで作成して元のエントリを残し、呼び出し側が rename で不可分に置き換えられる
+/// ようにする。ディレクトリ等でハードリンクできない場合はバックアップ名へ rename する。
+/// どちらもバックアップ名の既存エントリを上書きしない。単純バックアップが既にある場合は
+/// `retire` へそのパスを渡して（ゴミ箱へ）退避してから作成する。
+/// バックアップのパスが許可範囲外になる場合は作成しない。
+pub fn back_up<F>(
+    dir: &Dir,
+    name: &OsStr,
+    policy: &BackupPolicy,
+    scopes: &ScopeSet,
+    retire: F,
+) -> io::Result<Backup>
+where
+    F: FnOnce(&Path) -> io::Result<()>,
+{
+    validate_suffix(&policy.suffix).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let highest = highest_numbered(dir, name)?;
+    let numbered = match policy.control {
+        BackupControl::None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "backups are disabled",
+            ));
+        }
+        BackupControl::Numbered => true,
+        BackupControl::Existing => highest > 0,
+        BackupControl::Simple => false,
+    };
+
+    if !numbered {
+        let backup = simple_name(name, &policy.suffix);
+        ensure_allowed(dir, &backup, scopes)?;
+        if dir.contains(&backup) {
+            retire(&dir.verified_path(&backup)?)?;
+        }
+        let linked = place(dir, name, &backup)?;
+        return Ok(Backup {
+            name: backup,
+            linked,
+        });
+    }
+
+    let first = highest.checked_add(1).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "numbered backup count exceeds the largest supported number",
+        )
+    })?;
+    for number in (first..=u64::MAX).take(MAX_NUMBERED_ATTEMPTS as usize) {
+        let backup = numbered_name(name, number);
+        ensure_allowed(dir, &backup, scopes)?;
+        match place(dir, name, &backup) {
+            Ok(linked) => {
+                return Ok(Backup {
+                    name: backup,
+                    linked,
+                });
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "failed to allocate a numbered backup name",
+    ))
+}
+
+/// バックアップ名へハードリンクし、できなければ rename する。ハードリンクできたかを返す。
+fn place(dir: &Dir, name: &OsStr, backup: &OsStr) -> io::Result<bool> {
+    match dir.hard_link(name, dir, backup) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(e),
+        Err(_) => dir
+            .rename(name, dir, backup, RenameMode::NoReplace)
+            .map(|()| false),
+    }
+}
+
+/// バックアップのパスが許可範囲内かを確認する。
+fn ensure_allowed(dir: &Dir, backup: &OsStr, scopes: &ScopeSet) -> io::Result<()> {
+    let path: PathBuf = dir.path().join(backup);
+    if scopes.contains(&path) {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("backup '{}' is outside allowed scope", path.display()),
+    ))
+}
+
+/// `name` + 接尾辞の単純バックアップ名を返す。
+fn simple_name(name: &OsStr, suffix: &str) -> OsString {
+    let mut backup = name.to_os_string();
+    backup.push(suffix);
+    backup
+}
+
+/// `name.~N~` の番号付きバックアップ名を返す。
+fn numbered_name(name: &OsStr, number: u64) -> OsString {
+    let mut backup = name.to_os_string();
+    backup.push(format!(".~{number}~"));
+    backup
+}
+
+/// `dir` 内にある `name` の番号付きバックアップの最大番号を返す。なければ 0。
+fn highest_numbered(dir: &Dir, name: &OsStr) -> io::Result<u64> {
+    let prefix = simple_name(name, ".~");
+    let prefix = prefix.as_encoded_bytes();
+    let highest = dir
+        .entry_names()?
+        .iter()
+        .filter_map(|entry| {
+            let number = entry
+                .as_encoded_bytes()
+                .strip_prefix(prefix)?
+                .strip_suffix(b"~")?;
+            if number.first() == Some(&b'0') || !number.iter().all(u8::is_ascii_digit) {
+                return None;
+            }
+            std::str::from_utf8(number).ok()?.parse::<u64>().ok()
+        })
+        .max()
+        .unwrap_or(0);
+    Ok(highest)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn policy(control: BackupControl) -> BackupPolicy {
+        BackupPolicy {
+            control,
+            suffix: DEFAULT_SUFFIX.to_string(),
+        }
+    }
+
+    fn allow_all() -> ScopeSet {
+        let mut config = Config::default();
+        config.additional_allowed_directories.paths = vec![PathBuf::from("/")];
+        config.scope_set()
+    }
+
+    #[test]
+    fn numbered_backup_uses_next_free_number() {
+        // 番号付きバックアップが既存の最大番号の次になり、元のエントリも残ることを確認する。
+        let temp_dir = tempdir().expect("create temp dir");
+        fs::write(temp_dir.path().join("file"), b"current").expect("write file");
+        fs::write(temp_dir.path().join("file.~2~"), b"second").expect("write backup");
+        fs::write(temp_dir.path().join("file.~09~"), b"ignored").expect("write odd name");
+        let dir = Dir::open(temp_dir.path()).expect("open dir");
+
+        let backup = back_up(
+            &dir,
+            OsStr::new("file"),
+            &policy(BackupControl::Numbered),
+            &allow_all(),
+            |_| panic!("numbered backups never retire"),
+        )
+        .expect("back up");
+
+        assert_eq!(backup.name, "file.~3~");
+        assert!(backup.linked);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("file.~3~")).expect("read backup"),
+            "current"
+        );
+        assert!(temp_dir.path().join("file").exists());
+    }
+
+    #[test]
+    fn numbered_backup_fails_when_highest_number_is_max() {
+        // 既存の最大番号が u64::MAX の場合はオーバーフローせずエラーにし、何も作らないことを確認する。
+        let temp_dir = tempdir().expect("create temp dir");
+        fs::write(temp_dir.path().join("file"), b"current").expect("write file");
+        fs::write(
+            temp_dir.path().join(format!("file.~{}~", u64::MAX)),
+            b"last",
+        )
+        .expect("write backup");
+        let dir = Dir::open(temp_dir.path()).expect("open dir");
+
+        let err = back_up(
+            &dir,
+            OsStr::new("file"),
+            &policy(BackupControl::Numbered),
+            &allow_all(),
+            |_| panic!("numbered backups never retire"),
+        )
+        .expect_err("overflowing backup number");
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read_dir(temp_dir.path()).expect("read dir").count(), 2);
+    }
+
+    #[test]
+    fn existing_control_falls_back_to_simple_backup() {
+        // existing では番号付きバックアップがなければ単純バックアップを作ることを確認する。
+        let temp_dir = tempdir().expect("create temp dir");
+        fs::write(temp_dir.path().join("file"), b"current").expect("write file");
+        let dir = Dir::open(temp_dir.path()).expect("open dir");
+
+        let backup = back_up(
+            &dir,
+            OsStr::new("file"),
+            &policy(BackupControl::Existing),
+            &allow_all(),
+            |_| panic!("no previous backup to retire"),
+        )
+        .expect("back up");
+
+        assert_eq!(backup.name, "file~");
+    }
+
+    #[test]
+    fn simple_backup_retires_previous_backup_first() {
+        // 既存の単純バックアップを上書きせず、retire へ渡してから作成することを確認する。
+        let temp_dir = tempdir().expect("create temp dir");
+        fs::write(temp_dir.path().join("file"), b"current").expect("write file");
+        fs::write(temp_dir.path().join("file~"), b"previous").expect("write backup");
+        let retired = temp_dir.path().join("retired");
+        let dir = Dir::open(temp_dir.path()).expect("open dir");
+
+        back_up(
+            &dir,
+            OsStr::new("file"),
+            &policy(BackupControl::Simple),
+            &allow_all(),
+            |path| fs::rename(path, &retired),
+        )
+        .expect("back up");
+
+        assert_eq!(
+            fs::read_to_string(&retired).expect("read retired"),
+            "previous"
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("file~")).expect("read backup"),
+            "current"
+        );
+    }
+}
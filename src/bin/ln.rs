@@ -0,0 +1,6 @@
+use safecmd::multicall::Applet;
+
+/// Safe replacement for the `ln` command.
+fn main() {
+    std::process::exit(Applet::Ln.run(std::env::args_os()));
+}
@@ -1,20 +1,6 @@
-use clap::Parser;
-use safecmd::commands::rm::{self, args::Args};
-use safecmd::config::Config;
+use safecmd::multicall::Applet;
 
 /// Safe replacement for the `rm` command.
 fn main() {
-    let args = Args::parse();
-
-    // Load configuration
-    let config = match Config::load() {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("rm: {e}");
-            std::process::exit(1);
-        }
-    };
-
-    let exit_code = rm::run(args, config);
-    std::process::exit(exit_code);
+    std::process::exit(Applet::Rm.run(std::env::args_os()));
 }
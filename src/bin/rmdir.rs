@@ -0,0 +1,6 @@
+use safecmd::multicall::Applet;
+
+/// Safe replacement for the `rmdir` command.
+fn main() {
+    std::process::exit(Applet::Rmdir.run(std::env::args_os()));
+}
@@ -0,0 +1,50 @@
+use clap::{Parser, Subcommand};
+use safecmd::commands::{cp, ln, mv, rm, rmdir, write};
+use safecmd::multicall::{self, Applet, install};
+
+/// Safe replacements for destructive shell operations.
+///
+/// When invoked through a link named after a command (for example `rm`), runs that command.
+#[derive(Parser, Debug)]
+#[command(name = "safecmd", version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Move files or directories to the trash
+    Rm(rm::args::Args),
+    /// Move empty directories to the trash
+    Rmdir(rmdir::args::Args),
+    /// Copy files, trashing overwritten targets
+    Cp(cp::args::Args),
+    /// Move files, trashing overwritten targets
+    Mv(mv::args::Args),
+    /// Create links, trashing replaced link names
+    Ln(ln::args::Args),
+    /// Atomically replace a file with standard input, trashing the previous version
+    Write(write::args::Args),
+    /// Create links named rm, rmdir, cp, mv and ln that point to this executable
+    Install(install::Args),
+}
+
+fn main() {
+    let argv: Vec<_> = std::env::args_os().collect();
+    // argv[0] がコマンド名のリンク経由で起動された場合はそのコマンドとして実行する
+    if let Some(applet) = argv.first().and_then(|arg0| Applet::from_argv0(arg0)) {
+        std::process::exit(applet.run(argv));
+    }
+
+    let exit_code = match Cli::parse_from(argv).command {
+        Command::Rm(args) => multicall::run_with_config("rm", args, rm::run),
+        Command::Rmdir(args) => multicall::run_with_config("rmdir", args, rmdir::run),
+        Command::Cp(args) => multicall::run_with_config("cp", args, cp::run),
+        Command::Mv(args) => multicall::run_with_config("mv", args, mv::run),
+        Command::Ln(args) => multicall::run_with_config("ln", args, ln::run),
+        Command::Write(args) => multicall::run_with_config("write", args, write::run),
+        Command::Install(args) => install::run(args),
+    };
+    std::process::exit(exit_code);
+}
@@ -1,4 +1,8 @@
-use clap::Parser;
+use crate::backup::{self, BackupControl};
+use crate::update::UpdateMode;
+use crate::verify::HashAlgorithm;
+use clap::{Parser, ValueEnum};
+use std::num::NonZeroUsize;
 
 #[derive(Parser, Debug)]
 #[command(name = "cp")]
@@ -12,11 +16,211 @@ pub struct Args {
     #[arg(short = 'n')]
     pub no_clobber: bool,
 
+    /// Keep each overwritten destination as a backup beside it instead of trashing it (`-b` means `existing`)
+    #[arg(
+        short = 'b',
+        long = "backup",
+        value_name = "CONTROL",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "existing",
+        conflicts_with = "no_clobber"
+    )]
+    pub backup: Option<BackupControl>,
+
+    /// Override the usual backup suffix (`~`)
+    #[arg(short = 'S', long = "suffix", value_name = "SUFFIX", value_parser = backup::parse_suffix)]
+    pub suffix: Option<String>,
+
+    /// Replace existing destinations only when older (`-u`), never (`none`), always (`all`) or when their content differs (`content`)
+    #[arg(
+        short = 'u',
+        long = "update",
+        value_name = "WHEN",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = UpdateMode::All,
+        default_missing_value = "older"
+    )]
+    pub update: UpdateMode,
+
     /// Copy directories recursively
     #[arg(short = 'R', short_alias = 'r', long = "recursive")]
     pub recursive: bool,
 
-    /// Files to copy (source and target)
-    #[arg(required = true, num_args = 2..)]
+    /// Never follow symbolic links in SOURCE (default with -R/-a)
+    #[arg(
+        short = 'P',
+        long = "no-dereference",
+        overrides_with_all = ["dereference", "dereference_command_line"]
+    )]
+    pub no_dereference: bool,
+
+    /// Always follow symbolic links in SOURCE
+    #[arg(
+        short = 'L',
+        long = "dereference",
+        overrides_with_all = ["no_dereference", "dereference_command_line"]
+    )]
+    pub dereference: bool,
+
+    /// Follow symbolic links given on the command line only
+    #[arg(short = 'H', overrides_with_all = ["no_dereference", "dereference"])]
+    pub dereference_command_line: bool,
+
+    /// Same as `-R -P --preserve=all`
+    #[arg(short = 'a', long = "archive")]
+    pub archive: bool,
+
+    /// Same as `--preserve=mode,ownership,timestamps`
+    #[arg(short = 'p')]
+    pub preserve_default: bool,
+
+    /// Preserve the specified attributes (default: mode,ownership,timestamps)
+    #[arg(
+        long = "preserve",
+        value_name = "ATTR_LIST",
+        value_enum,
+        value_delimiter = ',',
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_values = ["mode", "ownership", "timestamps"]
+    )]
+    pub preserve: Vec<PreserveAttr>,
+
+    /// Control copy-on-write clones (`--reflink` alone means `always`)
+    #[arg(
+        long = "reflink",
+        value_name = "WHEN",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = ReflinkMode::Auto,
+        default_missing_value = "always"
+    )]
+    pub reflink: ReflinkMode,
+
+    /// Control creation of sparse files
+    #[arg(
+        long = "sparse",
+        value_name = "WHEN",
+        value_enum,
+        default_value_t = SparseMode::Auto
+    )]
+    pub sparse: SparseMode,
+
+    /// Hash each copied file while reading and re-read the copy after fsync to verify it
+    #[arg(
+        long = "verify",
+        value_name = "ALGO",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "blake3"
+    )]
+    pub verify: Option<HashAlgorithm>,
+
+    /// Explain what is being done
+    #[arg(short = 'v', long = "verbose")]
+    pub verbose: bool,
+
+    /// Copy directory trees with N worker threads (1 copies sequentially)
+    #[arg(long = "jobs", value_name = "N", default_value = "1")]
+    pub jobs: NonZeroUsize,
+
+    /// Report files and bytes copied on stderr (a live line on a terminal, JSON records otherwise)
+    #[arg(long = "progress")]
+    pub progress: bool,
+
+    /// Print one JSON record per copied entry to stdout
+    #[arg(long = "json")]
+    pub json: bool,
+
+    /// Overwrite targets with uncommitted git changes when `[git] protect_dirty` is enabled
+    #[arg(long = "allow-dirty")]
+    pub allow_dirty: bool,
+
+    /// Confirm trashing an existing target above the `[limits]` settings by echoing its exact file count
+    #[arg(long = "yes-i-mean-it", value_name = "COUNT")]
+    pub yes_i_mean_it: Option<u64>,
+
+    /// Skip entries excluded by .gitignore, .git/info/exclude and the global excludes file during recursive copy
+    #[arg(long = "respect-gitignore")]
+    pub respect_gitignore: bool,
+
+    /// Skip entries matching the gitignore-style pattern during recursive copy (repeatable)
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Copy entries matching the gitignore-style pattern even if otherwise skipped (repeatable)
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Copy all sources into DIRECTORY
+    #[arg(
+        short = 't',
+        long = "target-directory",
+        value_name = "DIRECTORY",
+        conflicts_with = "no_target_directory"
+    )]
+    pub target_directory: Option<String>,
+
+    /// Treat the destination as a normal file even if it is an existing directory
+    #[arg(short = 'T', long = "no-target-directory")]
+    pub no_target_directory: bool,
+
+    /// Hard link files instead of copying them
+    #[arg(
+        short = 'l',
+        long = "link",
+        conflicts_with_all = ["symbolic_link", "verify"]
+    )]
+    pub link: bool,
+
+    /// Make symbolic links to the source files instead of copying them
+    #[arg(short = 's', long = "symbolic-link", conflicts_with = "verify")]
+    pub symbolic_link: bool,
+
+    /// Append each source path to the target directory, creating the missing directories
+    #[arg(long = "parents", conflicts_with = "no_target_directory")]
+    pub parents: bool,
+
+    /// Files to copy (sources, then the target unless `-t` is given)
+    #[arg(required = true, num_args = 1..)]
     pub files: Vec<String>,
 }
+
+/// `--preserve` で指定できる属性。
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreserveAttr {
+    Mode,
+    Timestamps,
+    Ownership,
+    Xattr,
+    Links,
+    All,
+}
+
+/// `--reflink` で指定できる複製方式。
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReflinkMode {
+    /// 複製できない場合は通常コピーへフォールバックする
+    Auto,
+    /// 複製できない場合はエラーにする
+    Always,
+    /// 常に通常コピーを行う
+    Never,
+}
+
+/// `--sparse` で指定できる穴あきファイルの扱い。
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SparseMode {
+    /// コピー元が穴を含む場合のみ穴を再現する
+    Auto,
+    /// ゼロのみの領域もすべて穴にする
+    Always,
+    /// 穴を再現しない
+    Never,
+}
@@ -0,0 +1,83 @@
+use crate::commands::cp::args::Args;
+use std::io;
+
+/// シンボリックリンクをどこまで辿ってコピーするかを表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dereference {
+    /// `-P`: リンクを辿らずリンクとして複製する。
+    Never,
+    /// `-H`: コマンドライン引数のリンクのみ辿り、ツリー内のリンクはリンクとして複製する。
+    CommandLine,
+    /// `-L`: すべてのリンクを辿ってリンク先の内容を複製する。
+    Always,
+}
+
+impl Dereference {
+    /// 引数からリンクの扱いを決定する。
+    ///
+    /// 明示指定がない場合、GNU `cp` と同様に再帰コピー（`-R`/`-a`）では `-P`、
+    /// それ以外では `-H` 相当（コマンドライン引数のみ辿る）とする。
+    pub fn from_args(args: &Args) -> Self {
+        if args.dereference {
+            Self::Always
+        } else if args.dereference_command_line {
+            Self::CommandLine
+        } else if args.no_dereference || args.recursive || args.archive {
+            Self::Never
+        } else {
+            Self::CommandLine
+        }
+    }
+
+    /// コマンドライン引数で指定されたリンクを辿るかを返す。
+    pub fn follows_command_line(self) -> bool {
+        !matches!(self, Self::Never)
+    }
+
+    /// 再帰コピー中に見つけたリンクを辿るかを返す。
+    pub fn follows_in_tree(self) -> bool {
+        matches!(self, Self::Always)
+    }
+}
+
+/// リンクの循環（自己参照や相互参照）による解決失敗かを返す。
+#[cfg(unix)]
+pub fn is_symlink_loop(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::ELOOP)
+}
+
+#[cfg(not(unix))]
+pub fn is_symlink_loop(_err: &io::Error) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn policy_for(flags: &[&str]) -> Dereference {
+        let mut argv = vec!["cp"];
+        argv.extend_from_slice(flags);
+        argv.extend_from_slice(&["source", "target"]);
+        Dereference::from_args(&Args::parse_from(argv))
+    }
+
+    #[test]
+    fn from_args_defaults_depend_on_recursion() {
+        // 明示指定がない場合は再帰時に -P、非再帰時に -H 相当となることを確認する。
+        assert_eq!(policy_for(&[]), Dereference::CommandLine);
+        assert_eq!(policy_for(&["-r"]), Dereference::Never);
+        assert_eq!(policy_for(&["-a"]), Dereference::Never);
+    }
+
+    #[test]
+    fn from_args_respects_explicit_flags() {
+        // -P/-L/-H の明示指定と、後から指定したフラグが優先されることを確認する。
+        assert_eq!(policy_for(&["-P"]), Dereference::Never);
+        assert_eq!(policy_for(&["-r", "-L"]), Dereference::Always);
+        assert_eq!(policy_for(&["-a", "-H"]), Dereference::CommandLine);
+        assert_eq!(policy_for(&["-L", "-P"]), Dereference::Never);
+        assert_eq!(policy_for(&["-P", "-L"]), Dereference::Always);
+    }
+}
@@ -0,0 +1,600 @@
+use crate::commands::cp::args::{Args, ReflinkMode, SparseMode};
+use crate::verify::{self, Digest, HashAlgorithm, Hasher};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 通常ファイルの内容を複製した方式を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyMethod {
+    /// FICLONE によるコピーオンライトの複製。
+    Reflink,
+    /// `copy_file_range` によるカーネル内コピー。
+    CopyFileRange,
+    /// `SEEK_DATA`/`SEEK_HOLE` でデータ領域のみを書き込み、穴を再現したコピー。
+    Sparse,
+    /// ユーザー空間での読み書きによるコピー。
+    ReadWrite,
+}
+
+/// `--reflink`/`--sparse`/`--verify` で指定されたファイル内容の複製方針を保持する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyOptions {
+    pub reflink: ReflinkMode,
+    pub sparse: SparseMode,
+    pub verify: Option<HashAlgorithm>,
+}
+
+impl CopyOptions {
+    /// 引数から複製方針を組み立てる。
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            reflink: args.reflink,
+            sparse: args.sparse,
+            verify: args.verify,
+        }
+    }
+}
+
+/// 1ファイル分の複製結果を保持する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyOutcome {
+    pub method: CopyMethod,
+    /// コピー元の論理サイズ。
+    pub logical_bytes: u64,
+    /// コピー先が実際に確保したディスク領域のサイズ。
+    pub physical_bytes: u64,
+    /// `--verify` 指定時、コピー中に読み込んだコピー元のハッシュ。
+    pub source_digest: Option<Digest>,
+}
+
+/// 開いたコピー元の通常ファイルの内容とパーミッションを複製する。
+///
+/// `--reflink` に応じて FICLONE を試み、使えない場合は `--sparse` に応じて穴を再現するコピー、
+/// `copy_file_range`、読み書きの順に透過的にフォールバックする。
+/// `--verify` 指定時はコピー中に読み込んだコピー元のハッシュを計算し、コピー先を fsync する。
+/// 複製に失敗した場合は作成途中のコピー先を削除する。
+#[cfg(target_os = "linux")]
+pub fn copy_file(
+    mut source_file: fs::File,
+    target: &Path,
+    options: CopyOptions,
+) -> io::Result<CopyOutcome> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let source_meta = source_file.metadata()?;
+    // 作成直前に置かれたシンボリックリンクや既存ファイルへ書き込まないよう、排他的に作成する。
+    let mut target_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(target)?;
+
+    let copied = copy_contents(&mut source_file, &mut target_file, &source_meta, options).and_then(
+        |copied| {
+            if options.verify.is_some() {
+                target_file.sync_all()?;
+            }
+            Ok(copied)
+        },
+    );
+    let (method, source_digest) = match copied {
+        Ok(copied) => copied,
+        Err(e) => {
+            drop(target_file);
+            let _ = fs::remove_file(target);
+            return Err(e);
+        }
+    };
+    target_file.set_permissions(source_meta.permissions())?;
+    Ok(CopyOutcome {
+        method,
+        logical_bytes: source_meta.len(),
+        physical_bytes: allocated_bytes(&target_file.metadata()?),
+        source_digest,
+    })
+}
+
+/// FICLONE や `SEEK_DATA` を持たない環境向けに読み書きで複製する。
+#[cfg(not(target_os = "linux"))]
+pub fn copy_file(
+    mut source_file: fs::File,
+    target: &Path,
+    options: CopyOptions,
+) -> io::Result<CopyOutcome> {
+    if options.reflink == ReflinkMode::Always {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "failed to clone: reflink is not supported on this platform",
+        ));
+    }
+    let mut target_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)?;
+    let (logical_bytes, source_digest) = match options.verify {
+        Some(algorithm) => {
+            let (logical_bytes, digest) =
+                verify::copy_hashed(&mut source_file, &mut target_file, algorithm)?;
+            (logical_bytes, Some(digest))
+        }
+        None => (io::copy(&mut source_file, &mut target_file)?, None),
+    };
+    target_file.set_permissions(source_file.metadata()?.permissions())?;
+    if source_digest.is_some() {
+        target_file.sync_all()?;
+    }
+    Ok(CopyOutcome {
+        method: CopyMethod::ReadWrite,
+        logical_bytes,
+        physical_bytes: allocated_bytes(&target_file.metadata()?),
+        source_digest,
+    })
+}
+
+/// ファイルが実際に確保しているディスク領域のサイズを返す。
+#[cfg(unix)]
+pub fn allocated_bytes(meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() * 512
+}
+
+#[cfg(not(unix))]
+pub fn allocated_bytes(meta: &fs::Metadata) -> u64 {
+    meta.len()
+}
+
+/// 確保済み領域が論理サイズより小さい（穴を含む）ファイルかを返す。
+pub fn is_sparse(meta: &fs::Metadata) -> bool {
+    allocated_bytes(meta) < meta.len()
+}
+
+#[cfg(target_os = "linux")]
+fn copy_contents(
+    source: &mut fs::File,
+    target: &mut fs::File,
+    source_meta: &fs::Metadata,
+    options: CopyOptions,
+) -> io::Result<(CopyMethod, Option<Digest>)> {
+    if options.reflink != ReflinkMode::Never {
+        match clone_file(source, target) {
+            Ok(()) => {
+                // 複製ではデータを読まないため、検証時はコピー元を別途読み込んでハッシュを求める。
+                let digest = match options.verify {
+                    Some(algorithm) => {
+                        let mut hasher = Hasher::new(algorithm);
+                        hasher.update_reader(source)?;
+                        Some(hasher.finalize())
+                    }
+                    None => None,
+                };
+                return Ok((CopyMethod::Reflink, digest));
+            }
+            Err(e) if options.reflink == ReflinkMode::Always => {
+                return Err(io::Error::new(e.kind(), format!("failed to clone: {e}")));
+            }
+            Err(_) => {}
+        }
+    }
+
+    let punch_zeros = match options.sparse {
+        SparseMode::Never => None,
+        SparseMode::Auto => is_sparse(source_meta).then_some(false),
+        SparseMode::Always => Some(true),
+    };
+    if let Some(punch_zeros) = punch_zeros {
+        let mut hasher = options.verify.map(Hasher::new);
+        copy_sparse(
+            source,
+            target,
+            source_meta.len(),
+            punch_zeros,
+            hasher.as_mut(),
+        )?;
+        return Ok((CopyMethod::Sparse, hasher.map(Hasher::finalize)));
+    }
+
+    // `copy_file_range` はデータがユーザー空間を通らないため、検証時は読み書きでコピーする。
+    if let Some(algorithm) = options.verify {
+        let (_, digest) = verify::copy_hashed(source, target, algorithm)?;
+        return Ok((CopyMethod::ReadWrite, Some(digest)));
+    }
+    if copy_with_file_range(source, target)? {
+        return Ok((CopyMethod::CopyFileRange, None));
+    }
+    io::copy(source, target)?;
+    Ok((CopyMethod::ReadWrite, None))
+}
+
+/// FICLONE ioctl でコピー先をコピー元のコピーオンライト複製にする。
+#[cfg(target_os = "linux")]
+fn clone_file(source: &fs::File, target: &fs::File) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    // SAFETY: どちらのファイル記述子も呼び出し中は有効で、FICLONE は引数に記述子の値のみを取る。
+    let ret = unsafe { libc::ioctl(target.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// `copy_file_range` で末尾までコピーする。
+///
+/// 最初の呼び出しが未対応系のエラーで失敗した場合は何も書き込まずに `false` を返し、
+/// 呼び出し側で読み書きによるコピーへフォールバックさせる。
+#[cfg(target_os = "linux")]
+fn copy_with_file_range(source: &fs::File, target: &fs::File) -> io::Result<bool> {
+    use std::os::fd::AsRawFd;
+
+    const CHUNK: usize = 1 << 30;
+    let mut copied_any = false;
+
+    loop {
+        // SAFETY: オフセットに NULL を渡し、各記述子のファイル位置を使ってコピーする。
+        let ret = unsafe {
+            libc::copy_file_range(
+                source.as_raw_fd(),
+                std::ptr::null_mut(),
+                target.as_raw_fd(),
+                std::ptr::null_mut(),
+                CHUNK,
+                0,
+            )
+        };
+        match ret {
+            0 => return Ok(true),
+            n if n > 0 => copied_any = true,
+            _ => {
+                let err = io::Error::last_os_error();
+                if !copied_any && is_copy_file_range_unsupported(&err) {
+                    return Ok(false);
+                }
+                return Err(err);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_copy_file_range_unsupported(err: &io::Error) -> bool {
+    matches!(
+        err.raw_os_error(),
+        Some(libc::ENOSYS | libc::EXDEV | libc::EINVAL | libc::EOPNOTSUPP | libc::EPERM)
+    )
+}
+
+/// 穴を判定する単位。`--sparse=always` ではこの単位でゼロのみの領域を書き込まない。
+#[cfg(target_os = "linux")]
+const SPARSE_BLOCK: usize = 4096;
+
+/// データ領域のみを同じオフセットへ書き込み、穴をコピー先でも再現する。
+///
+/// `punch_zeros` が `true` の場合はデータ領域内のゼロのみのブロックも書き込まずに穴とする。
+/// `hasher` 指定時は穴をゼロとして扱い、ファイル全体のハッシュを計算する。
+#[cfg(target_os = "linux")]
+fn copy_sparse(
+    source: &fs::File,
+    target: &fs::File,
+    len: u64,
+    punch_zeros: bool,
+    mut hasher: Option<&mut Hasher>,
+) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    let mut buffer = vec![0u8; 256 * SPARSE_BLOCK];
+    let mut offset = 0;
+    let mut hashed = 0;
+    while offset < len {
+        let Some((data_start, data_end)) = next_data_segment(source, offset, len)? else {
+            break;
+        };
+
+        if let Some(hasher) = hasher.as_deref_mut() {
+            hasher.update_zeros(data_start - hashed);
+        }
+        let mut position = data_start;
+        while position < data_end {
+            let wanted = buffer.len().min((data_end - position) as usize);
+            let read = source.read_at(&mut buffer[..wanted], position)?;
+            if read == 0 {
+                break;
+            }
+            if let Some(hasher) = hasher.as_deref_mut() {
+                hasher.update(&buffer[..read]);
+            }
+            write_chunk(target, &buffer[..read], position, punch_zeros)?;
+            position += read as u64;
+        }
+        hashed = position;
+        offset = data_end;
+    }
+    if let Some(hasher) = hasher {
+        hasher.update_zeros(len.saturating_sub(hashed));
+    }
+
+    // 末尾の穴は書き込みでは作られないため、論理サイズを明示的に合わせる。
+    target.set_len(len)
+}
+
+/// `offset` 以降で最初のデータ領域を `[開始, 終了)` で返す。データが残っていない場合は `None`。
+///
+/// `SEEK_DATA` 非対応のファイルシステムでは残り全体をデータ領域として扱う。
+#[cfg(target_os = "linux")]
+fn next_data_segment(file: &fs::File, offset: u64, len: u64) -> io::Result<Option<(u64, u64)>> {
+    let start = match seek(file, offset, libc::SEEK_DATA) {
+        Ok(start) => start,
+        Err(e) if e.raw_os_error() == Some(libc::ENXIO) => return Ok(None),
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => return Ok(Some((offset, len))),
+        Err(e) => return Err(e),
+    };
+    if start >= len {
+        return Ok(None);
+    }
+    let end = seek(file, start, libc::SEEK_HOLE).unwrap_or(len);
+    Ok(Some((start, end.min(len))))
+}
+
+#[cfg(target_os = "linux")]
+fn seek(file: &fs::File, offset: u64, whence: libc::c_int) -> io::Result<u64> {
+    use std::os::fd::AsRawFd;
+
+    let offset = libc::off_t::try_from(offset)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "offset too large"))?;
+    // SAFETY: 有効なファイル記述子に対する lseek で、メモリを操作しない。
+    let ret = unsafe { libc::lseek(file.as_raw_fd(), offset, whence) };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ret as u64)
+}
+
+#[cfg(target_os = "linux")]
+fn write_chunk(target: &fs::File, chunk: &[u8], offset: u64, punch_zeros: bool) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    if !punch_zeros {
+        return target.write_all_at(chunk, offset);
+    }
+    for (index, block) in chunk.chunks(SPARSE_BLOCK).enumerate() {
+        if block.iter().all(|byte| *byte == 0) {
+            continue;
+        }
+        target.write_all_at(block, offset + (index * SPARSE_BLOCK) as u64)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn options(reflink: ReflinkMode, sparse: SparseMode) -> CopyOptions {
+        CopyOptions {
+            reflink,
+            sparse,
+            verify: None,
+        }
+    }
+
+    #[test]
+    fn copy_file_never_reflink_copies_contents_and_permissions() {
+        // --reflink=never では複製を使わずに内容とパーミッションをコピーすることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.bin");
+        let target = temp_dir.path().join("target.bin");
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&source, &data).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&source, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        let method = copy_file(
+            fs::File::open(&source).unwrap(),
+            &target,
+            options(ReflinkMode::Never, SparseMode::Auto),
+        )
+        .unwrap()
+        .method;
+
+        assert_ne!(method, CopyMethod::Reflink);
+        assert_eq!(fs::read(&target).unwrap(), data);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&target).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+    }
+
+    #[test]
+    fn copy_file_auto_falls_back_transparently() {
+        // --reflink=auto では複製の可否にかかわらず内容が一致するコピーを作成することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.txt");
+        let target = temp_dir.path().join("target.txt");
+        fs::write(&source, "auto reflink").unwrap();
+
+        copy_file(
+            fs::File::open(&source).unwrap(),
+            &target,
+            options(ReflinkMode::Auto, SparseMode::Auto),
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "auto reflink");
+    }
+
+    #[test]
+    fn copy_file_verify_returns_source_digest() {
+        // --verify 指定時はどの複製方式でもコピー元のハッシュを返すことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.txt");
+        fs::write(&source, "verified").unwrap();
+        let expected = verify::hash_file(&source, HashAlgorithm::Blake3).unwrap();
+
+        for reflink in [ReflinkMode::Auto, ReflinkMode::Never] {
+            let target = temp_dir.path().join(format!("{reflink:?}.txt"));
+            let outcome = copy_file(
+                fs::File::open(&source).unwrap(),
+                &target,
+                CopyOptions {
+                    verify: Some(HashAlgorithm::Blake3),
+                    ..options(reflink, SparseMode::Never)
+                },
+            )
+            .unwrap();
+
+            assert_eq!(outcome.source_digest.as_ref(), Some(&expected));
+            assert_eq!(fs::read_to_string(&target).unwrap(), "verified");
+        }
+    }
+
+    #[test]
+    fn copy_file_always_reflink_clones_or_fails_cleanly() {
+        // --reflink=always は複製できた場合のみ成功し、失敗時はコピー先を残さないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.txt");
+        let target = temp_dir.path().join("target.txt");
+        fs::write(&source, "clone me").unwrap();
+
+        match copy_file(
+            fs::File::open(&source).unwrap(),
+            &target,
+            options(ReflinkMode::Always, SparseMode::Auto),
+        ) {
+            Ok(outcome) => {
+                assert_eq!(outcome.method, CopyMethod::Reflink);
+                assert_eq!(fs::read_to_string(&target).unwrap(), "clone me");
+            }
+            Err(e) => {
+                assert!(e.to_string().contains("failed to clone"));
+                assert!(!target.exists());
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod sparse_tests {
+    use super::*;
+    use std::io::{Seek, SeekFrom, Write};
+    use tempfile::TempDir;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn options(sparse: SparseMode) -> CopyOptions {
+        CopyOptions {
+            reflink: ReflinkMode::Never,
+            sparse,
+            verify: None,
+        }
+    }
+
+    /// 先頭と末尾付近にだけデータを持つ 8 MiB の穴あきファイルを作成する。
+    fn create_sparse_file(path: &Path) -> bool {
+        let mut file = fs::File::create(path).unwrap();
+        file.write_all(b"head").unwrap();
+        file.seek(SeekFrom::Start(6 * MIB)).unwrap();
+        file.write_all(b"tail").unwrap();
+        file.set_len(8 * MIB).unwrap();
+        drop(file);
+        is_sparse(&fs::metadata(path).unwrap())
+    }
+
+    #[test]
+    fn copy_file_auto_preserves_holes_of_sparse_source() {
+        // --sparse=auto で穴あきファイルの穴を保ったまま同じ内容にコピーすることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("disk.img");
+        let target = temp_dir.path().join("copy.img");
+        if !create_sparse_file(&source) {
+            return;
+        }
+
+        let outcome = copy_file(
+            fs::File::open(&source).unwrap(),
+            &target,
+            options(SparseMode::Auto),
+        )
+        .unwrap();
+
+        assert_eq!(outcome.method, CopyMethod::Sparse);
+        assert_eq!(outcome.logical_bytes, 8 * MIB);
+        assert!(outcome.physical_bytes < outcome.logical_bytes);
+        assert_eq!(fs::read(&target).unwrap(), fs::read(&source).unwrap());
+    }
+
+    #[test]
+    fn copy_file_never_writes_holes_densely() {
+        // --sparse=never では穴もゼロとして書き込み、物理サイズが論理サイズ以上になることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("disk.img");
+        let target = temp_dir.path().join("copy.img");
+        if !create_sparse_file(&source) {
+            return;
+        }
+
+        let outcome = copy_file(
+            fs::File::open(&source).unwrap(),
+            &target,
+            options(SparseMode::Never),
+        )
+        .unwrap();
+
+        assert_ne!(outcome.method, CopyMethod::Sparse);
+        assert!(outcome.physical_bytes >= outcome.logical_bytes);
+        assert_eq!(fs::read(&target).unwrap(), fs::read(&source).unwrap());
+    }
+
+    #[test]
+    fn copy_file_always_punches_zero_blocks_of_dense_source() {
+        // --sparse=always では密なファイル中のゼロ領域も穴としてコピーすることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("zeros.bin");
+        let target = temp_dir.path().join("copy.bin");
+        let mut data = vec![0u8; 4 * MIB as usize];
+        data[..4].copy_from_slice(b"data");
+        fs::write(&source, &data).unwrap();
+
+        let outcome = copy_file(
+            fs::File::open(&source).unwrap(),
+            &target,
+            options(SparseMode::Always),
+        )
+        .unwrap();
+
+        assert_eq!(outcome.method, CopyMethod::Sparse);
+        assert!(outcome.physical_bytes < outcome.logical_bytes);
+        assert_eq!(fs::read(&target).unwrap(), data);
+    }
+
+    #[test]
+    fn copy_file_verify_hashes_holes_as_zeros() {
+        // 穴を再現するコピーでも、穴をゼロとして含めたコピー元全体のハッシュを返すことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("sparse.img");
+        let target = temp_dir.path().join("copy.img");
+        create_sparse_file(&source);
+
+        let outcome = copy_file(
+            fs::File::open(&source).unwrap(),
+            &target,
+            CopyOptions {
+                verify: Some(HashAlgorithm::Sha256),
+                ..options(SparseMode::Always)
+            },
+        )
+        .unwrap();
+
+        let expected = verify::hash_file(&source, HashAlgorithm::Sha256).unwrap();
+        assert_eq!(outcome.source_digest, Some(expected.clone()));
+        assert_eq!(
+            verify::hash_file(&target, HashAlgorithm::Sha256).unwrap(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use crate::commands::cp::args::Args;
+use crate::git::IgnoreRules;
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+
+/// 再帰コピー時に配下エントリをコピー対象へ含めるかを判定する。
+///
+/// # 判定ルール
+/// 1. `--include` に一致するエントリは常にコピーする
+/// 2. `--exclude` に一致するエントリはコピーしない
+/// 3. `--respect-gitignore` 指定時は git の除外設定に一致するエントリをコピーしない
+///
+/// パターンは `.gitignore` と同じ書式で、コピー元ディレクトリからの相対パスに対して評価する。
+/// スキップしたディレクトリの配下は走査しないため、その中のエントリは `--include` でも復活しない。
+pub struct EntryFilter {
+    source_root: PathBuf,
+    canonical_root: PathBuf,
+    gitignore: Option<IgnoreRules>,
+    exclude: Gitignore,
+    include: Gitignore,
+}
+
+impl EntryFilter {
+    /// コピー元ディレクトリと引数から判定ルールを構築する。
+    pub fn new(source_root: &Path, args: &Args) -> Result<Self, String> {
+        let canonical_root = source_root
+            .canonicalize()
+            .map_err(|e| format!("cp: cannot access '{}': {}", source_root.display(), e))?;
+
+        let gitignore = if args.respect_gitignore {
+            Some(IgnoreRules::for_directory(&canonical_root).map_err(|e| {
+                format!(
+                    "cp: cannot read ignore rules for '{}': {}",
+                    source_root.display(),
+                    e
+                )
+            })?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            source_root: source_root.to_path_buf(),
+            exclude: build_patterns(&canonical_root, &args.exclude, "--exclude")?,
+            include: build_patterns(&canonical_root, &args.include, "--include")?,
+            canonical_root,
+            gitignore,
+        })
+    }
+
+    /// 配下エントリをコピー対象へ含めるかを返す。
+    pub fn should_copy(&self, entry_path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = entry_path.strip_prefix(&self.source_root) else {
+            return true;
+        };
+
+        if is_match(&self.include, relative, is_dir) {
+            return true;
+        }
+        if is_match(&self.exclude, relative, is_dir) {
+            return false;
+        }
+
+        match &self.gitignore {
+            Some(rules) => !rules.is_ignored(&self.canonical_root.join(relative), is_dir),
+            None => true,
+        }
+    }
+}
+
+/// `.gitignore` 書式のパターン列から照合器を構築する。
+fn build_patterns(root: &Path, patterns: &[String], option: &str) -> Result<Gitignore, String> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| format!("cp: invalid {option} pattern '{pattern}': {e}"))?;
+    }
+    builder
+        .build()
+        .map_err(|e| format!("cp: invalid {option} pattern: {e}"))
+}
+
+fn is_match(patterns: &Gitignore, relative: &Path, is_dir: bool) -> bool {
+    matches!(
+        patterns.matched_path_or_any_parents(relative, is_dir),
+        Match::Ignore(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn build_args(flags: &[&str]) -> Args {
+        let mut argv = vec!["cp", "-R"];
+        argv.extend_from_slice(flags);
+        argv.extend_from_slice(&["source", "target"]);
+        Args::parse_from(argv)
+    }
+
+    #[test]
+    fn should_copy_skips_gitignored_entries_only_when_requested() {
+        // --respect-gitignore 指定時のみ .gitignore の除外設定を適用することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("project");
+        fs::create_dir_all(source.join("build")).unwrap();
+        fs::write(source.join(".gitignore"), "build/\n").unwrap();
+
+        let respecting = EntryFilter::new(&source, &build_args(&["--respect-gitignore"])).unwrap();
+        let plain = EntryFilter::new(&source, &build_args(&[])).unwrap();
+
+        assert!(!respecting.should_copy(&source.join("build"), true));
+        assert!(respecting.should_copy(&source.join("main.rs"), false));
+        assert!(plain.should_copy(&source.join("build"), true));
+    }
+
+    #[test]
+    fn should_copy_applies_exclude_and_include_patterns() {
+        // --exclude で除外したエントリを --include で再度含められることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("project");
+        fs::create_dir_all(&source).unwrap();
+
+        let filter = EntryFilter::new(
+            &source,
+            &build_args(&["--exclude", "*.log", "--include", "keep.log"]),
+        )
+        .unwrap();
+
+        assert!(!filter.should_copy(&source.join("debug.log"), false));
+        assert!(!filter.should_copy(&source.join("nested").join("trace.log"), false));
+        assert!(filter.should_copy(&source.join("keep.log"), false));
+        assert!(filter.should_copy(&source.join("main.rs"), false));
+    }
+
+    #[test]
+    fn should_copy_lets_include_override_gitignore() {
+        // --include が .gitignore の除外設定より優先されることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("project");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join(".gitignore"), ".env\n").unwrap();
+
+        let filter = EntryFilter::new(
+            &source,
+            &build_args(&["--respect-gitignore", "--include", ".env"]),
+        )
+        .unwrap();
+
+        assert!(filter.should_copy(&source.join(".env"), false));
+    }
+}
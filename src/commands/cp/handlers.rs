@@ -1,34 +1,88 @@
-use crate::config::Config;
+use crate::backup::{self, BackupPolicy};
+use crate::commands::cp::args::Args;
+use crate::commands::cp::dereference::{Dereference, is_symlink_loop};
+use crate::commands::cp::file_copy::{self, CopyOptions};
+use crate::commands::cp::filter::EntryFilter;
+use crate::commands::cp::preserve::{HardlinkTracker, PreserveOptions};
+use crate::commands::cp::replace;
+use crate::commands::cp::report::{self, CopyRecord, EntryKind};
+use crate::config::{BackupScopes, Config, ScopeSet};
+use crate::dirfd::{Dir, Pinned, RenameMode};
+use crate::git::Repositories;
+use crate::limits::{self, TreeStats};
+use crate::operands::Destination;
+use crate::progress::Progress;
+use crate::update::{SkipReason, UpdateMode};
+use crate::verify::{self, Digest};
+use crate::walk::{
+    Entry, ParallelWalker, SharedVisitor, Visit, Visitor, Walker, canonicalize_link_location,
+};
+use std::ffi::OsStr;
 use std::fs;
 use std::io;
 use std::path::Component;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
 /// 既存ターゲットを安全に退避する処理を抽象化した結果型。
 type TrashResult = Result<(), String>;
 
 /// cp 実行時に必要な設定とオプションを保持するコンテキスト。
 pub struct ProcessContext {
-    pub recursive: bool,
-    pub no_clobber: bool,
+    pub args: Args,
     pub config: Config,
+    /// 実行開始時に解決した許可スコープ。再帰コピー中はこれを使い回す。
+    pub scopes: ScopeSet,
+    /// 実行中に開いた git リポジトリ。`[git] protect_dirty` の判定に使い回す。
+    pub repositories: Repositories,
+    /// 実行開始時に解決した `[[backup.scopes]]`。
+    pub backup_scopes: BackupScopes,
+    pub preserve: PreserveOptions,
+    pub dereference: Dereference,
+    pub copy_options: CopyOptions,
+    pub progress: Progress,
 }
 
 impl ProcessContext {
-    /// cp 実行に必要な情報をまとめたコンテキストを生成する。
-    pub fn new(recursive: bool, no_clobber: bool, config: Config) -> Self {
+    /// cp 実行に必要な引数と設定をまとめたコンテキストを生成する。
+    pub fn new(args: Args, config: Config) -> Self {
+        let preserve = PreserveOptions::from_args(&args);
+        let dereference = Dereference::from_args(&args);
+        let copy_options = CopyOptions::from_args(&args);
+        let progress = Progress::new("cp", args.progress);
+        let scopes = config.scope_set();
+        let repositories = Repositories::new(&config.git);
+        let backup_scopes = config.backup.resolve_scopes();
         Self {
-            recursive,
-            no_clobber,
+            args,
             config,
+            scopes,
+            repositories,
+            backup_scopes,
+            preserve,
+            dereference,
+            copy_options,
+            progress,
         }
     }
+
+    /// `target` の上書きに適用するバックアップ方針を決める。
+    pub fn backup_policy(&self, target: &Path) -> BackupPolicy {
+        BackupPolicy::resolve(
+            self.args.backup,
+            self.args.suffix.as_deref(),
+            &self.config.backup,
+            &self.backup_scopes,
+            target,
+        )
+    }
 }
 
 /// コピー対象の種別を表す。
 #[derive(Clone, Copy)]
 pub enum CopyKind {
     File,
+    Symlink,
     RecursiveDirectory,
     DirectoryWithoutRecursive,
     UnsupportedType,
@@ -40,6 +94,8 @@ pub struct CopyTask {
     pub source: PathBuf,
     pub source_label: String,
     pub final_target: PathBuf,
+    /// `--parents` 指定時に作成する中間ディレクトリの（コピー元, コピー先）の組。浅い順に並ぶ。
+    pub parent_dirs: Vec<(PathBuf, PathBuf)>,
 }
 
 /// コピー実行前にパス許可範囲と最終ターゲットを検証し、実行タスクを構築する。
@@ -47,26 +103,42 @@ pub fn validate(
     kind: CopyKind,
     source: &str,
     source_path: &Path,
-    target_path: &Path,
+    destination: &Destination,
     context: &ProcessContext,
 ) -> Result<CopyTask, String> {
-    let canonical_source = source_path
-        .canonicalize()
-        .map_err(|_| format!("cp: cannot access '{source}': Permission denied"))?;
+    let canonical_source = match kind {
+        CopyKind::Symlink => canonicalize_link_location(source_path),
+        _ => source_path.canonicalize(),
+    }
+    .map_err(|_| format!("cp: cannot access '{source}': Permission denied"))?;
 
-    if !context.config.is_path_allowed(&canonical_source) {
+    if !context.scopes.contains(&canonical_source) {
         return Err(format!(
             "cp: cannot copy '{source}': path is outside allowed scope"
         ));
     }
 
-    let final_target = if target_path.is_dir() {
-        let file_name = source_path
-            .file_name()
-            .ok_or_else(|| format!("cp: invalid source path: '{source}'"))?;
-        target_path.join(file_name)
+    let source_is_dir = matches!(
+        kind,
+        CopyKind::RecursiveDirectory | CopyKind::DirectoryWithoutRecursive
+    );
+    let (final_target, parent_dirs) = if context.args.parents {
+        let mut source_dirs: Vec<&Path> = source_path
+            .ancestors()
+            .skip(1)
+            .filter(|dir| dir.file_name().is_some())
+            .collect();
+        source_dirs.reverse();
+        let parent_dirs = source_dirs
+            .into_iter()
+            .map(|dir| Ok((dir.to_path_buf(), destination.parents_target("cp", dir)?)))
+            .collect::<Result<_, String>>()?;
+        (destination.parents_target("cp", source_path)?, parent_dirs)
     } else {
-        PathBuf::from(target_path)
+        (
+            destination.final_target("cp", source_path, source_is_dir)?,
+            Vec::new(),
+        )
     };
 
     if final_target.exists() {
@@ -77,13 +149,13 @@ pub fn validate(
             )
         })?;
 
-        if !context.config.is_path_allowed(&canonical_target) {
+        if !context.scopes.contains(&canonical_target) {
             return Err(format!(
                 "cp: cannot copy to '{}': path is outside allowed scope",
                 final_target.display()
             ));
         }
-    } else if !context.config.is_path_allowed(&final_target) {
+    } else if !context.scopes.contains(&final_target) {
         return Err(format!(
             "cp: cannot copy to '{}': path is outside allowed scope",
             final_target.display()
@@ -95,44 +167,141 @@ pub fn validate(
         source: source_path.to_path_buf(),
         source_label: source.to_string(),
         final_target,
+        parent_dirs,
     })
 }
 
-/// コピー種別に応じた実処理を行う。
+/// `--parents` の中間ディレクトリを用意してからタスクを実行する。
+///
+/// 中間ディレクトリの属性は、書き込み権限を落とす場合に備えてコピー完了後に深い順で適用する。
 pub fn execute(task: &CopyTask, context: &ProcessContext) -> Result<(), String> {
+    let created = create_parent_dirs(task, context)?;
+    copy_task(task, context)?;
+    for (source_dir, target_dir) in created.into_iter().rev() {
+        preserve_attributes(
+            &Source::operand(source_dir),
+            target_dir,
+            target_dir,
+            true,
+            context,
+        )?;
+    }
+    Ok(())
+}
+
+/// `--parents` 指定時、最終ターゲットまでの中間ディレクトリを作成し、新しく作成した組を返す。
+///
+/// 各ディレクトリは許可範囲内であることを確認し、開いた親ディレクトリ直下へ作成する。
+/// 既存のディレクトリはそのまま使い、ディレクトリ以外（シンボリックリンクを含む）はエラーにする。
+fn create_parent_dirs<'a>(
+    task: &'a CopyTask,
+    context: &ProcessContext,
+) -> Result<Vec<(&'a Path, &'a Path)>, String> {
+    if matches!(
+        task.kind,
+        CopyKind::DirectoryWithoutRecursive | CopyKind::UnsupportedType
+    ) {
+        return Ok(Vec::new());
+    }
+    let mut created = Vec::new();
+    for (source_dir, target_dir) in &task.parent_dirs {
+        let create_error = |reason: &dyn std::fmt::Display| {
+            format!(
+                "cp: cannot create directory '{}': {}",
+                target_dir.display(),
+                reason
+            )
+        };
+        if !context.scopes.contains(target_dir) {
+            return Err(create_error(&"path is outside allowed scope"));
+        }
+        let pinned = context
+            .scopes
+            .open_parent(target_dir)
+            .map_err(|e| create_error(&e))?;
+        match pinned.dir.create_dir(&pinned.name, 0o777) {
+            Ok(()) => {
+                report::emit(
+                    &context.args,
+                    CopyRecord::new(EntryKind::Directory, source_dir, target_dir),
+                );
+                created.push((source_dir.as_path(), target_dir.as_path()));
+            }
+            Err(e)
+                if e.kind() == io::ErrorKind::AlreadyExists
+                    && pinned
+                        .dir
+                        .symlink_metadata(&pinned.name)
+                        .is_ok_and(|meta| meta.is_dir()) => {}
+            Err(e) => return Err(create_error(&e)),
+        }
+    }
+    Ok(created)
+}
+
+/// コピー種別に応じた実処理を行う。
+fn copy_task(task: &CopyTask, context: &ProcessContext) -> Result<(), String> {
     match task.kind {
         CopyKind::File => {
-            if task.final_target.exists() {
-                if context.no_clobber && task.final_target.is_file() {
-                    return Ok(());
-                }
-                if !context.no_clobber {
-                    move_existing_file_to_trash(&task.final_target)?;
-                }
+            let pinned = pin_target(&task.final_target, &context.scopes)?;
+            let source = Source::operand(&task.source);
+            if !prepare_entry_target(
+                &source,
+                EntryKind::File,
+                &pinned,
+                &task.final_target,
+                context,
+            )? {
+                return Ok(());
             }
+            ensure_task_within_limits(task, &pinned, false, context)?;
 
-            fs::copy(&task.source, &task.final_target)
-                .map(|_| ())
-                .map_err(|e| {
-                    format!(
-                        "cp: cannot copy '{}' to '{}': {}",
-                        task.source_label,
-                        task.final_target.display(),
-                        e
-                    )
-                })
+            if !copy_regular_file(&source, &pinned, &task.final_target, context)? {
+                return Ok(());
+            }
+            report_top_level_progress(&task.source, fs::metadata(&task.source), context);
+            Ok(())
+        }
+        CopyKind::Symlink => {
+            ensure_target_path_allowed_for_write(&task.final_target, &context.scopes)?;
+            let pinned = pin_target(&task.final_target, &context.scopes)?;
+            let source = Source::operand(&task.source);
+            if !prepare_entry_target(
+                &source,
+                EntryKind::Symlink,
+                &pinned,
+                &task.final_target,
+                context,
+            )? {
+                return Ok(());
+            }
+            ensure_task_within_limits(task, &pinned, false, context)?;
+
+            copy_link(&source, &pinned, &task.final_target, context)?;
+            report_top_level_progress(&task.source, fs::symlink_metadata(&task.source), context);
+            Ok(())
         }
         CopyKind::RecursiveDirectory => {
-            if task.final_target.exists() && !context.no_clobber {
-                move_existing_file_to_trash(&task.final_target)?;
+            let pinned = pin_target(&task.final_target, &context.scopes)?;
+            // --update 指定時は既存ディレクトリを退避せず、配下のファイルごとに置き換えを判定する。
+            let updates_in_place = context.args.update != UpdateMode::All
+                && pinned
+                    .dir
+                    .symlink_metadata(&pinned.name)
+                    .is_ok_and(|meta| meta.is_dir());
+            if task.final_target.exists() && !context.args.no_clobber && !updates_in_place {
+                context.repositories.ensure_clean(
+                    "cp",
+                    &task.final_target,
+                    context.args.allow_dirty,
+                )?;
+                ensure_task_within_limits(task, &pinned, false, context)?;
+                retire_existing_target(&pinned, &task.final_target, context)?;
+            } else if updates_in_place {
+                ensure_task_within_limits(task, &pinned, true, context)?;
             }
 
-            copy_dir_recursive(
-                &task.source,
-                &task.final_target,
-                &context.config,
-                context.no_clobber,
-            )
+            copy_dir_recursive(&task.source, &task.final_target, pinned, context)
         }
         CopyKind::DirectoryWithoutRecursive => {
             Err(format!("cp: omitting directory '{}'", task.source_label))
@@ -144,91 +313,963 @@ pub fn execute(task: &CopyTask, context: &ProcessContext) -> Result<(), String>
     }
 }
 
+/// `--progress` の総量として、タスクがコピーするファイル数とバイト数を事前走査する。
+///
+/// 走査できなかった部分は総量に含めない。
+pub fn prescan(task: &CopyTask, context: &ProcessContext) -> TreeStats {
+    match task.kind {
+        CopyKind::File => single_entry_stats(fs::metadata(&task.source)),
+        CopyKind::Symlink => single_entry_stats(fs::symlink_metadata(&task.source)),
+        CopyKind::RecursiveDirectory => {
+            let Ok(filter) = EntryFilter::new(&task.source, &context.args) else {
+                return TreeStats::default();
+            };
+            let mut scan = TreeScan {
+                filter,
+                stats: TreeStats::default(),
+            };
+            let _ = Walker::new(&task.source)
+                .follow_root(true)
+                .follow_links(context.dereference.follows_in_tree())
+                .run(&mut scan);
+            scan.stats
+        }
+        CopyKind::DirectoryWithoutRecursive | CopyKind::UnsupportedType => TreeStats::default(),
+    }
+}
+
+fn single_entry_stats(meta: io::Result<fs::Metadata>) -> TreeStats {
+    meta.map(|meta| TreeStats {
+        files: 1,
+        bytes: meta.len(),
+    })
+    .unwrap_or_default()
+}
+
+/// `[limits]` 設定時、タスクが上書きで退避する既存エントリをコピー前に1回だけ走査し、
+/// 合計が上限内かを検証する。
+///
+/// `merges` が `true`（`--update` で既存ディレクトリへ書き込む再帰コピー）の場合は、
+/// コピー元を同じ絞り込みで走査し、置き換えられる既存エントリだけを合計する。
+/// それ以外は既存ターゲット全体が退避されるため、存在すればそれを走査する。
+fn ensure_task_within_limits(
+    task: &CopyTask,
+    pinned: &Pinned,
+    merges: bool,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    let limits_config = &context.config.limits;
+    if !limits_config.is_configured() {
+        return Ok(());
+    }
+    let confirmed = context.args.yes_i_mean_it;
+    if !merges {
+        if !entry_slot_is_occupied(&pinned.dir, &pinned.name) {
+            return Ok(());
+        }
+        return limits::ensure_within("cp", &task.final_target, limits_config, confirmed);
+    }
+
+    let mut scan = OverwriteScan {
+        filter: EntryFilter::new(&task.source, &context.args)?,
+        target_root: &task.final_target,
+        update: context.args.update,
+        stats: TreeStats::default(),
+    };
+    Walker::new(&task.source)
+        .follow_root(true)
+        .follow_links(context.dereference.follows_in_tree())
+        .run(&mut scan)
+        .map_err(|e| format!("cp: cannot scan '{}': {}", task.final_target.display(), e))?;
+    limits::ensure_stats_within(
+        "cp",
+        &task.final_target,
+        limits_config,
+        &scan.stats,
+        confirmed,
+    )
+}
+
+/// 既存ディレクトリへの再帰コピーで、置き換えられる既存エントリを合計するビジター。
+///
+/// `--update` の条件でスキップされるエントリは数えない。
+struct OverwriteScan<'a> {
+    filter: EntryFilter,
+    target_root: &'a Path,
+    update: UpdateMode,
+    stats: TreeStats,
+}
+
+impl Visitor for OverwriteScan<'_> {
+    type Error = io::Error;
+
+    fn enter_dir(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+        if !entry.is_root() && !self.filter.should_copy(entry.path, true) {
+            return Ok(Visit::Skip);
+        }
+        if entry.reenters_ancestor() {
+            return Ok(Visit::Skip);
+        }
+        Ok(Visit::Continue)
+    }
+
+    fn visit(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+        let file_type = entry.metadata.file_type();
+        if !self.filter.should_copy(entry.path, false)
+            || !(file_type.is_file() || file_type.is_symlink())
+        {
+            return Ok(Visit::Skip);
+        }
+        let target = self.target_root.join(entry.relative);
+        let target_meta = match fs::symlink_metadata(&target) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Visit::Continue),
+            Err(e) => return Err(e),
+        };
+        if self
+            .update
+            .skip_reason(&entry.pinned_path(), entry.metadata, &target, &target_meta)?
+            .is_none()
+        {
+            self.stats += limits::scan(&target)?;
+        }
+        Ok(Visit::Continue)
+    }
+
+    fn walk_error(&mut self, _path: &Path, err: io::Error) -> io::Error {
+        err
+    }
+}
+
+/// 再帰コピーと同じ絞り込みで、コピー対象となるエントリを数えるビジター。
+struct TreeScan {
+    filter: EntryFilter,
+    stats: TreeStats,
+}
+
+impl Visitor for TreeScan {
+    type Error = io::Error;
+
+    fn enter_dir(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+        if !entry.is_root() && !self.filter.should_copy(entry.path, true) {
+            return Ok(Visit::Skip);
+        }
+        if entry.reenters_ancestor() {
+            return Ok(Visit::Skip);
+        }
+        Ok(Visit::Continue)
+    }
+
+    fn visit(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+        let file_type = entry.metadata.file_type();
+        if !self.filter.should_copy(entry.path, false)
+            || !(file_type.is_file() || file_type.is_symlink())
+        {
+            return Ok(Visit::Skip);
+        }
+        self.stats.files += 1;
+        self.stats.bytes += entry.metadata.len();
+        Ok(Visit::Continue)
+    }
+
+    fn walk_error(&mut self, _path: &Path, err: io::Error) -> io::Error {
+        err
+    }
+}
+
+/// 再帰コピー以外でコピーしたエントリを進捗へ報告する。
+fn report_top_level_progress(
+    source: &Path,
+    meta: io::Result<fs::Metadata>,
+    context: &ProcessContext,
+) {
+    if let Ok(meta) = meta {
+        context.progress.advance(source, meta.len());
+    }
+}
+
 /// ディレクトリを再帰的に走査し、配下を同構造でコピーする。
+///
+/// `-L` 以外では配下のシンボリックリンクをリンクとして複製し、
+/// ディレクトリ自身の属性は配下のコピー完了後に適用する。
+/// `--jobs` が2以上の場合は並列走査でコピーし、発生したエラーをすべてパス順に報告する。
+/// コピー先は `target` の親として開いた `pinned` から辿り、パスを解決し直さない。
 fn copy_dir_recursive(
     source: &Path,
     target: &Path,
-    config: &Config,
-    no_clobber: bool,
+    pinned: Pinned,
+    context: &ProcessContext,
 ) -> Result<(), String> {
-    ensure_target_path_allowed_for_write(target, config)?;
-
-    fs::create_dir_all(target)
-        .map_err(|e| format!("cp: cannot create directory '{}': {}", target.display(), e))?;
+    let mut tree = TreeCopy {
+        context,
+        target_root: target,
+        target_parent: pinned,
+        root_dir: OnceLock::new(),
+        filter: EntryFilter::new(source, &context.args)?,
+        links: Mutex::new(HardlinkTracker::default()),
+    };
+    let follow_links = context.dereference.follows_in_tree();
+
+    let jobs = context.args.jobs.get();
+    if jobs > 1 {
+        return ParallelWalker::new(source, jobs)
+            .follow_root(true)
+            .follow_links(follow_links)
+            .progress(&context.progress)
+            .run(&tree)
+            .map_err(|errors| errors.join("\n"));
+    }
 
-    let entries = fs::read_dir(source)
-        .map_err(|e| format!("cp: cannot read directory '{}': {}", source.display(), e))?;
+    Walker::new(source)
+        .follow_root(true)
+        .follow_links(follow_links)
+        .progress(&context.progress)
+        .run(&mut tree)
+}
 
-    for entry in entries {
-        let entry = entry
-            .map_err(|e| format!("cp: error reading directory '{}': {}", source.display(), e))?;
+/// 再帰コピー1回分の状態を保持し、走査したエントリをコピー先へ複製する。
+///
+/// 逐次走査と並列走査の両方から使うため、状態は共有参照から更新できるようにする。
+/// コピー先のエントリは、作成したルートディレクトリのディスクリプタからシンボリックリンクを
+/// 辿らずに開いた親ディレクトリを基準に作成する。
+struct TreeCopy<'a> {
+    context: &'a ProcessContext,
+    target_root: &'a Path,
+    /// コピー先ルートの親ディレクトリとルートのエントリ名。
+    target_parent: Pinned,
+    /// 作成したコピー先ルートディレクトリ。ルートの `enter_dir` で設定する。
+    root_dir: OnceLock<Dir>,
+    filter: EntryFilter,
+    links: Mutex<HardlinkTracker>,
+}
 
-        let entry_path = entry.path();
-        let file_name = entry.file_name();
-        let target_path = target.join(&file_name);
+impl TreeCopy<'_> {
+    fn target_for(&self, entry: &Entry<'_>) -> PathBuf {
+        if entry.is_root() {
+            self.target_root.to_path_buf()
+        } else {
+            self.target_root.join(entry.relative)
+        }
+    }
 
-        let canonical_entry = entry_path.canonicalize().map_err(|_| {
+    /// エントリのコピー先の親ディレクトリを、コピー先ルートから開く。
+    fn pin(&self, entry: &Entry<'_>) -> Result<Pinned, String> {
+        let pin_error = |e: io::Error| {
             format!(
-                "cp: cannot access '{}': Permission denied",
-                entry_path.display()
+                "cp: cannot copy to '{}': {}",
+                self.target_for(entry).display(),
+                e
             )
-        })?;
+        };
+        if entry.is_root() {
+            return self.target_parent.try_clone().map_err(pin_error);
+        }
+        let (Some(root), Some(name)) = (self.root_dir.get(), entry.relative.file_name()) else {
+            return Err(pin_error(io::Error::from(io::ErrorKind::NotFound)));
+        };
+        let parent = entry.relative.parent().unwrap_or(Path::new(""));
+        let dir = root.open_beneath(parent).map_err(pin_error)?;
+        Ok(Pinned {
+            dir,
+            name: name.to_os_string(),
+        })
+    }
+
+    /// 走査中のエントリをコピー元として、走査で開いた親ディレクトリから辿る。
+    fn source<'e>(&self, entry: &Entry<'e>) -> Source<'e> {
+        Source {
+            path: entry.path,
+            read_path: entry.pinned_path(),
+            parent: entry.parent,
+            follow: self.context.dereference.follows_in_tree(),
+        }
+    }
 
-        if !config.is_path_allowed(&canonical_entry) {
+    /// 走査で解決済みの位置（リンクとして複製する場合はリンク自身、辿った場合はリンク先）を
+    /// 許可範囲チェックの対象とする。
+    fn ensure_entry_allowed(&self, entry: &Entry<'_>) -> Result<(), String> {
+        if !self.context.scopes.contains(entry.resolved) {
             return Err(format!(
                 "cp: cannot copy '{}': path is outside allowed scope",
-                entry_path.display()
+                entry.path.display()
             ));
         }
+        Ok(())
+    }
+
+    /// ディレクトリをコピー対象に含めるかを判定し、含める場合は許可範囲を検証する。
+    fn admit_dir(&self, entry: &Entry<'_>) -> Result<Visit, String> {
+        if !entry.is_root() {
+            if !self.filter.should_copy(entry.path, true) {
+                return Ok(Visit::Skip);
+            }
+            self.ensure_entry_allowed(entry)?;
+        }
+        ensure_target_path_allowed_for_write(&self.target_for(entry), &self.context.scopes)?;
+        Ok(Visit::Continue)
+    }
+
+    /// コピー先ディレクトリを作成する。既存のディレクトリはそのまま使う。
+    fn create_dir(&self, entry: &Entry<'_>) -> Result<(), String> {
+        let target = self.target_for(entry);
+        let create_error =
+            |e: io::Error| format!("cp: cannot create directory '{}': {}", target.display(), e);
+        let pinned = self.pin(entry)?;
+        match pinned.dir.create_dir(&pinned.name, 0o777) {
+            Ok(()) => {}
+            Err(e)
+                if e.kind() == io::ErrorKind::AlreadyExists
+                    && pinned
+                        .dir
+                        .symlink_metadata(&pinned.name)
+                        .is_ok_and(|meta| meta.is_dir()) => {}
+            Err(e) => return Err(create_error(e)),
+        }
+        if entry.is_root() {
+            let root = pinned.dir.open_dir(&pinned.name).map_err(create_error)?;
+            let _ = self.root_dir.set(root);
+        }
+        report::emit(
+            &self.context.args,
+            CopyRecord::new(EntryKind::Directory, entry.path, &target),
+        );
+        Ok(())
+    }
+
+    /// 配下のコピー完了後、ディレクトリ自身の属性を適用する。
+    fn finish_dir(&self, entry: &Entry<'_>) -> Result<(), String> {
+        if self.context.preserve == PreserveOptions::default() {
+            return Ok(());
+        }
+        let pinned = self.pin(entry)?;
+        preserve_attributes(
+            &self.source(entry),
+            &pinned.entry_path(),
+            &self.target_for(entry),
+            true,
+            self.context,
+        )
+    }
+
+    /// ディレクトリ以外のエントリをコピーする。
+    fn copy_entry(&self, entry: &Entry<'_>) -> Result<Visit, String> {
+        // リンクを辿らない走査ではメタデータがリンク自身のものとなる。
+        let copies_as_link = entry.metadata.file_type().is_symlink();
+        if !self.filter.should_copy(entry.path, false) {
+            return Ok(Visit::Skip);
+        }
+        self.ensure_entry_allowed(entry)?;
+        if !copies_as_link && !entry.metadata.is_file() {
+            return Ok(Visit::Skip);
+        }
+
+        let target_path = self.target_for(entry);
+        ensure_target_path_allowed_for_write(&target_path, &self.context.scopes)?;
+        let pinned = self.pin(entry)?;
+        let kind = if copies_as_link {
+            EntryKind::Symlink
+        } else {
+            EntryKind::File
+        };
+        let source = self.source(entry);
+        if !prepare_entry_target(&source, kind, &pinned, &target_path, self.context)? {
+            return Ok(Visit::Skip);
+        }
+
+        if copies_as_link {
+            copy_link(&source, &pinned, &target_path, self.context)?;
+        } else {
+            self.copy_file_entry(&source, entry.metadata, &pinned, &target_path)?;
+        }
+        Ok(Visit::Continue)
+    }
+
+    /// 通常ファイルをコピーする。`--preserve=links` では同一 inode の2件目以降をハードリンクにする。
+    fn copy_file_entry(
+        &self,
+        source: &Source<'_>,
+        metadata: &fs::Metadata,
+        pinned: &Pinned,
+        target_path: &Path,
+    ) -> Result<(), String> {
+        let context = self.context;
+        if !context.preserve.links || !HardlinkTracker::tracks(metadata) {
+            return copy_regular_file(source, pinned, target_path, context).map(|_| ());
+        }
 
-        if entry_path.is_file() {
-            ensure_target_path_allowed_for_write(&target_path, config)?;
-
-            if target_path.exists() {
-                if no_clobber {
-                    if target_path.is_file() {
-                        continue;
-                    }
-                    return Err(format!(
-                        "cp: cannot copy '{}' to '{}': destination is not a file",
-                        entry_path.display(),
-                        target_path.display()
-                    ));
-                }
-                move_existing_file_to_trash(&target_path)?;
+        // 同じ inode の初回コピーが並行して複数回行われないよう、記録と複製を同じロック内で行う。
+        let mut links = self
+            .links
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(first_copy) = links.copied_path(metadata) {
+            let linked = install_entry(pinned, target_path, context, |dest| {
+                fs::hard_link(first_copy, dest).map_err(|e| {
+                    format!(
+                        "cp: cannot create hard link '{}': {}",
+                        target_path.display(),
+                        e
+                    )
+                })
+            })?;
+            if linked.is_none() {
+                return Ok(());
             }
+            report::emit(
+                &context.args,
+                CopyRecord::new(EntryKind::Hardlink, source.path, target_path),
+            );
+            return Ok(());
+        }
+        if copy_regular_file(source, pinned, target_path, context)? {
+            links.record(metadata, target_path);
+        }
+        Ok(())
+    }
+}
+
+impl SharedVisitor for TreeCopy<'_> {
+    type Error = String;
+
+    fn enter_dir(&self, entry: &Entry<'_>) -> Result<Visit, String> {
+        if self.admit_dir(entry)? == Visit::Skip {
+            return Ok(Visit::Skip);
+        }
+        if entry.reenters_ancestor() {
+            return Err(cycle_error(entry.path));
+        }
+        self.create_dir(entry)?;
+        Ok(Visit::Continue)
+    }
+
+    fn leave_dir(&self, entry: &Entry<'_>) -> Result<(), String> {
+        self.finish_dir(entry)
+    }
+
+    fn visit(&self, entry: &Entry<'_>) -> Result<Visit, String> {
+        self.copy_entry(entry)
+    }
+
+    fn walk_error(&self, path: &Path, err: io::Error) -> String {
+        entry_access_error(path, &err)
+    }
+}
+
+/// 逐次走査では、並列走査と同じ処理を1スレッドで行う。
+impl Visitor for TreeCopy<'_> {
+    type Error = String;
+
+    fn enter_dir(&mut self, entry: &Entry<'_>) -> Result<Visit, String> {
+        SharedVisitor::enter_dir(self, entry)
+    }
+
+    fn leave_dir(&mut self, entry: &Entry<'_>) -> Result<(), String> {
+        SharedVisitor::leave_dir(self, entry)
+    }
+
+    fn visit(&mut self, entry: &Entry<'_>) -> Result<Visit, String> {
+        SharedVisitor::visit(self, entry)
+    }
+
+    fn walk_error(&mut self, path: &Path, err: io::Error) -> String {
+        SharedVisitor::walk_error(self, path, err)
+    }
+}
+
+/// コピー元のエントリ。
+///
+/// 再帰コピーの配下は走査中に開いた親ディレクトリから辿り、途中のパス要素を解決し直さない。
+/// トップレベルのオペランドはコマンドラインのパスをそのまま使う。
+struct Source<'a> {
+    /// メッセージや出力に使うパス。
+    path: &'a Path,
+    /// 内容や属性の読み込みに使うパス。配下のエントリではディスクリプタ経由のパスとなる。
+    read_path: PathBuf,
+    /// 配下のエントリを含む、走査中に開いた親ディレクトリ。
+    parent: Option<&'a Dir>,
+    /// 配下のシンボリックリンクを辿って内容を読むか（`-L`）。
+    follow: bool,
+}
+
+impl<'a> Source<'a> {
+    /// コマンドラインのオペランドをコピー元とする。
+    fn operand(path: &'a Path) -> Self {
+        Self {
+            path,
+            read_path: path.to_path_buf(),
+            parent: None,
+            follow: true,
+        }
+    }
 
-            fs::copy(&entry_path, &target_path).map_err(|e| {
+    /// 通常ファイルを読み込み用に開く。配下のエントリは `-L` 以外ではリンクを辿らずに開く。
+    fn open_file(&self) -> io::Result<fs::File> {
+        match (self.parent, self.path.file_name()) {
+            (Some(parent), Some(name)) if !self.follow => parent.open_file(name),
+            _ => fs::File::open(&self.read_path),
+        }
+    }
+}
+
+/// 通常ファイルの内容をコピーし、属性の保持と結果の出力を行う。
+///
+/// `-l`/`-s` 指定時は内容をコピーせずリンクを作成する。
+/// `-n` の配置時に既存ターゲットが現れてスキップした場合は `false` を返す。
+fn copy_regular_file(
+    source: &Source<'_>,
+    pinned: &Pinned,
+    target: &Path,
+    context: &ProcessContext,
+) -> Result<bool, String> {
+    if context.args.link || context.args.symbolic_link {
+        return link_regular_file(source, pinned, target, context);
+    }
+    let installed = install_entry(pinned, target, context, |dest| {
+        let copy_error = |e: io::Error| {
+            format!(
+                "cp: cannot copy '{}' to '{}': {}",
+                source.path.display(),
+                target.display(),
+                e
+            )
+        };
+        let source_file = source.open_file().map_err(copy_error)?;
+        let outcome =
+            file_copy::copy_file(source_file, dest, context.copy_options).map_err(copy_error)?;
+        preserve_attributes(source, dest, target, true, context)?;
+        if let Some(expected) = &outcome.source_digest {
+            verify_copied_file(source.path, &pinned.dir, dest, target, expected)?;
+        }
+        Ok(outcome)
+    })?;
+    let Some(outcome) = installed else {
+        return Ok(false);
+    };
+    report::emit(
+        &context.args,
+        CopyRecord::new(EntryKind::File, source.path, target)
+            .with_sizes(outcome.logical_bytes, outcome.physical_bytes)
+            .with_checksum(outcome.source_digest),
+    );
+    Ok(true)
+}
+
+/// `-l` ではハードリンク、`-s` ではシンボリックリンクをコピーの代わりに作成する。
+///
+/// 既存ターゲットの置き換え・`-n`・バックアップは通常のコピーと同じく `install_entry` で扱う。
+/// ハードリンクはコピー元がシンボリックリンクならリンク先の実体へ作成する。
+/// シンボリックリンクはコピー元の絶対パスを指し、その実体が許可範囲外なら作成しない。
+fn link_regular_file(
+    source: &Source<'_>,
+    pinned: &Pinned,
+    target: &Path,
+    context: &ProcessContext,
+) -> Result<bool, String> {
+    let (kind, installed) = if context.args.symbolic_link {
+        let link_target = symlink_target_within_scope(source.path, target, context)?;
+        let installed = install_entry(pinned, target, context, |dest| {
+            create_symlink(&link_target, dest).map_err(|e| {
                 format!(
-                    "cp: cannot copy '{}' to '{}': {}",
-                    entry_path.display(),
-                    target_path.display(),
+                    "cp: cannot create symbolic link '{}': {}",
+                    target.display(),
                     e
                 )
-            })?;
-        } else if entry_path.is_dir() {
-            ensure_target_path_allowed_for_write(&target_path, config)?;
-            copy_dir_recursive(&entry_path, &target_path, config, no_clobber)?;
+            })
+        })?;
+        (EntryKind::Symlink, installed)
+    } else {
+        let link_error = |e: io::Error| {
+            format!(
+                "cp: cannot create hard link '{}' to '{}': {}",
+                target.display(),
+                source.path.display(),
+                e
+            )
+        };
+        let original = source.read_path.canonicalize().map_err(link_error)?;
+        let installed = install_entry(pinned, target, context, |dest| {
+            fs::hard_link(&original, dest).map_err(link_error)
+        })?;
+        (EntryKind::Hardlink, installed)
+    };
+    if installed.is_none() {
+        return Ok(false);
+    }
+    report::emit(&context.args, CopyRecord::new(kind, source.path, target));
+    Ok(true)
+}
+
+/// `-s` で作成するリンクの内容として、コピー元の絶対パスを返す。
+///
+/// リンクの置き場所だけでなく、リンクを辿った先の実体も許可範囲内であることを確認する。
+fn symlink_target_within_scope(
+    source: &Path,
+    target: &Path,
+    context: &ProcessContext,
+) -> Result<PathBuf, String> {
+    let link_target = std::path::absolute(source)
+        .map_err(|e| format!("cp: cannot access '{}': {}", source.display(), e))?;
+    let resolved = link_target
+        .canonicalize()
+        .map_err(|e| format!("cp: cannot access '{}': {}", source.display(), e))?;
+    if !context.scopes.contains(&resolved) {
+        return Err(format!(
+            "cp: cannot create symbolic link '{}' to '{}': link target is outside allowed scope",
+            target.display(),
+            link_target.display()
+        ));
+    }
+    Ok(link_target)
+}
+
+/// `--verify` 指定時、fsync 済みのコピーを読み直してコピー元のハッシュと照合する。
+///
+/// 一致しない場合は `dir` 直下の不正なコピーをゴミ箱へ移動し、当該項目を失敗とする。
+fn verify_copied_file(
+    source: &Path,
+    dir: &Dir,
+    copy: &Path,
+    target: &Path,
+    expected: &Digest,
+) -> Result<(), String> {
+    let Err(e) = verify::verify_file(copy, expected) else {
+        return Ok(());
+    };
+    let message = format!(
+        "cp: verification of '{}' to '{}' failed: {}",
+        source.display(),
+        target.display(),
+        e
+    );
+    let trashed = match copy.file_name() {
+        Some(name) => trash_entry(dir, name),
+        None => move_existing_file_to_trash(copy),
+    };
+    match trashed {
+        Ok(()) => Err(message),
+        Err(trash_error) => Err(format!("{message}; {trash_error}")),
+    }
+}
+
+/// シンボリックリンクをリンクとして複製し、属性の保持と結果の出力を行う。
+fn copy_link(
+    source: &Source<'_>,
+    pinned: &Pinned,
+    target: &Path,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    let installed = install_entry(pinned, target, context, |dest| {
+        copy_symlink_entry(&source.read_path, dest).map_err(|e| {
+            format!(
+                "cp: cannot create symbolic link '{}': {}",
+                target.display(),
+                e
+            )
+        })?;
+        preserve_attributes(source, dest, target, false, context)
+    })?;
+    if installed.is_none() {
+        return Ok(());
+    }
+    report::emit(
+        &context.args,
+        CopyRecord::new(EntryKind::Symlink, source.path, target),
+    );
+    Ok(())
+}
+
+/// 開いた親ディレクトリ `pinned` の直下へエントリを作成し、配置した場合は `Some` を返す。
+///
+/// `create` にはディスクリプタ経由で作成先を指すパスを渡し、`target` はメッセージに使う。
+/// 既存ターゲットがある場合は同じディレクトリの隠し一時エントリへ作成して fsync し、
+/// rename で置き換えた後に古いターゲットをゴミ箱へ移動する。
+/// 作成に失敗しても既存ターゲットはそのまま残る。
+/// バックアップ方針が有効なら、古いターゲットはゴミ箱の代わりにバックアップとして残す。
+/// `-n` では `place_without_replacing` で配置し、既存エントリがあれば `None` を返す。
+fn install_entry<T, F>(
+    pinned: &Pinned,
+    target: &Path,
+    context: &ProcessContext,
+    create: F,
+) -> Result<Option<T>, String>
+where
+    F: FnOnce(&Path) -> Result<T, String>,
+{
+    let dir = &pinned.dir;
+    if context.args.no_clobber {
+        return place_without_replacing(pinned, target, create);
+    }
+    if !entry_slot_is_occupied(dir, &pinned.name) {
+        return create(&pinned.entry_path()).map(Some);
+    }
+
+    let replace_error = |e: io::Error| format!("cp: cannot replace '{}': {}", target.display(), e);
+    let staged = replace::sibling_temp_name(dir, &pinned.name).map_err(replace_error)?;
+    let value = create_staged(dir, &staged, create, replace_error)?;
+    let policy = context.backup_policy(target);
+    if policy.makes_backup() {
+        replace::swap_in_with_backup(
+            dir,
+            &staged,
+            &pinned.name,
+            target,
+            &policy,
+            &context.scopes,
+            move_existing_file_to_trash,
+        )?;
+        return Ok(Some(value));
+    }
+    replace::swap_in(
+        dir,
+        &staged,
+        &pinned.name,
+        target,
+        move_existing_file_to_trash,
+    )?;
+    Ok(Some(value))
+}
+
+/// `-n` 指定時、一時エントリへ作成してから `RENAME_NOREPLACE` で配置する。
+///
+/// 事前の確認後に別プロセスが作成したターゲットも上書きせず、
+/// その場合は一時エントリを削除して `None` を返す。
+fn place_without_replacing<T, F>(
+    pinned: &Pinned,
+    target: &Path,
+    create: F,
+) -> Result<Option<T>, String>
+where
+    F: FnOnce(&Path) -> Result<T, String>,
+{
+    let dir = &pinned.dir;
+    let create_error = |e: io::Error| format!("cp: cannot create '{}': {}", target.display(), e);
+    let staged = replace::sibling_temp_name(dir, &pinned.name).map_err(create_error)?;
+    let value = create_staged(dir, &staged, create, create_error)?;
+    match dir.rename(&staged, dir, &pinned.name, RenameMode::NoReplace) {
+        Ok(()) => Ok(Some(value)),
+        Err(e) => {
+            replace::remove_staged(dir, &staged);
+            if e.kind() == io::ErrorKind::AlreadyExists {
+                Ok(None)
+            } else {
+                Err(create_error(e))
+            }
         }
     }
+}
 
+/// `dir` 直下の一時エントリ `staged` へ作成して fsync する。失敗時は一時エントリを削除する。
+fn create_staged<T, F, E>(dir: &Dir, staged: &OsStr, create: F, io_error: E) -> Result<T, String>
+where
+    F: FnOnce(&Path) -> Result<T, String>,
+    E: Fn(io::Error) -> String,
+{
+    let created = create(&dir.entry_path(staged)).and_then(|value| {
+        replace::sync_entry(dir, staged)
+            .map(|()| value)
+            .map_err(io_error)
+    });
+    if created.is_err() {
+        replace::remove_staged(dir, staged);
+    }
+    created
+}
+
+/// 再帰コピーで置き換える既存ターゲットを、バックアップ方針に従って退避する。
+///
+/// バックアップが有効なら同じディレクトリのバックアップ名へ移し、無効ならゴミ箱へ移動する。
+fn retire_existing_target(
+    pinned: &Pinned,
+    target: &Path,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    let policy = context.backup_policy(target);
+    if !policy.makes_backup() {
+        return trash_entry(&pinned.dir, &pinned.name);
+    }
+    let backup_error = |e: io::Error| format!("cp: cannot back up '{}': {}", target.display(), e);
+    let backup = backup::back_up(
+        &pinned.dir,
+        &pinned.name,
+        &policy,
+        &context.scopes,
+        |path| move_existing_file_to_trash(path).map_err(io::Error::other),
+    )
+    .map_err(backup_error)?;
+    if backup.linked {
+        pinned.dir.remove_file(&pinned.name).map_err(backup_error)?;
+    }
     Ok(())
 }
 
+/// 既存ターゲットを置き換えてよいかを検査し、コピーを続行すべきかを返す。
+///
+/// `-n` 指定時に既存通常ファイルがあれば `false` を返してスキップさせる。
+/// `--update` の条件でスキップする場合も `false` を返し、スキップを `-v`/`--json` へ出力する。
+/// `[limits]` の検査はタスク単位で `ensure_task_within_limits` が行う。
+/// `kind` はコピー方法で、`File` ならコピー元のリンクを辿ったメタデータで比較する。
+/// 既存ターゲットのゴミ箱への移動は、新しい内容の配置後に `install_entry` が行う。
+fn prepare_entry_target(
+    source: &Source<'_>,
+    kind: EntryKind,
+    pinned: &Pinned,
+    target_path: &Path,
+    context: &ProcessContext,
+) -> Result<bool, String> {
+    if !entry_slot_is_occupied(&pinned.dir, &pinned.name) {
+        return Ok(true);
+    }
+
+    if context.args.no_clobber {
+        if pinned.entry_path().is_file() {
+            return Ok(false);
+        }
+        return Err(format!(
+            "cp: cannot copy '{}' to '{}': destination is not a file",
+            source.path.display(),
+            target_path.display()
+        ));
+    }
+    if let Some(reason) = update_skip_reason(source, kind, pinned, target_path, context)? {
+        report::emit(
+            &context.args,
+            CopyRecord::new(kind, source.path, target_path).with_skip(reason),
+        );
+        return Ok(false);
+    }
+    context
+        .repositories
+        .ensure_clean("cp", target_path, context.args.allow_dirty)?;
+    Ok(true)
+}
+
+/// `--update` の条件で既存ターゲットの置き換えをスキップするかを判定し、その理由を返す。
+fn update_skip_reason(
+    source: &Source<'_>,
+    kind: EntryKind,
+    pinned: &Pinned,
+    target: &Path,
+    context: &ProcessContext,
+) -> Result<Option<SkipReason>, String> {
+    let mode = context.args.update;
+    if mode == UpdateMode::All {
+        return Ok(None);
+    }
+    let source_meta = if kind == EntryKind::Symlink {
+        fs::symlink_metadata(&source.read_path)
+    } else {
+        fs::metadata(&source.read_path)
+    }
+    .map_err(|e| format!("cp: cannot stat '{}': {}", source.path.display(), e))?;
+    let target_meta = pinned
+        .dir
+        .symlink_metadata(&pinned.name)
+        .map_err(|e| format!("cp: cannot stat '{}': {}", target.display(), e))?;
+    mode.skip_reason(
+        &source.read_path,
+        &source_meta,
+        &pinned.entry_path(),
+        &target_meta,
+    )
+    .map_err(|e| {
+        format!(
+            "cp: cannot compare '{}' with '{}': {}",
+            source.path.display(),
+            target.display(),
+            e
+        )
+    })
+}
+
+/// `-a`/`-p`/`--preserve` 指定時、コピー先へコピー元の属性を適用する。
+///
+/// `follow` が `true` の場合はコピー元がシンボリックリンクでもリンク先の属性を使う。
+/// `dest` は属性を適用するパス、`target` はメッセージに使うコピー先パスとする。
+fn preserve_attributes(
+    source: &Source<'_>,
+    dest: &Path,
+    target: &Path,
+    follow: bool,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    if context.preserve == PreserveOptions::default() {
+        return Ok(());
+    }
+
+    let source_meta = if follow {
+        fs::metadata(&source.read_path)
+    } else {
+        fs::symlink_metadata(&source.read_path)
+    }
+    .map_err(|e| format!("cp: cannot stat '{}': {}", source.path.display(), e))?;
+
+    context
+        .preserve
+        .apply(&source.read_path, &source_meta, dest)
+        .map_err(|e| {
+            format!(
+                "cp: failed to preserve attributes for '{}': {}",
+                target.display(),
+                e
+            )
+        })
+}
+
+/// エントリへアクセスできない場合のエラーメッセージを組み立てる。
+///
+/// リンクの循環は権限エラーと区別して報告する。
+fn entry_access_error(path: &Path, err: &io::Error) -> String {
+    if is_symlink_loop(err) {
+        return cycle_error(path);
+    }
+    format!("cp: cannot access '{}': Permission denied", path.display())
+}
+
+fn cycle_error(path: &Path) -> String {
+    format!(
+        "cp: cannot copy '{}': symbolic link cycle detected",
+        path.display()
+    )
+}
+
+/// 書き込み先の親ディレクトリを許可スコープのルートからシンボリックリンクを辿らずに開く。
+fn pin_target(path: &Path, scopes: &ScopeSet) -> Result<Pinned, String> {
+    scopes
+        .open_parent(path)
+        .map_err(|e| format!("cp: cannot copy to '{}': {}", path.display(), e))
+}
+
+/// 開いた親ディレクトリ直下のエントリを、パスが差し替えられていないことを確認してゴミ箱へ移動する。
+fn trash_entry(dir: &Dir, name: &OsStr) -> TrashResult {
+    let path = dir
+        .verified_path(name)
+        .map_err(|e| format!("cp: failed to move existing file to trash: {e}"))?;
+    move_existing_file_to_trash(&path)
+}
+
 /// 既存ターゲットをゴミ箱へ移動し、失敗時はフォールバック移動を試みる。
 fn move_existing_file_to_trash(target: &Path) -> TrashResult {
+    move_existing_file_to_trash_as("cp", target)
+}
+
+/// `move_existing_file_to_trash` と同じ退避を行い、エラーメッセージの接頭辞に `command` を使う。
+///
+/// `cp` 以外のコマンドが置き換えた既存ファイルを同じフォールバック付きで退避する場合に使う。
+pub fn move_existing_file_to_trash_as(command: &str, target: &Path) -> TrashResult {
     move_existing_file_to_trash_with_fallback(
+        command,
         target,
         |path| {
             trash::delete(path)
-                .map_err(|e| format!("cp: failed to move existing file to trash: {e}"))
+                .map_err(|e| format!("{command}: failed to move existing file to trash: {e}"))
         },
-        resolve_fallback_trash_dir,
+        || resolve_fallback_trash_dir(command),
     )
 }
 
 /// 既存ターゲットを退避し、主経路失敗時はフォールバック先へ一意名で移動する。
 fn move_existing_file_to_trash_with_fallback<F, G>(
+    command: &str,
     target: &Path,
     primary_delete: F,
     fallback_dir_resolver: G,
@@ -242,13 +1283,13 @@ where
         Err(primary_err) => {
             let fallback_dir = fallback_dir_resolver()?;
             fs::create_dir_all(&fallback_dir).map_err(|e| {
-                format!("{primary_err}; cp: failed to prepare fallback trash directory: {e}")
+                format!("{primary_err}; {command}: failed to prepare fallback trash directory: {e}")
             })?;
 
-            let fallback_path = build_unique_fallback_path(&fallback_dir, target)?;
+            let fallback_path = build_unique_fallback_path(command, &fallback_dir, target)?;
             move_to_fallback_path(target, &fallback_path).map_err(|e| {
                 format!(
-                    "{primary_err}; cp: failed to move existing file to fallback trash '{}': {}",
+                    "{primary_err}; {command}: failed to move existing file to fallback trash '{}': {}",
                     fallback_path.display(),
                     e
                 )
@@ -312,31 +1353,45 @@ fn copy_and_remove(source: &Path, destination: &Path) -> io::Result<()> {
 
 /// ディレクトリを再帰コピーする。
 fn copy_dir_all(source: &Path, destination: &Path) -> io::Result<()> {
-    fs::create_dir_all(destination)?;
-    for entry in fs::read_dir(source)? {
-        let entry = entry?;
-        let entry_path = entry.path();
-        let target_path = destination.join(entry.file_name());
-        let entry_meta = fs::symlink_metadata(&entry_path)?;
-        let entry_type = entry_meta.file_type();
+    Walker::new(source).run(&mut MirrorCopy { destination })
+}
+
+/// ゴミ箱フォールバック用に、走査したエントリをそのまま `destination` 配下へ複製するビジター。
+struct MirrorCopy<'a> {
+    destination: &'a Path,
+}
+
+impl Visitor for MirrorCopy<'_> {
+    type Error = io::Error;
+
+    fn enter_dir(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+        fs::create_dir_all(self.destination.join(entry.relative))?;
+        Ok(Visit::Continue)
+    }
+
+    fn visit(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+        let target_path = self.destination.join(entry.relative);
+        let entry_type = entry.metadata.file_type();
         if entry_type.is_symlink() {
-            copy_symlink_entry(&entry_path, &target_path)?;
-        } else if entry_type.is_dir() {
-            copy_dir_all(&entry_path, &target_path)?;
+            copy_symlink_entry(&entry.pinned_path(), &target_path)?;
         } else if entry_type.is_file() {
-            fs::copy(&entry_path, &target_path)?;
+            fs::copy(entry.pinned_path(), &target_path)?;
         } else {
             return Err(io::Error::other("unsupported entry type in directory copy"));
         }
+        Ok(Visit::Continue)
+    }
+
+    fn walk_error(&mut self, _path: &Path, err: io::Error) -> io::Error {
+        err
     }
-    Ok(())
 }
 
 /// シンボリックリンクを辿らずリンクとして複製する。
 #[cfg(unix)]
 fn copy_symlink_entry(source: &Path, destination: &Path) -> io::Result<()> {
     let link_target = fs::read_link(source)?;
-    std::os::unix::fs::symlink(link_target, destination)
+    create_symlink(&link_target, destination)
 }
 
 /// シンボリックリンク複製の非Unix向けスタブ。
@@ -347,12 +1402,26 @@ fn copy_symlink_entry(_source: &Path, _destination: &Path) -> io::Result<()> {
     ))
 }
 
+/// `link_target` を指すシンボリックリンクを `destination` に作成する。
+#[cfg(unix)]
+fn create_symlink(link_target: &Path, destination: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(link_target, destination)
+}
+
+/// シンボリックリンク作成の非Unix向けスタブ。
+#[cfg(not(unix))]
+fn create_symlink(_link_target: &Path, _destination: &Path) -> io::Result<()> {
+    Err(io::Error::other(
+        "symlink creation is not supported on this platform",
+    ))
+}
+
 /// フォールバック用のゴミ箱ディレクトリを解決する。
-fn resolve_fallback_trash_dir() -> Result<PathBuf, String> {
+fn resolve_fallback_trash_dir(command: &str) -> Result<PathBuf, String> {
     let Some(home_dir) = dirs::home_dir() else {
-        return Err(
-            "cp: failed to resolve fallback trash directory: home directory not found".to_string(),
-        );
+        return Err(format!(
+            "{command}: failed to resolve fallback trash directory: home directory not found"
+        ));
     };
 
     if cfg!(target_os = "macos") {
@@ -367,10 +1436,14 @@ fn resolve_fallback_trash_dir() -> Result<PathBuf, String> {
 }
 
 /// フォールバック先で衝突しない退避パスを生成する。
-fn build_unique_fallback_path(fallback_dir: &Path, target: &Path) -> Result<PathBuf, String> {
+fn build_unique_fallback_path(
+    command: &str,
+    fallback_dir: &Path,
+    target: &Path,
+) -> Result<PathBuf, String> {
     let file_name = target.file_name().ok_or_else(|| {
         format!(
-            "cp: failed to build fallback trash path for '{}': invalid file name",
+            "{command}: failed to build fallback trash path for '{}': invalid file name",
             target.display()
         )
     })?;
@@ -390,14 +1463,23 @@ fn build_unique_fallback_path(fallback_dir: &Path, target: &Path) -> Result<Path
     }
 
     Err(format!(
-        "cp: failed to build fallback trash path for '{}': too many name collisions",
+        "{command}: failed to build fallback trash path for '{}': too many name collisions",
         target.display()
     ))
 }
 
 /// 壊れたシンボリックリンクも衝突として扱うため、symlink_metadata で占有判定する。
 fn path_slot_is_occupied(path: &Path) -> bool {
-    match fs::symlink_metadata(path) {
+    slot_is_occupied(fs::symlink_metadata(path))
+}
+
+/// 開いた親ディレクトリ直下のエントリについて `path_slot_is_occupied` と同じ判定を行う。
+fn entry_slot_is_occupied(dir: &Dir, name: &OsStr) -> bool {
+    slot_is_occupied(dir.symlink_metadata(name))
+}
+
+fn slot_is_occupied(meta: io::Result<fs::Metadata>) -> bool {
+    match meta {
         Ok(_) => true,
         Err(err) => err.kind() != io::ErrorKind::NotFound,
     }
@@ -437,7 +1519,7 @@ fn sanitize_file_name(file_name: &std::ffi::OsStr) -> String {
 }
 
 /// コピー先への書き込み前に、許可範囲外パスとシンボリックリンク経由を拒否する。
-fn ensure_target_path_allowed_for_write(path: &Path, config: &Config) -> Result<(), String> {
+fn ensure_target_path_allowed_for_write(path: &Path, scopes: &ScopeSet) -> Result<(), String> {
     if let Ok(meta) = fs::symlink_metadata(path)
         && meta.file_type().is_symlink()
     {
@@ -447,7 +1529,7 @@ fn ensure_target_path_allowed_for_write(path: &Path, config: &Config) -> Result<
         ));
     }
 
-    if !config.is_path_allowed(path) {
+    if !scopes.contains(path) {
         return Err(format!(
             "cp: cannot copy to '{}': path is outside allowed scope",
             path.display()
@@ -466,6 +1548,31 @@ mod tests {
     use std::{fs::symlink_metadata, os::unix::fs::symlink};
     use tempfile::tempdir;
 
+    #[test]
+    fn place_without_replacing_keeps_target_created_after_check() {
+        // 作成中に別プロセスがターゲットを置いても上書きせず、一時エントリも残らないことを確認する。
+        let temp_dir = tempdir().expect("create temp dir");
+        let target = temp_dir.path().join("target.txt");
+        let pinned = Pinned {
+            dir: Dir::open(temp_dir.path()).expect("open dir"),
+            name: "target.txt".into(),
+        };
+
+        let placed = place_without_replacing(&pinned, &target, |dest| {
+            fs::write(dest, b"new").map_err(|e| e.to_string())?;
+            fs::write(&target, b"raced").map_err(|e| e.to_string())
+        })
+        .expect("placement should not fail");
+
+        assert!(placed.is_none(), "existing target should cause a skip");
+        assert_eq!(fs::read_to_string(&target).expect("read target"), "raced");
+        let entries: Vec<_> = fs::read_dir(temp_dir.path())
+            .expect("read dir")
+            .map(|entry| entry.expect("dir entry").file_name())
+            .collect();
+        assert_eq!(entries, vec![std::ffi::OsString::from("target.txt")]);
+    }
+
     #[test]
     fn move_existing_file_to_trash_with_fallback_succeeds_when_primary_fails() {
         // 主経路のゴミ箱移動が失敗しても、フォールバックで退避できれば成功することを確認する。
@@ -476,6 +1583,7 @@ mod tests {
         file.write_all(b"old").expect("write target");
 
         let result = move_existing_file_to_trash_with_fallback(
+            "cp",
             &target,
             |_| Err("cp: failed to move existing file to trash: primary failed".to_string()),
             || Ok(fallback_dir.clone()),
@@ -512,6 +1620,7 @@ mod tests {
             .expect("write collision");
 
         let result = move_existing_file_to_trash_with_fallback(
+            "cp",
             &target,
             |_| Err("cp: failed to move existing file to trash: primary failed".to_string()),
             || Ok(fallback_dir.clone()),
@@ -536,6 +1645,7 @@ mod tests {
         file.write_all(b"old").expect("write target");
 
         let result = move_existing_file_to_trash_with_fallback(
+            "cp",
             &target,
             |_| Err("cp: failed to move existing file to trash: primary failed".to_string()),
             || {
@@ -627,7 +1737,7 @@ mod tests {
         fs::write(&target, "payload").expect("create target");
 
         let selected =
-            build_unique_fallback_path(&fallback_dir, &target).expect("select fallback path");
+            build_unique_fallback_path("cp", &fallback_dir, &target).expect("select fallback path");
 
         assert_eq!(selected, fallback_dir.join("report.txt.1"));
     }
@@ -1,28 +1,56 @@
+use std::fs;
 use std::path::Path;
 
 use crate::config::Config;
+use crate::limits::TreeStats;
 use crate::notifications::{CommandKind, CommandResultCounter};
+use crate::operands::{Destination, Operands};
+use args::Args;
 use handlers::{CopyKind, ProcessContext};
 
 pub mod args;
+pub mod dereference;
+pub mod file_copy;
+pub mod filter;
 pub mod handlers;
+pub mod preserve;
+pub mod replace;
+pub mod report;
 
 /// cp コマンド全体を実行し、各ソースの処理結果に応じて終了コードを決定する。
-pub fn run(
-    sources: Vec<String>,
-    target: String,
-    recursive: bool,
-    _force: bool,
-    no_clobber: bool,
-    config: Config,
-) -> i32 {
-    let target_path = Path::new(&target);
+///
+/// `-t` 指定時は `args.files` をすべてコピー元とし、それ以外は末尾をコピー先として扱う。
+pub fn run(args: Args, config: Config) -> i32 {
     let mut exit_code = 0;
     let mut counter = CommandResultCounter::new(CommandKind::Cp);
-    let context = ProcessContext::new(recursive, no_clobber, config);
+    let context = ProcessContext::new(args, config);
+    let operands = match Operands::parse(
+        "cp",
+        &context.args.files,
+        context.args.target_directory.as_deref(),
+        context.args.no_target_directory,
+    ) {
+        Ok(operands) => operands,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return 1;
+        }
+    };
+    let sources = operands.sources;
 
-    if sources.len() > 1 && !target_path.is_dir() {
-        eprintln!("cp: target '{target}' is not a directory");
+    let destination = match operands.destination("cp") {
+        Ok(destination) => destination,
+        Err(msg) => {
+            eprintln!("{msg}");
+            counter.record_failures(sources.len());
+            if context.config.notify.macos_notify {
+                counter.notify();
+            }
+            return 1;
+        }
+    };
+    if context.args.parents && !destination.is_directory() {
+        eprintln!("cp: with --parents, the destination must be a directory");
         counter.record_failures(sources.len());
         if context.config.notify.macos_notify {
             counter.notify();
@@ -30,8 +58,14 @@ pub fn run(
         return 1;
     }
 
+    if context.progress.is_enabled() {
+        context
+            .progress
+            .set_totals(prescan_sources(sources, &destination, &context));
+    }
+
     for source in sources {
-        if let Err(msg) = process_source(&source, target_path, &context) {
+        if let Err(msg) = process_source(source, &destination, &context) {
             eprintln!("{msg}");
             exit_code = 1;
             counter.record_failure();
@@ -40,6 +74,7 @@ pub fn run(
         }
     }
 
+    context.progress.finish();
     if context.config.notify.macos_notify {
         counter.notify();
     }
@@ -47,20 +82,48 @@ pub fn run(
     exit_code
 }
 
+/// `--progress` の総量として、コピー可能なソースすべてを事前走査して合計する。
+fn prescan_sources(
+    sources: &[String],
+    destination: &Destination,
+    context: &ProcessContext,
+) -> TreeStats {
+    let mut totals = TreeStats::default();
+    for source in sources {
+        let source_path = Path::new(source);
+        let Ok(kind) = determine_handler(source_path, context) else {
+            continue;
+        };
+        if let Ok(task) = handlers::validate(kind, source, source_path, destination, context) {
+            let stats = handlers::prescan(&task, context);
+            totals.files += stats.files;
+            totals.bytes += stats.bytes;
+        }
+    }
+    totals
+}
+
 /// 単一ソースの処理としてハンドラ選択・検証・実行を行う。
 fn process_source(
     source: &str,
-    target_path: &Path,
+    destination: &Destination,
     context: &ProcessContext,
 ) -> Result<(), String> {
     let source_path = Path::new(source);
     let kind = determine_handler(source_path, context)?;
-    let task = handlers::validate(kind, source, source_path, target_path, context)?;
+    let task = handlers::validate(kind, source, source_path, destination, context)?;
     handlers::execute(&task, context)
 }
 
 /// ソース種別とオプションに応じてコピー処理種別を決定する。
 fn determine_handler(source_path: &Path, context: &ProcessContext) -> Result<CopyKind, String> {
+    // `-P`（再帰コピー時の既定）ではシンボリックリンクを辿らずリンクとして複製する。
+    if !context.dereference.follows_command_line()
+        && fs::symlink_metadata(source_path).is_ok_and(|meta| meta.file_type().is_symlink())
+    {
+        return Ok(CopyKind::Symlink);
+    }
+
     if !source_path.exists() {
         return Err(format!(
             "cp: cannot stat '{}': No such file or directory",
@@ -71,7 +134,7 @@ fn determine_handler(source_path: &Path, context: &ProcessContext) -> Result<Cop
     if source_path.is_file() {
         Ok(CopyKind::File)
     } else if source_path.is_dir() {
-        if context.recursive {
+        if context.args.recursive || context.args.archive {
             Ok(CopyKind::RecursiveDirectory)
         } else {
             Ok(CopyKind::DirectoryWithoutRecursive)
@@ -86,6 +149,7 @@ mod tests {
     use super::*;
     use crate::config::{AdditionalAllowedDirectories, Config, NotifyConfig};
     use crate::notifications::{self, CommandKind, CommandSummary};
+    use clap::Parser;
     use std::cell::RefCell;
     use std::fs;
     use std::thread_local;
@@ -102,12 +166,17 @@ mod tests {
         Ok(())
     }
 
+    fn build_args(files: Vec<String>) -> Args {
+        Args::parse_from(std::iter::once("cp".to_string()).chain(files))
+    }
+
     fn allow_all_config(macos_notify: bool) -> Config {
         Config {
             additional_allowed_directories: AdditionalAllowedDirectories {
                 paths: vec![std::path::PathBuf::from("/")],
             },
             notify: NotifyConfig { macos_notify },
+            ..Config::default()
         }
     }
 
@@ -124,11 +193,10 @@ mod tests {
         });
         notifications::with_test_notifier(capture_notification, || {
             let exit_code = run(
-                vec![source.to_string_lossy().to_string()],
-                target.to_string_lossy().to_string(),
-                false,
-                false,
-                false,
+                build_args(vec![
+                    source.to_string_lossy().to_string(),
+                    target.to_string_lossy().to_string(),
+                ]),
                 allow_all_config(true),
             );
             assert_eq!(exit_code, 0);
@@ -162,14 +230,11 @@ mod tests {
         });
         notifications::with_test_notifier(capture_notification, || {
             let exit_code = run(
-                vec![
+                build_args(vec![
                     source1.to_string_lossy().to_string(),
                     source2.to_string_lossy().to_string(),
-                ],
-                target.to_string_lossy().to_string(),
-                false,
-                false,
-                false,
+                    target.to_string_lossy().to_string(),
+                ]),
                 allow_all_config(true),
             );
             assert_eq!(exit_code, 1);
@@ -200,11 +265,10 @@ mod tests {
         });
         notifications::with_test_notifier(capture_notification, || {
             let exit_code = run(
-                vec![source.to_string_lossy().to_string()],
-                target.to_string_lossy().to_string(),
-                false,
-                false,
-                false,
+                build_args(vec![
+                    source.to_string_lossy().to_string(),
+                    target.to_string_lossy().to_string(),
+                ]),
                 allow_all_config(false),
             );
             assert_eq!(exit_code, 0);
@@ -0,0 +1,291 @@
+use crate::commands::cp::args::{Args, PreserveAttr};
+use filetime::FileTime;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `-a`/`-p`/`--preserve` で指定された保持対象の属性を表す。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PreserveOptions {
+    pub mode: bool,
+    pub timestamps: bool,
+    pub ownership: bool,
+    pub xattr: bool,
+    pub links: bool,
+}
+
+impl PreserveOptions {
+    /// 引数から保持対象の属性を組み立てる。
+    ///
+    /// `-a` は全属性、`-p` は `mode,ownership,timestamps` を指定したものとして扱う。
+    pub fn from_args(args: &Args) -> Self {
+        let mut options = Self::default();
+        if args.archive {
+            options.add(PreserveAttr::All);
+        }
+        if args.preserve_default {
+            options.add(PreserveAttr::Mode);
+            options.add(PreserveAttr::Ownership);
+            options.add(PreserveAttr::Timestamps);
+        }
+        for attr in &args.preserve {
+            options.add(*attr);
+        }
+        options
+    }
+
+    fn add(&mut self, attr: PreserveAttr) {
+        match attr {
+            PreserveAttr::Mode => self.mode = true,
+            PreserveAttr::Timestamps => self.timestamps = true,
+            PreserveAttr::Ownership => self.ownership = true,
+            PreserveAttr::Xattr => self.xattr = true,
+            PreserveAttr::Links => self.links = true,
+            PreserveAttr::All => {
+                *self = Self {
+                    mode: true,
+                    timestamps: true,
+                    ownership: true,
+                    xattr: true,
+                    links: true,
+                }
+            }
+        }
+    }
+
+    /// コピー後に属性の付け直しが必要かを返す。
+    fn applies_any_attribute(&self) -> bool {
+        self.mode || self.timestamps || self.ownership || self.xattr
+    }
+
+    /// コピー済みエントリへソースの属性を適用する。
+    ///
+    /// chown は setuid/setgid ビットを落とすため、所有者 → 拡張属性 → モード → 時刻の順に適用する。
+    /// ディレクトリの場合は配下のコピー完了後に呼び出すことで、時刻が子の作成で上書きされないようにする。
+    pub fn apply(
+        &self,
+        source: &Path,
+        source_meta: &fs::Metadata,
+        target: &Path,
+    ) -> io::Result<()> {
+        if !self.applies_any_attribute() {
+            return Ok(());
+        }
+        let is_symlink = source_meta.file_type().is_symlink();
+
+        if self.ownership {
+            apply_ownership(source_meta, target)?;
+        }
+        // Linux ではシンボリックリンクへ user 名前空間の拡張属性を設定できないため対象外とする。
+        if self.xattr && !is_symlink {
+            copy_xattrs(source, target)?;
+        }
+        // シンボリックリンク自体のパーミッションは変更できない（chmod はリンク先を変更してしまう）。
+        if self.mode && !is_symlink {
+            fs::set_permissions(target, source_meta.permissions())?;
+        }
+        if self.timestamps {
+            let atime = FileTime::from_last_access_time(source_meta);
+            let mtime = FileTime::from_last_modification_time(source_meta);
+            filetime::set_symlink_file_times(target, atime, mtime)?;
+        }
+        Ok(())
+    }
+}
+
+/// コピー元ツリー内のハードリンクを記録し、同一 inode の2件目以降をリンクとして再現する。
+#[derive(Debug, Default)]
+pub struct HardlinkTracker {
+    copied: HashMap<(u64, u64), PathBuf>,
+}
+
+impl HardlinkTracker {
+    /// 既にコピー済みの inode であれば初回のコピー先を返す。
+    ///
+    /// 初出の inode は `None` を返すため、呼び出し側で通常のコピー後に `record` する。
+    pub fn copied_path(&self, source_meta: &fs::Metadata) -> Option<&Path> {
+        inode_key(source_meta)
+            .and_then(|key| self.copied.get(&key))
+            .map(PathBuf::as_path)
+    }
+
+    /// ハードリンクとして再現する候補（リンク数が2以上の非ディレクトリ）かを返す。
+    pub fn tracks(source_meta: &fs::Metadata) -> bool {
+        inode_key(source_meta).is_some()
+    }
+
+    /// 通常コピーしたファイルのコピー先を inode と対応付けて記録する。
+    pub fn record(&mut self, source_meta: &fs::Metadata, target: &Path) {
+        if let Some(key) = inode_key(source_meta) {
+            self.copied.insert(key, target.to_path_buf());
+        }
+    }
+}
+
+/// リンク数が2以上の非ディレクトリについて (デバイス, inode) を返す。
+#[cfg(unix)]
+fn inode_key(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    if meta.is_dir() || meta.nlink() < 2 {
+        return None;
+    }
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn inode_key(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// 所有者とグループを適用する。
+///
+/// root 以外では他ユーザーへの chown が拒否されるため、GNU `cp` と同様にグループのみを試み、
+/// それも拒否された場合は黙って諦める。
+#[cfg(unix)]
+fn apply_ownership(source_meta: &fs::Metadata, target: &Path) -> io::Result<()> {
+    use std::os::unix::fs::{MetadataExt, lchown};
+
+    match lchown(target, Some(source_meta.uid()), Some(source_meta.gid())) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied && !running_as_root() => {
+            match lchown(target, None, Some(source_meta.gid())) {
+                Err(e) if e.kind() != io::ErrorKind::PermissionDenied => Err(e),
+                _ => Ok(()),
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_ownership(_source_meta: &fs::Metadata, _target: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn running_as_root() -> bool {
+    // SAFETY: geteuid は常に成功し、副作用を持たない。
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// 拡張属性を複製する。
+///
+/// ファイルシステムが拡張属性に対応していない場合や、`security.*` など権限不足で
+/// 設定できない名前空間の属性は GNU `cp -a` と同様にスキップする。
+#[cfg(unix)]
+fn copy_xattrs(source: &Path, target: &Path) -> io::Result<()> {
+    let names = match xattr::list(source) {
+        Ok(names) => names,
+        Err(e) if is_skippable_xattr_error(&e) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for name in names {
+        let Some(value) = xattr::get(source, &name)? else {
+            continue;
+        };
+        match xattr::set(target, &name, &value) {
+            Ok(()) => {}
+            Err(e) if is_skippable_xattr_error(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_skippable_xattr_error(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::PermissionDenied
+        || err.kind() == io::ErrorKind::Unsupported
+        || err.raw_os_error() == Some(libc::ENOTSUP)
+}
+
+#[cfg(not(unix))]
+fn copy_xattrs(_source: &Path, _target: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    fn options_for(flags: &[&str]) -> PreserveOptions {
+        let mut argv = vec!["cp"];
+        argv.extend_from_slice(flags);
+        argv.extend_from_slice(&["source", "target"]);
+        PreserveOptions::from_args(&Args::parse_from(argv))
+    }
+
+    #[test]
+    fn from_args_maps_flags_to_attributes() {
+        // -a は全属性、-p と値なし --preserve は mode,ownership,timestamps を保持対象にすることを確認する。
+        let all = PreserveOptions {
+            mode: true,
+            timestamps: true,
+            ownership: true,
+            xattr: true,
+            links: true,
+        };
+        let default_set = PreserveOptions {
+            mode: true,
+            timestamps: true,
+            ownership: true,
+            ..PreserveOptions::default()
+        };
+
+        assert_eq!(options_for(&[]), PreserveOptions::default());
+        assert_eq!(options_for(&["-a"]), all);
+        assert_eq!(options_for(&["--preserve=all"]), all);
+        assert_eq!(options_for(&["-p"]), default_set);
+        assert_eq!(options_for(&["--preserve"]), default_set);
+        assert_eq!(
+            options_for(&["-p", "--preserve=xattr,links"]),
+            PreserveOptions {
+                xattr: true,
+                links: true,
+                ..default_set
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hardlink_tracker_returns_first_copy_of_same_inode() {
+        // 同一 inode の2件目以降では初回のコピー先を返すことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let original = temp_dir.path().join("original");
+        let alias = temp_dir.path().join("alias");
+        fs::write(&original, "data").unwrap();
+        fs::hard_link(&original, &alias).unwrap();
+        let first_target = temp_dir.path().join("copy-original");
+        let mut tracker = HardlinkTracker::default();
+
+        let original_meta = fs::metadata(&original).unwrap();
+        assert_eq!(tracker.copied_path(&original_meta), None);
+        tracker.record(&original_meta, &first_target);
+        let alias_meta = fs::metadata(&alias).unwrap();
+
+        assert_eq!(
+            tracker.copied_path(&alias_meta),
+            Some(first_target.as_path())
+        );
+    }
+
+    #[test]
+    fn hardlink_tracker_ignores_files_with_single_link() {
+        // リンク数1のファイルは記録せず通常コピーさせることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("single");
+        fs::write(&file, "data").unwrap();
+        let mut tracker = HardlinkTracker::default();
+        let meta = fs::metadata(&file).unwrap();
+
+        tracker.record(&meta, &temp_dir.path().join("a"));
+
+        assert_eq!(tracker.copied_path(&meta), None);
+    }
+}
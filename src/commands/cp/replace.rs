@@ -0,0 +1,296 @@
+use crate::backup::{self, BackupPolicy};
+use crate::config::ScopeSet;
+use crate::dirfd::{Dir, RenameMode};
+use crate::staging;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// コピー先と同じディレクトリに、置き換え用の隠し一時エントリ名を生成する。
+///
+/// エントリ自体は作成しないため、呼び出し側は `create_new` 相当の排他的な作成で使う。
+pub fn sibling_temp_name(dir: &Dir, name: &OsStr) -> io::Result<OsString> {
+    staging::unique_name(dir, |id| {
+        format!(".{}.safecmd-tmp-{id}", name.to_string_lossy())
+    })
+}
+
+/// 一時エントリに用意した新しい内容をディスクへ書き出す。
+///
+/// シンボリックリンクは開くとリンク先を辿ってしまうため対象外とする。
+pub fn sync_entry(dir: &Dir, name: &OsStr) -> io::Result<()> {
+    if dir.symlink_metadata(name)?.file_type().is_symlink() {
+        return Ok(());
+    }
+    fs::File::open(dir.entry_path(name))?.sync_all()
+}
+
+/// 一時エントリの新しい内容で既存ターゲットを置き換え、古いターゲットを `retire` で退避する。
+///
+/// 古いターゲットは元のパスのまま `retire` へ渡すため、ゴミ箱には本来の場所が記録される。
+/// 退避の前に同じディレクトリの隠しステージングディレクトリへ元の名前でハードリンクしておき、
+/// 退避後の `RENAME_NOREPLACE` による配置に失敗した場合はそこから元の名前へ戻す。
+/// ハードリンクできない場合（ディレクトリやハードリンク非対応のファイルシステム）は控えを残さない。
+/// 操作はすべて開いた親ディレクトリ `dir` を基準に行い、`retire` には差し替えがないことを
+/// 確認したパスを渡す。失敗時は `staged` を削除し、ターゲットは置き換え前の状態に保つ。
+pub fn swap_in<F>(
+    dir: &Dir,
+    staged: &OsStr,
+    name: &OsStr,
+    target: &Path,
+    retire: F,
+) -> Result<(), String>
+where
+    F: FnOnce(&Path) -> Result<(), String>,
+{
+    swap_in_as("cp", dir, staged, name, target, retire)
+}
+
+/// `swap_in` と同じ置き換えを行い、エラーメッセージの接頭辞に `command` を使う。
+///
+/// `cp` 以外のコマンドが既存ターゲットを同じ手順で置き換える場合に使う。
+pub fn swap_in_as<F>(
+    command: &str,
+    dir: &Dir,
+    staged: &OsStr,
+    name: &OsStr,
+    target: &Path,
+    retire: F,
+) -> Result<(), String>
+where
+    F: FnOnce(&Path) -> Result<(), String>,
+{
+    let replace_error = |target: &Path, e: &io::Error| {
+        format!("{command}: cannot replace '{}': {}", target.display(), e)
+    };
+    let fail = |e: io::Error| {
+        remove_staged(dir, staged);
+        replace_error(target, &e)
+    };
+    let staging_name = staging::create_dir(dir, "safecmd-old").map_err(fail)?;
+    let staging_dir = match dir.open_dir(&staging_name) {
+        Ok(staging_dir) => staging_dir,
+        Err(e) => {
+            let _ = dir.remove_dir(&staging_name);
+            return Err(fail(e));
+        }
+    };
+    let kept = KeptTarget {
+        dir,
+        staging_name: &staging_name,
+        staging_dir: &staging_dir,
+        name,
+        linked: dir.hard_link(name, &staging_dir, name).is_ok(),
+    };
+    let with_kept = |e: String| match kept.restore() {
+        Ok(()) => e,
+        Err(_) => format!(
+            "{e}; previous content of '{}' was kept at '{}'",
+            target.display(),
+            staging_dir.path().join(name).display()
+        ),
+    };
+
+    let retired = match dir.verified_path(name) {
+        Ok(retired) => retired,
+        Err(e) => return Err(with_kept(fail(e))),
+    };
+    if let Err(e) = retire(&retired) {
+        remove_staged(dir, staged);
+        return Err(with_kept(e));
+    }
+    if let Err(e) = dir.rename(staged, dir, name, RenameMode::NoReplace) {
+        return Err(with_kept(fail(e)));
+    }
+    let _ = kept.discard();
+    dir.sync_all().map_err(|e| replace_error(target, &e))
+}
+
+/// 置き換え中に控えとして残した古いターゲットのハードリンク。
+struct KeptTarget<'a> {
+    dir: &'a Dir,
+    staging_name: &'a OsStr,
+    staging_dir: &'a Dir,
+    name: &'a OsStr,
+    linked: bool,
+}
+
+impl KeptTarget<'_> {
+    /// 置き換えを中断したときに、ターゲットのパスが空いていれば控えを元の名前へ戻す。
+    ///
+    /// 既に何かが存在する場合は上書きせず控えを削除する。戻せなかった場合は控えを残す。
+    fn restore(&self) -> io::Result<()> {
+        if self.linked {
+            match self
+                .staging_dir
+                .rename(self.name, self.dir, self.name, RenameMode::NoReplace)
+            {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    self.staging_dir.remove_file(self.name)?
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        self.dir.remove_dir(self.staging_name)
+    }
+
+    /// 置き換えが完了した後に控えとステージングディレクトリを削除する。
+    fn discard(&self) -> io::Result<()> {
+        if self.linked {
+            self.staging_dir.remove_file(self.name)?;
+        }
+        self.dir.remove_dir(self.staging_name)
+    }
+}
+
+/// 既存ターゲットを同じディレクトリのバックアップとして残し、一時エントリの新しい内容で置き換える。
+///
+/// バックアップをハードリンクで作成できた場合は rename で不可分に置き換える。古いターゲットを
+/// バックアップ名へ rename した場合は `RENAME_NOREPLACE` で配置し、失敗時はバックアップを元の名前へ戻す。
+/// 既存の単純バックアップは `retire` で退避する。失敗時は `staged` を削除する。
+pub fn swap_in_with_backup<F>(
+    dir: &Dir,
+    staged: &OsStr,
+    name: &OsStr,
+    target: &Path,
+    policy: &BackupPolicy,
+    scopes: &ScopeSet,
+    retire: F,
+) -> Result<(), String>
+where
+    F: FnOnce(&Path) -> Result<(), String>,
+{
+    let backup = backup::back_up(dir, name, policy, scopes, |path| {
+        retire(path).map_err(io::Error::other)
+    })
+    .map_err(|e| {
+        remove_staged(dir, staged);
+        format!("cp: cannot back up '{}': {}", target.display(), e)
+    })?;
+
+    let mode = if backup.linked {
+        RenameMode::Replace
+    } else {
+        RenameMode::NoReplace
+    };
+    if let Err(e) = dir.rename(staged, dir, name, mode) {
+        remove_staged(dir, staged);
+        if !backup.linked {
+            let _ = dir.rename(&backup.name, dir, name, RenameMode::NoReplace);
+        }
+        return Err(replace_error(target, &e));
+    }
+    dir.sync_all().map_err(|e| replace_error(target, &e))
+}
+
+/// 置き換えに使わなかった一時エントリを削除する。
+pub fn remove_staged(dir: &Dir, staged: &OsStr) {
+    let result = match dir.symlink_metadata(staged) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(dir.entry_path(staged)),
+        Ok(_) => dir.remove_file(staged),
+        Err(_) => return,
+    };
+    let _ = result;
+}
+
+fn replace_error(target: &Path, err: &io::Error) -> String {
+    format!("cp: cannot replace '{}': {}", target.display(), err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use tempfile::TempDir;
+
+    #[test]
+    fn swap_in_retires_old_target_under_its_original_path() {
+        // 古いターゲットを元のパスのまま退避処理へ渡してから新しい内容を配置することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("data.txt");
+        fs::write(&target, "old").unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+        let name = OsStr::new("data.txt");
+        let staged = sibling_temp_name(&dir, name).unwrap();
+        fs::write(dir.entry_path(&staged), "new").unwrap();
+        let retired = RefCell::new(None);
+
+        swap_in(&dir, &staged, name, &target, |old| {
+            assert_eq!(old, target);
+            *retired.borrow_mut() = Some(fs::read_to_string(old).unwrap());
+            fs::remove_file(old).map_err(|e| e.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(retired.into_inner().as_deref(), Some("old"));
+        assert!(!dir.contains(&staged));
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn swap_in_keeps_target_when_retire_fails() {
+        // 退避に失敗した場合はターゲットを置き換えず、一時エントリと控えを残さないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("data.txt");
+        fs::write(&target, "old").unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+        let name = OsStr::new("data.txt");
+        let staged = sibling_temp_name(&dir, name).unwrap();
+        fs::write(dir.entry_path(&staged), "new").unwrap();
+
+        let err = swap_in(&dir, &staged, name, &target, |_| {
+            Err("trash failed".to_string())
+        })
+        .unwrap_err();
+
+        assert_eq!(err, "trash failed");
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn swap_in_restores_old_target_when_rename_fails() {
+        // 退避後に新しい内容を配置できない場合、控えから古いターゲットを元の名前へ戻すことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("data.txt");
+        fs::write(&target, "old").unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+        let name = OsStr::new("data.txt");
+        let staged = sibling_temp_name(&dir, name).unwrap();
+        fs::write(dir.entry_path(&staged), "new").unwrap();
+
+        let err = swap_in(&dir, &staged, name, &target, |old| {
+            fs::remove_file(dir.entry_path(&staged)).unwrap();
+            fs::remove_file(old).map_err(|e| e.to_string())
+        })
+        .unwrap_err();
+
+        assert!(err.starts_with("cp: cannot replace"), "{err}");
+        assert!(!err.contains("previous content of"), "{err}");
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn swap_in_replaces_directory_target_after_retiring_it() {
+        // ハードリンクできないディレクトリは退避後に置き換えることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("entry");
+        fs::create_dir(&target).unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+        let name = OsStr::new("entry");
+        let staged = sibling_temp_name(&dir, name).unwrap();
+        fs::write(dir.entry_path(&staged), "new").unwrap();
+
+        swap_in(&dir, &staged, name, &target, |old| {
+            fs::remove_dir(old).map_err(|e| e.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::commands::cp::args::Args;
+use crate::update::SkipReason;
+use crate::verify::Digest;
+use serde::Serialize;
+use std::path::Path;
+
+/// コピーしたエントリの種別を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    File,
+    Hardlink,
+    Symlink,
+    Directory,
+}
+
+/// `-v`/`--json` で出力するコピー1件分の結果を保持する。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CopyRecord {
+    pub kind: EntryKind,
+    pub source: String,
+    pub target: String,
+    /// ファイルの論理サイズ（穴を含む見かけのサイズ）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logical_bytes: Option<u64>,
+    /// コピー先が実際に確保したディスク領域のサイズ。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub physical_bytes: Option<u64>,
+    /// `--verify` で検証したコピー元のハッシュ。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<Digest>,
+    /// `--update` で既存ターゲットを置き換えなかった理由。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<SkipReason>,
+}
+
+impl CopyRecord {
+    /// サイズ情報を持たないエントリ（リンク・ディレクトリ）の記録を生成する。
+    pub fn new(kind: EntryKind, source: &Path, target: &Path) -> Self {
+        Self {
+            kind,
+            source: source.display().to_string(),
+            target: target.display().to_string(),
+            logical_bytes: None,
+            physical_bytes: None,
+            checksum: None,
+            skipped: None,
+        }
+    }
+
+    /// 論理サイズと物理サイズを付与する。
+    pub fn with_sizes(mut self, logical_bytes: u64, physical_bytes: u64) -> Self {
+        self.logical_bytes = Some(logical_bytes);
+        self.physical_bytes = Some(physical_bytes);
+        self
+    }
+
+    /// 検証済みのハッシュを付与する。
+    pub fn with_checksum(mut self, checksum: Option<Digest>) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// `--update` で置き換えずにスキップしたことを記録する。
+    pub fn with_skip(mut self, reason: SkipReason) -> Self {
+        self.skipped = Some(reason);
+        self
+    }
+
+    /// `-v` 向けの1行表現を返す。
+    fn to_verbose_line(&self) -> String {
+        if let Some(reason) = self.skipped {
+            return format!("skipped '{}' -> '{}' ({reason})", self.source, self.target);
+        }
+        let mut line = format!("'{}' -> '{}'", self.source, self.target);
+        if let (Some(logical), Some(physical)) = (self.logical_bytes, self.physical_bytes) {
+            line.push_str(&format!(" ({logical} bytes, {physical} bytes on disk)"));
+        }
+        if let Some(checksum) = &self.checksum {
+            line.push_str(&format!(" [{checksum}]"));
+        }
+        line
+    }
+}
+
+/// 引数に応じてコピー結果を標準出力へ書き出す。
+///
+/// `--json` 指定時は1件1行の JSON、`-v` 指定時は GNU `cp -v` 形式で出力する。
+pub fn emit(args: &Args, record: CopyRecord) {
+    if args.json {
+        match serde_json::to_string(&record) {
+            Ok(json) => println!("{json}"),
+            Err(e) => eprintln!("cp: failed to encode report: {e}"),
+        }
+    } else if args.verbose {
+        println!("{}", record.to_verbose_line());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::HashAlgorithm;
+
+    #[test]
+    fn verbose_line_includes_logical_and_physical_sizes() {
+        // -v の出力にコピー元・コピー先と論理/物理サイズが含まれることを確認する。
+        let record = CopyRecord::new(EntryKind::File, Path::new("a.img"), Path::new("b.img"))
+            .with_sizes(1_048_576, 4096);
+
+        assert_eq!(
+            record.to_verbose_line(),
+            "'a.img' -> 'b.img' (1048576 bytes, 4096 bytes on disk)"
+        );
+    }
+
+    #[test]
+    fn json_record_omits_sizes_for_symlinks() {
+        // サイズを持たないエントリでは JSON からサイズ項目を省略することを確認する。
+        let record = CopyRecord::new(EntryKind::Symlink, Path::new("link"), Path::new("copy"));
+
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"kind":"symlink","source":"link","target":"copy"}"#
+        );
+    }
+
+    #[test]
+    fn skipped_record_reports_reason_in_verbose_and_json() {
+        // --update でスキップしたエントリが -v と JSON の両方で理由付きで出力されることを確認する。
+        let record = CopyRecord::new(EntryKind::File, Path::new("a"), Path::new("b"))
+            .with_skip(SkipReason::Identical);
+
+        assert_eq!(
+            record.to_verbose_line(),
+            "skipped 'a' -> 'b' (content is identical)"
+        );
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"kind":"file","source":"a","target":"b","skipped":"identical"}"#
+        );
+    }
+
+    #[test]
+    fn verified_record_includes_checksum_in_verbose_and_json() {
+        // --verify で検証したハッシュが -v と JSON の両方に含まれることを確認する。
+        let checksum = Digest {
+            algorithm: HashAlgorithm::Sha256,
+            hex: "ab".repeat(32),
+        };
+        let record = CopyRecord::new(EntryKind::File, Path::new("a"), Path::new("b"))
+            .with_sizes(3, 4096)
+            .with_checksum(Some(checksum.clone()));
+
+        assert!(
+            record
+                .to_verbose_line()
+                .ends_with(&format!("[sha256:{}]", checksum.hex))
+        );
+        let json: serde_json::Value = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["checksum"]["algorithm"], "sha256");
+        assert_eq!(json["checksum"]["hex"], checksum.hex);
+    }
+}
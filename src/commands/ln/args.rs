@@ -0,0 +1,43 @@
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(name = "ln")]
+#[command(about = "Safely create links between files", long_about = None)]
+pub struct Args {
+    /// Make symbolic links instead of hard links
+    #[arg(short = 's', long = "symbolic")]
+    pub symbolic: bool,
+
+    /// Replace existing destination files, moving them to the trash first
+    #[arg(short = 'f', long = "force")]
+    pub force: bool,
+
+    /// Treat a destination that is a symlink to a directory as if it were a normal file
+    #[arg(short = 'n', long = "no-dereference")]
+    pub no_dereference: bool,
+
+    /// Create symbolic links relative to the link location
+    #[arg(short = 'r', long = "relative", requires = "symbolic")]
+    pub relative: bool,
+
+    /// Create all links in DIRECTORY
+    #[arg(
+        short = 't',
+        long = "target-directory",
+        value_name = "DIRECTORY",
+        conflicts_with = "no_target_directory"
+    )]
+    pub target_directory: Option<String>,
+
+    /// Treat LINK_NAME as a normal file always
+    #[arg(short = 'T', long = "no-target-directory")]
+    pub no_target_directory: bool,
+
+    /// Print the name of each linked file
+    #[arg(short = 'v', long = "verbose")]
+    pub verbose: bool,
+
+    /// Link targets, then the link name or directory unless `-t` is given
+    #[arg(required = true, num_args = 1..)]
+    pub files: Vec<String>,
+}
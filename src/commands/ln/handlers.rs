@@ -0,0 +1,307 @@
+use crate::commands::cp::{handlers as cp_handlers, replace};
+use crate::commands::ln::args::Args;
+use crate::config::{Config, ScopeSet};
+use crate::dirfd::Dir;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// ln 実行時に必要な引数と設定を保持するコンテキスト。
+pub struct ProcessContext {
+    pub args: Args,
+    pub config: Config,
+    /// 実行開始時に解決した許可スコープ。
+    pub scopes: ScopeSet,
+}
+
+impl ProcessContext {
+    /// ln 実行に必要な引数と設定をまとめたコンテキストを生成する。
+    pub fn new(args: Args, config: Config) -> Self {
+        let scopes = config.scope_set();
+        Self {
+            args,
+            config,
+            scopes,
+        }
+    }
+}
+
+/// 作成するリンクの種類を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    Hard,
+    Symbolic,
+}
+
+impl LinkKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Hard => "hard link",
+            Self::Symbolic => "symbolic link",
+        }
+    }
+}
+
+/// 実行フェーズで使うリンク作成タスク情報を保持する。
+pub struct LinkTask {
+    pub kind: LinkKind,
+    /// コマンドラインで指定されたリンク元。
+    pub source: PathBuf,
+    /// ハードリンクの実体、またはシンボリックリンクの内容。
+    pub link_target: PathBuf,
+    pub link_name: PathBuf,
+}
+
+/// リンク作成前にパス許可範囲とリンク元を検証し、実行タスクを構築する。
+///
+/// リンクの置き場所は常に許可範囲内である必要がある。ハードリンクはリンク元も許可範囲内で
+/// ある必要があり、シンボリックリンクは `[ln] confine_symlink_targets` 有効時のみリンク先を確認する。
+pub fn validate(
+    source: &str,
+    link_name: &Path,
+    context: &ProcessContext,
+) -> Result<LinkTask, String> {
+    if !context.scopes.contains(link_name) {
+        return Err(format!(
+            "ln: cannot create link '{}': path is outside allowed scope",
+            link_name.display()
+        ));
+    }
+    let source_path = Path::new(source);
+
+    if context.args.symbolic {
+        let link_target = if context.args.relative {
+            relative_link_target(source_path, link_name)?
+        } else {
+            source_path.to_path_buf()
+        };
+        if context.config.ln.confine_symlink_targets {
+            ensure_symlink_target_allowed(&link_target, link_name, context)?;
+        }
+        return Ok(LinkTask {
+            kind: LinkKind::Symbolic,
+            source: source_path.to_path_buf(),
+            link_target,
+            link_name: link_name.to_path_buf(),
+        });
+    }
+
+    let source_meta = fs::symlink_metadata(source_path).map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            format!("ln: failed to access '{source}': No such file or directory")
+        } else {
+            format!("ln: failed to access '{source}': {e}")
+        }
+    })?;
+    if source_meta.is_dir() {
+        return Err(format!(
+            "ln: '{source}': hard link not allowed for directory"
+        ));
+    }
+    if !context.scopes.contains(source_path) {
+        return Err(format!(
+            "ln: cannot link '{source}': path is outside allowed scope"
+        ));
+    }
+    Ok(LinkTask {
+        kind: LinkKind::Hard,
+        source: source_path.to_path_buf(),
+        link_target: source_path.to_path_buf(),
+        link_name: link_name.to_path_buf(),
+    })
+}
+
+/// リンクを作成する。
+///
+/// リンク名が既に存在する場合は `-f` 指定時のみ置き換える。新しいリンクを同じディレクトリの
+/// 隠し一時エントリに作成してから rename で置き換え、古いエントリはゴミ箱へ移動する。
+/// ディレクトリは置き換えない。
+pub fn execute(task: &LinkTask, context: &ProcessContext) -> Result<(), String> {
+    let link_name = &task.link_name;
+    let create_error = |e: io::Error| {
+        format!(
+            "ln: failed to create {} '{}': {}",
+            task.kind.label(),
+            link_name.display(),
+            e
+        )
+    };
+    let pinned = context
+        .scopes
+        .open_parent(link_name)
+        .map_err(create_error)?;
+
+    match pinned.dir.symlink_metadata(&pinned.name) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            create_link(task, &pinned.dir, &pinned.name).map_err(create_error)?;
+        }
+        Err(e) => {
+            return Err(format!(
+                "ln: cannot access '{}': {}",
+                link_name.display(),
+                e
+            ));
+        }
+        Ok(existing) => {
+            if !context.args.force {
+                return Err(format!(
+                    "ln: failed to create {} '{}': File exists",
+                    task.kind.label(),
+                    link_name.display()
+                ));
+            }
+            if existing.is_dir() {
+                return Err(format!(
+                    "ln: cannot overwrite directory '{}'",
+                    link_name.display()
+                ));
+            }
+            ensure_not_same_file(task, &existing)?;
+            let staged =
+                replace::sibling_temp_name(&pinned.dir, &pinned.name).map_err(create_error)?;
+            create_link(task, &pinned.dir, &staged).map_err(create_error)?;
+            replace::swap_in_as(
+                "ln",
+                &pinned.dir,
+                &staged,
+                &pinned.name,
+                link_name,
+                move_existing_file_to_trash,
+            )?;
+        }
+    }
+
+    if context.args.verbose {
+        let arrow = match task.kind {
+            LinkKind::Hard => "=>",
+            LinkKind::Symbolic => "->",
+        };
+        println!(
+            "'{}' {arrow} '{}'",
+            link_name.display(),
+            task.link_target.display()
+        );
+    }
+    Ok(())
+}
+
+/// 開いたディレクトリ `dir` 直下の `name` にリンクを作成する。
+fn create_link(task: &LinkTask, dir: &Dir, name: &OsStr) -> io::Result<()> {
+    match task.kind {
+        LinkKind::Symbolic => dir.symlink(&task.link_target, name),
+        LinkKind::Hard => fs::hard_link(&task.link_target, dir.entry_path(name)),
+    }
+}
+
+/// `-f` で置き換える既存エントリがリンク元と同一実体ならエラーにする。
+fn ensure_not_same_file(task: &LinkTask, existing: &fs::Metadata) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        let Ok(source_meta) = fs::metadata(&task.source) else {
+            return Ok(());
+        };
+        if source_meta.dev() == existing.dev() && source_meta.ino() == existing.ino() {
+            return Err(format!(
+                "ln: '{}' and '{}' are the same file",
+                task.source.display(),
+                task.link_name.display()
+            ));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (task, existing);
+    Ok(())
+}
+
+/// `-r` 指定時、リンクの置き場所から見たリンク元の相対パスを返す。
+fn relative_link_target(source: &Path, link_name: &Path) -> Result<PathBuf, String> {
+    let access_error =
+        |path: &Path, e: io::Error| format!("ln: cannot access '{}': {}", path.display(), e);
+    let link_dir = parent_or_current(link_name);
+    let base = link_dir
+        .canonicalize()
+        .map_err(|e| access_error(link_dir, e))?;
+    let target = source
+        .canonicalize()
+        .or_else(|_| std::path::absolute(source))
+        .map_err(|e| access_error(source, e))?;
+    Ok(relative_path(&target, &base))
+}
+
+/// 絶対パス `target` を、ディレクトリ `base` からの相対パスで表す。
+fn relative_path(target: &Path, base: &Path) -> PathBuf {
+    let common = target
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in base.components().skip(common) {
+        relative.push("..");
+    }
+    for component in target.components().skip(common) {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+/// シンボリックリンクを辿った先の実体が許可範囲内であることを確認する。
+///
+/// 相対パスのリンク内容はリンクの置き場所を基準に解決し、存在する場合は途中のリンクも辿る。
+fn ensure_symlink_target_allowed(
+    link_target: &Path,
+    link_name: &Path,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    let resolved = parent_or_current(link_name).join(link_target);
+    let resolved = resolved.canonicalize().unwrap_or(resolved);
+    if context.scopes.contains(&resolved) {
+        return Ok(());
+    }
+    Err(format!(
+        "ln: cannot create symbolic link '{}' to '{}': link target is outside allowed scope",
+        link_name.display(),
+        link_target.display()
+    ))
+}
+
+/// パスの親ディレクトリを返す。親が空の場合はカレントディレクトリとする。
+fn parent_or_current(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// 置き換えた既存エントリをゴミ箱（失敗時はフォールバック先）へ移動する。
+fn move_existing_file_to_trash(path: &Path) -> Result<(), String> {
+    cp_handlers::move_existing_file_to_trash_as("ln", path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_path_walks_up_to_common_ancestor() {
+        // 共通の祖先まで `..` で遡ってからリンク元へ下る相対パスになることを確認する。
+        assert_eq!(
+            relative_path(Path::new("/work/src/lib.rs"), Path::new("/work/out/links")),
+            PathBuf::from("../../src/lib.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("/work/a.txt"), Path::new("/work")),
+            PathBuf::from("a.txt")
+        );
+        assert_eq!(
+            relative_path(Path::new("/work"), Path::new("/work")),
+            PathBuf::from(".")
+        );
+    }
+}
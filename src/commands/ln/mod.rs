@@ -0,0 +1,91 @@
+pub mod args;
+pub mod handlers;
+
+use crate::config::Config;
+use crate::notifications::{CommandKind, CommandResultCounter};
+use crate::operands::{Destination, Operands};
+use args::Args;
+use handlers::ProcessContext;
+use std::fs;
+use std::path::Path;
+
+/// ln コマンド全体を実行し、各リンクの作成結果に応じて終了コードを決定する。
+///
+/// `-t` 指定時は `args.files` をすべてリンク元とし、それ以外は末尾をリンク名として扱う。
+/// オペランドが1つだけの場合は GNU と同じくカレントディレクトリへ同じ名前のリンクを作成する。
+pub fn run(args: Args, config: Config) -> i32 {
+    let mut exit_code = 0;
+    let mut counter = CommandResultCounter::new(CommandKind::Ln);
+    let context = ProcessContext::new(args, config);
+    let args = &context.args;
+    let links_into_current_dir = args.files.len() == 1 && !args.no_target_directory;
+    let target_directory = args
+        .target_directory
+        .as_deref()
+        .or(links_into_current_dir.then_some("."));
+    let operands = match Operands::parse(
+        "ln",
+        &args.files,
+        target_directory,
+        args.no_target_directory || treats_link_name_as_file(args),
+    ) {
+        Ok(operands) => operands,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return 1;
+        }
+    };
+    let sources = operands.sources;
+
+    let destination = match operands.destination("ln") {
+        Ok(destination) => destination,
+        Err(msg) => {
+            eprintln!("{msg}");
+            counter.record_failures(sources.len());
+            if context.config.notify.macos_notify {
+                counter.notify();
+            }
+            return 1;
+        }
+    };
+
+    for source in sources {
+        if let Err(msg) = process_source(source, &destination, &context) {
+            eprintln!("{msg}");
+            exit_code = 1;
+            counter.record_failure();
+        } else {
+            counter.record_success();
+        }
+    }
+
+    if context.config.notify.macos_notify {
+        counter.notify();
+    }
+
+    exit_code
+}
+
+/// `-n` 指定時、リンク名がシンボリックリンクなら、リンク先がディレクトリでも `-T` と同じく扱う。
+fn treats_link_name_as_file(args: &Args) -> bool {
+    if !args.no_dereference || args.target_directory.is_some() {
+        return false;
+    }
+    matches!(
+        args.files.as_slice(),
+        [_, link_name] if fs::symlink_metadata(link_name)
+            .is_ok_and(|meta| meta.file_type().is_symlink())
+    )
+}
+
+/// 単一のリンク元に対して検証と作成を行う。
+fn process_source(
+    source: &str,
+    destination: &Destination,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    // リンク自体はディレクトリにならないため、末尾が `/` の存在しないリンク名はエラーにする。
+    let link_name = destination.final_target("ln", Path::new(source), false)?;
+    let task = handlers::validate(source, &link_name, context)?;
+    handlers::execute(&task, context)
+}
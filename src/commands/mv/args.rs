@@ -1,3 +1,6 @@
+use crate::backup::{self, BackupControl};
+use crate::update::UpdateMode;
+use crate::verify::HashAlgorithm;
 use clap::Parser;
 
 #[derive(Parser, Debug)]
@@ -12,7 +15,85 @@ pub struct Args {
     #[arg(short = 'n')]
     pub no_clobber: bool,
 
-    /// Files to move (source and target)
-    #[arg(required = true, num_args = 2..)]
+    /// Atomically swap each source with its destination; both must exist
+    #[arg(long = "exchange", conflicts_with = "no_clobber")]
+    pub exchange: bool,
+
+    /// Merge a source directory into an existing destination directory entry by entry
+    #[arg(long = "merge", conflicts_with = "exchange")]
+    pub merge: bool,
+
+    /// Keep each overwritten destination as a backup beside it instead of trashing it (`-b` means `existing`)
+    #[arg(
+        short = 'b',
+        long = "backup",
+        value_name = "CONTROL",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "existing",
+        conflicts_with = "no_clobber"
+    )]
+    pub backup: Option<BackupControl>,
+
+    /// Override the usual backup suffix (`~`)
+    #[arg(short = 'S', long = "suffix", value_name = "SUFFIX", value_parser = backup::parse_suffix)]
+    pub suffix: Option<String>,
+
+    /// Replace existing destinations only when older (`-u`), never (`none`), always (`all`) or when their content differs (`content`)
+    #[arg(
+        short = 'u',
+        long = "update",
+        value_name = "WHEN",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = UpdateMode::All,
+        default_missing_value = "older"
+    )]
+    pub update: UpdateMode,
+
+    /// Overwrite targets with uncommitted git changes when `[git] protect_dirty` is enabled
+    #[arg(long = "allow-dirty")]
+    pub allow_dirty: bool,
+
+    /// Confirm trashing an existing target above the `[limits]` settings by echoing its exact file count
+    #[arg(long = "yes-i-mean-it", value_name = "COUNT")]
+    pub yes_i_mean_it: Option<u64>,
+
+    /// When moving across devices, hash each file while copying and re-read the copy before trashing the source
+    #[arg(
+        long = "verify",
+        value_name = "ALGO",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "blake3"
+    )]
+    pub verify: Option<HashAlgorithm>,
+
+    /// Explain what is being done, including sources skipped by `--update`
+    #[arg(short = 'v', long = "verbose")]
+    pub verbose: bool,
+
+    /// Report files and bytes moved on stderr (a live line on a terminal, JSON records otherwise)
+    #[arg(long = "progress")]
+    pub progress: bool,
+
+    /// Move all sources into DIRECTORY
+    #[arg(
+        short = 't',
+        long = "target-directory",
+        value_name = "DIRECTORY",
+        conflicts_with = "no_target_directory"
+    )]
+    pub target_directory: Option<String>,
+
+    /// Treat the destination as a normal file even if it is an existing directory
+    #[arg(short = 'T', long = "no-target-directory")]
+    pub no_target_directory: bool,
+
+    /// Files to move (sources, then the target unless `-t` is given)
+    #[arg(required = true, num_args = 1..)]
     pub files: Vec<String>,
 }
@@ -0,0 +1,218 @@
+use crate::progress::Progress;
+use crate::verify::{self, HashAlgorithm};
+use crate::walk::{Entry, Visit, Visitor, Walker};
+use filetime::FileTime;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// rename できない別デバイスへの移動のため、ソースを属性ごと複製する。
+///
+/// 通常ファイルは `verify` 指定時に読み込みながらハッシュを計算し、fsync 後に読み直して照合する。
+/// ディレクトリのパーミッションと時刻は配下の複製完了後に適用する。
+/// 複製したディレクトリ以外のエントリは `progress` へ報告する。
+pub fn copy_tree(
+    source: &Path,
+    destination: &Path,
+    verify: Option<HashAlgorithm>,
+    progress: &Progress,
+) -> Result<(), String> {
+    let mut mirror = TreeMirror {
+        source,
+        destination,
+        verify,
+    };
+    Walker::new(source).progress(progress).run(&mut mirror)
+}
+
+/// 走査したエントリを移動先の同じ相対位置へ複製するビジター。
+struct TreeMirror<'a> {
+    source: &'a Path,
+    destination: &'a Path,
+    verify: Option<HashAlgorithm>,
+}
+
+impl TreeMirror<'_> {
+    fn target_for(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(self.source) {
+            Ok(relative) if !relative.as_os_str().is_empty() => self.destination.join(relative),
+            _ => self.destination.to_path_buf(),
+        }
+    }
+}
+
+impl Visitor for TreeMirror<'_> {
+    type Error = String;
+
+    fn enter_dir(&mut self, entry: &Entry<'_>) -> Result<Visit, String> {
+        let target = self.target_for(entry.path);
+        fs::create_dir(&target).map_err(|e| copy_error(entry.path, &target, &e))?;
+        Ok(Visit::Continue)
+    }
+
+    fn leave_dir(&mut self, entry: &Entry<'_>) -> Result<(), String> {
+        let target = self.target_for(entry.path);
+        fs::set_permissions(&target, entry.metadata.permissions())
+            .map_err(|e| copy_error(entry.path, &target, &e))?;
+        copy_times(entry, &target)
+    }
+
+    fn visit(&mut self, entry: &Entry<'_>) -> Result<Visit, String> {
+        let target = self.target_for(entry.path);
+        let file_type = entry.metadata.file_type();
+        if file_type.is_symlink() {
+            copy_symlink(&entry.pinned_path(), &target)
+                .map_err(|e| copy_error(entry.path, &target, &e))?;
+        } else if file_type.is_file() {
+            copy_file(entry, &target, self.verify)?;
+        } else {
+            return Err(format!(
+                "mv: cannot move '{}' across devices: unsupported file type",
+                entry.path.display()
+            ));
+        }
+        copy_times(entry, &target)?;
+        Ok(Visit::Continue)
+    }
+
+    fn walk_error(&mut self, path: &Path, err: io::Error) -> String {
+        copy_error(path, &self.target_for(path), &err)
+    }
+}
+
+/// ソースのアクセス時刻と更新時刻を複製先へ適用する。
+fn copy_times(entry: &Entry<'_>, target: &Path) -> Result<(), String> {
+    let atime = FileTime::from_last_access_time(entry.metadata);
+    let mtime = FileTime::from_last_modification_time(entry.metadata);
+    filetime::set_symlink_file_times(target, atime, mtime)
+        .map_err(|e| copy_error(entry.path, target, &e))
+}
+
+/// 通常ファイルの内容とパーミッションを複製し、必要に応じて検証する。
+///
+/// ソースは走査中に開いた親ディレクトリからリンクを辿らずに開く。
+fn copy_file(
+    entry: &Entry<'_>,
+    destination: &Path,
+    verify: Option<HashAlgorithm>,
+) -> Result<(), String> {
+    let (source, meta) = (entry.path, entry.metadata);
+    let mut source_file = entry
+        .open_file()
+        .map_err(|e| copy_error(source, destination, &e))?;
+    let mut destination_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(destination)
+        .map_err(|e| copy_error(source, destination, &e))?;
+
+    let Some(algorithm) = verify else {
+        io::copy(&mut source_file, &mut destination_file)
+            .map_err(|e| copy_error(source, destination, &e))?;
+        return fs::set_permissions(destination, meta.permissions())
+            .map_err(|e| copy_error(source, destination, &e));
+    };
+
+    let (_, expected) = verify::copy_hashed(&mut source_file, &mut destination_file, algorithm)
+        .map_err(|e| copy_error(source, destination, &e))?;
+    destination_file
+        .sync_all()
+        .map_err(|e| copy_error(source, destination, &e))?;
+    fs::set_permissions(destination, meta.permissions())
+        .map_err(|e| copy_error(source, destination, &e))?;
+    verify::verify_file(destination, &expected).map_err(|e| {
+        format!(
+            "mv: verification of '{}' to '{}' failed: {}",
+            source.display(),
+            destination.display(),
+            e
+        )
+    })
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, destination: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, destination)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, _destination: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("cannot copy symbolic link '{}'", source.display()),
+    ))
+}
+
+fn copy_error(source: &Path, destination: &Path, err: &io::Error) -> String {
+    format!(
+        "mv: cannot copy '{}' to '{}': {}",
+        source.display(),
+        destination.display(),
+        err
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_tree_replicates_files_links_and_modes_with_verification() {
+        // ファイル・リンク・パーミッション・時刻を保ったまま検証付きで複製することを確認する。
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("nested").join("data.txt"), "payload").unwrap();
+        fs::set_permissions(
+            source.join("nested").join("data.txt"),
+            fs::Permissions::from_mode(0o640),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink("nested/data.txt", source.join("link")).unwrap();
+        let mtime = FileTime::from_unix_time(1_600_000_000, 0);
+        filetime::set_file_mtime(source.join("nested"), mtime).unwrap();
+        let destination = temp_dir.path().join("destination");
+
+        copy_tree(
+            &source,
+            &destination,
+            Some(HashAlgorithm::Sha256),
+            &Progress::disabled(),
+        )
+        .unwrap();
+
+        let copied = destination.join("nested").join("data.txt");
+        assert_eq!(fs::read_to_string(&copied).unwrap(), "payload");
+        assert_eq!(
+            fs::metadata(&copied).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+        assert_eq!(
+            fs::read_link(destination.join("link")).unwrap(),
+            Path::new("nested/data.txt")
+        );
+        assert_eq!(
+            FileTime::from_last_modification_time(
+                &fs::metadata(destination.join("nested")).unwrap()
+            ),
+            mtime
+        );
+    }
+
+    #[test]
+    fn copy_tree_refuses_existing_destination() {
+        // 既存パスへは書き込まず、上書きを防ぐことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source.txt");
+        let destination = temp_dir.path().join("destination.txt");
+        fs::write(&source, "new").unwrap();
+        fs::write(&destination, "old").unwrap();
+
+        assert!(copy_tree(&source, &destination, None, &Progress::disabled()).is_err());
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "old");
+    }
+}
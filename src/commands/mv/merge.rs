@@ -0,0 +1,400 @@
+use super::{ProcessContext, move_entry, remove_staged_copy};
+use crate::dirfd::{Dir, Pinned, RenameMode};
+use crate::limits;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// マージ中の一時退避ディレクトリの名前の接頭辞。実行ごとに `<接頭辞><pid>-<番号>` で作成する。
+const STAGING_DIR_PREFIX: &str = ".safecmd-mv-merge.";
+
+/// 一時退避ディレクトリであることを示すマーカーファイルの名前。
+///
+/// 中断後の再実行では、このマーカーを持つディレクトリだけを前回の一時退避先として扱う。
+const STAGING_MARKER: &str = "marker";
+
+/// 退避したエントリを元の名前のまま置く、一時退避ディレクトリ内のサブディレクトリの名前。
+const STAGED_ENTRIES: &str = "entries";
+
+/// `--merge` 指定時、既存ディレクトリ `target_pin` へソースディレクトリ `source_pin` の中身を移す。
+///
+/// 衝突しないエントリはそのまま rename し、衝突するファイルは通常の移動と同じく既存側を
+/// ゴミ箱へ退避してから置き換える（`-n` ではスキップする）。両方ディレクトリなら配下を再帰的に
+/// マージし、空になったソースのディレクトリは最後に削除する。
+/// 一部のエントリを移動できなくても残りの処理は続け、失敗があればエラーを返す。
+pub(super) fn merge_into(
+    source_pin: &Pinned,
+    target_pin: &Pinned,
+    source_path: &Path,
+    final_target: &Path,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    ensure_not_into_itself(source_path, final_target)?;
+    let source_dir = source_pin
+        .dir
+        .open_dir(&source_pin.name)
+        .map_err(|e| access_error(source_path, &e))?;
+    let target_dir = target_pin
+        .dir
+        .open_dir(&target_pin.name)
+        .map_err(|e| access_error(final_target, &e))?;
+
+    let mut merge = Merge {
+        context,
+        failed: false,
+    };
+    merge.merge_dir(&source_dir, &target_dir, source_path, final_target);
+    if merge.failed {
+        return Err(format!(
+            "mv: cannot merge '{}' into '{}': some entries were not moved",
+            source_path.display(),
+            final_target.display()
+        ));
+    }
+    remove_emptied_dir(&source_pin.dir, &source_pin.name, source_path)
+}
+
+/// マージ処理中の状態。エントリごとのエラーは即座に出力し、失敗の有無だけを保持する。
+struct Merge<'a> {
+    context: &'a ProcessContext,
+    failed: bool,
+}
+
+impl Merge<'_> {
+    /// 開いたソース・移動先ディレクトリの組について、直下のエントリを順にマージする。
+    ///
+    /// 衝突するエントリの一時退避先は、移動先ディレクトリ内に最初に必要になった時点で作成し、
+    /// 処理後に空であれば削除する。
+    fn merge_dir(&mut self, source: &Dir, target: &Dir, source_path: &Path, target_path: &Path) {
+        self.recover_staged(source, target, source_path, target_path);
+        let names = match source.entry_names() {
+            Ok(names) => names,
+            Err(e) => return self.report(access_error(source_path, &e)),
+        };
+        let mut staging = None;
+        for name in names {
+            if let Err(msg) = self.merge_entry(
+                source,
+                target,
+                &name,
+                source_path,
+                target_path,
+                &mut staging,
+            ) {
+                self.report(msg);
+            }
+        }
+        if let Some(staging) = staging {
+            staging.remove(target);
+        }
+    }
+
+    /// ソース直下のエントリ `name` を移動先ディレクトリへマージする。
+    fn merge_entry(
+        &mut self,
+        source: &Dir,
+        target: &Dir,
+        name: &OsStr,
+        source_path: &Path,
+        target_path: &Path,
+        staging: &mut Option<Staging>,
+    ) -> Result<(), String> {
+        let entry_source = source_path.join(name);
+        let entry_target = target_path.join(name);
+        let source_meta = source
+            .symlink_metadata(name)
+            .map_err(|e| access_error(&entry_source, &e))?;
+        let target_meta = match target.symlink_metadata(name) {
+            Ok(meta) => Some(meta),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(access_error(&entry_target, &e)),
+        };
+
+        if source_meta.is_dir() && target_meta.as_ref().is_some_and(fs::Metadata::is_dir) {
+            let sub_source = source
+                .open_dir(name)
+                .map_err(|e| access_error(&entry_source, &e))?;
+            let sub_target = target
+                .open_dir(name)
+                .map_err(|e| access_error(&entry_target, &e))?;
+            self.merge_dir(&sub_source, &sub_target, &entry_source, &entry_target);
+            return remove_emptied_dir(source, name, &entry_source);
+        }
+        if self.context.args.no_clobber && target_meta.is_some() {
+            return Ok(());
+        }
+
+        let pin = |dir: &Dir, path: &Path| {
+            dir.try_clone()
+                .map(|dir| Pinned {
+                    dir,
+                    name: name.to_owned(),
+                })
+                .map_err(|e| access_error(path, &e))
+        };
+        let source_pin = pin(source, &entry_source)?;
+        let target_pin = pin(target, &entry_target)?;
+        let scanned = if self.context.progress.is_enabled() {
+            limits::scan(&source_pin.entry_path()).ok()
+        } else {
+            None
+        };
+        let staging = match staging {
+            Some(staging) => staging,
+            None => staging.insert(Staging::create(target).map_err(|e| {
+                format!(
+                    "mv: cannot move '{}' to '{}': failed to create staging directory: {}",
+                    entry_source.display(),
+                    entry_target.display(),
+                    e
+                )
+            })?),
+        };
+        let stage = staging
+            .slot(name)
+            .map_err(|e| access_error(&entry_target, &e))?;
+        move_entry(
+            &source_pin,
+            &target_pin,
+            &stage,
+            &entry_source,
+            &entry_target,
+            scanned,
+            self.context,
+        )
+    }
+
+    /// 前回の中断で移動先に残った一時退避ディレクトリから、退避したエントリを片付ける。
+    ///
+    /// マーカーを持つ一時退避ディレクトリだけを対象にし、同じ接頭辞の利用者のエントリには触れない。
+    /// 同一デバイスの rename で退避したものは、移動先が空いていれば配置を完了し、
+    /// 既存ターゲットが残っていればソースへ戻して改めてマージさせる。
+    /// 別デバイスへの複製はソースが残っているため削除する。
+    fn recover_staged(
+        &mut self,
+        source: &Dir,
+        target: &Dir,
+        source_path: &Path,
+        target_path: &Path,
+    ) {
+        let Ok(names) = target.entry_names() else {
+            return;
+        };
+        for name in names {
+            let Some(staging) = Staging::open_leftover(target, &name) else {
+                continue;
+            };
+            let staged_names = staging.entries.entry_names().unwrap_or_default();
+            for original in staged_names {
+                let recovered = if !same_device(source, target) {
+                    recover_copied(source, &staging.entries, &original)
+                } else if target.contains(&original) {
+                    staging
+                        .entries
+                        .rename(&original, source, &original, RenameMode::NoReplace)
+                } else {
+                    staging
+                        .entries
+                        .rename(&original, target, &original, RenameMode::NoReplace)
+                };
+                if let Err(e) = recovered {
+                    self.report(format!(
+                        "mv: cannot resume merge of '{}' left at '{}': {}",
+                        source_path.join(&original).display(),
+                        target_path
+                            .join(&name)
+                            .join(STAGED_ENTRIES)
+                            .join(&original)
+                            .display(),
+                        e
+                    ));
+                }
+            }
+            staging.remove(target);
+        }
+    }
+
+    fn report(&mut self, msg: String) {
+        eprintln!("{msg}");
+        self.failed = true;
+    }
+}
+
+/// 別デバイスへの複製途中で残った一時退避先を、ソースが残っている場合に限り削除する。
+fn recover_copied(source: &Dir, entries: &Dir, original: &OsStr) -> io::Result<()> {
+    if !source.contains(original) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "source of the staged copy no longer exists",
+        ));
+    }
+    remove_staged_copy(entries, original);
+    Ok(())
+}
+
+/// 移動先ディレクトリ内に作成した、マージ中の一時退避ディレクトリ。
+///
+/// `<移動先>/.safecmd-mv-merge.<pid>-<番号>/` にマーカーファイルと `entries/` を置き、
+/// 退避したエントリは `entries/` の下に元の名前のまま置く。
+struct Staging {
+    name: OsString,
+    dir: Dir,
+    entries: Dir,
+}
+
+impl Staging {
+    /// `target` 直下にこの実行の一時退避ディレクトリを作成する。
+    ///
+    /// 中断時に再実行で見つけられるよう、エントリを退避する前にマーカーを置く。
+    fn create(target: &Dir) -> io::Result<Self> {
+        let process_id = std::process::id();
+        for attempt in 0..256 {
+            let name = OsString::from(format!("{STAGING_DIR_PREFIX}{process_id}-{attempt}"));
+            match target.create_dir(&name, 0o700) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+            let dir = target.open_dir(&name)?;
+            dir.create_file(OsStr::new(STAGING_MARKER), 0o600)?;
+            dir.create_dir(OsStr::new(STAGED_ENTRIES), 0o700)?;
+            let entries = dir.open_dir(OsStr::new(STAGED_ENTRIES))?;
+            return Ok(Self { name, dir, entries });
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "failed to allocate staging directory",
+        ))
+    }
+
+    /// `target` 直下の `name` が前回の実行で残った一時退避ディレクトリなら開く。
+    ///
+    /// 接頭辞が一致し、マーカーファイルを持つディレクトリだけを対象とする。
+    fn open_leftover(target: &Dir, name: &OsStr) -> Option<Self> {
+        if !name
+            .as_encoded_bytes()
+            .starts_with(STAGING_DIR_PREFIX.as_bytes())
+        {
+            return None;
+        }
+        let dir = target.open_dir(name).ok()?;
+        if !dir
+            .symlink_metadata(OsStr::new(STAGING_MARKER))
+            .is_ok_and(|meta| meta.is_file())
+        {
+            return None;
+        }
+        if !dir.contains(OsStr::new(STAGED_ENTRIES)) {
+            dir.create_dir(OsStr::new(STAGED_ENTRIES), 0o700).ok()?;
+        }
+        let entries = dir.open_dir(OsStr::new(STAGED_ENTRIES)).ok()?;
+        Some(Self {
+            name: name.to_owned(),
+            dir,
+            entries,
+        })
+    }
+
+    /// 元の名前 `name` のエントリを退避する場所を返す。
+    fn slot(&self, name: &OsStr) -> io::Result<Pinned> {
+        Ok(Pinned {
+            dir: self.entries.try_clone()?,
+            name: name.to_owned(),
+        })
+    }
+
+    /// 一時退避ディレクトリを削除する。退避したエントリが残っている場合はマーカーごと残す。
+    fn remove(self, target: &Dir) {
+        let _ = self
+            .dir
+            .remove_dir(OsStr::new(STAGED_ENTRIES))
+            .and_then(|()| self.dir.remove_file(OsStr::new(STAGING_MARKER)))
+            .and_then(|()| target.remove_dir(&self.name));
+    }
+}
+
+/// マージ対象がソース自身の配下にある場合はエラーにする。
+fn ensure_not_into_itself(source_path: &Path, final_target: &Path) -> Result<(), String> {
+    let (Ok(source), Ok(target)) = (source_path.canonicalize(), final_target.canonicalize()) else {
+        return Ok(());
+    };
+    if target.starts_with(&source) {
+        return Err(format!(
+            "mv: cannot move '{}' to a subdirectory of itself, '{}'",
+            source_path.display(),
+            final_target.display()
+        ));
+    }
+    Ok(())
+}
+
+/// 移動し終えたソースのディレクトリを削除する。スキップや失敗で中身が残る場合はそのままにする。
+fn remove_emptied_dir(dir: &Dir, name: &OsStr, path: &Path) -> Result<(), String> {
+    match dir.remove_dir(name) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::DirectoryNotEmpty => Ok(()),
+        Err(e) => Err(format!("mv: cannot remove '{}': {}", path.display(), e)),
+    }
+}
+
+/// 2つの開いたディレクトリが同じデバイス上にあるかを返す。
+fn same_device(a: &Dir, b: &Dir) -> bool {
+    #[cfg(unix)]
+    {
+        match (a.metadata(), b.metadata()) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev(),
+            _ => true,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        true
+    }
+}
+
+fn access_error(path: &Path, err: &io::Error) -> String {
+    format!("mv: cannot access '{}': {}", path.display(), err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn open_leftover_requires_marker() {
+        // 接頭辞が一致してもマーカーのないディレクトリは一時退避先として扱わないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let target = Dir::open(temp_dir.path()).unwrap();
+        let user_dir = OsString::from(format!("{STAGING_DIR_PREFIX}notes"));
+        target.create_dir(&user_dir, 0o755).unwrap();
+
+        assert!(Staging::open_leftover(&target, &user_dir).is_none());
+
+        let staging = Staging::create(&target).unwrap();
+        let name = staging.name.clone();
+        drop(staging);
+        assert!(Staging::open_leftover(&target, &name).is_some());
+    }
+
+    #[test]
+    fn staging_keeps_long_names_and_is_removed_when_empty() {
+        // 退避先は元の名前のまま置けるため長い名前でも失敗せず、空になれば削除されることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let target = Dir::open(temp_dir.path()).unwrap();
+        let long_name = OsString::from("n".repeat(255));
+        let staging = Staging::create(&target).unwrap();
+        let name = staging.name.clone();
+
+        let slot = staging.slot(&long_name).unwrap();
+        slot.dir.create_file(&slot.name, 0o600).unwrap();
+        slot.dir.remove_file(&slot.name).unwrap();
+        staging.remove(&target);
+
+        assert!(!target.contains(&name));
+    }
+}
@@ -1,12 +1,82 @@
-use crate::config::Config;
+use crate::backup::{self, BackupPolicy};
+use crate::config::{BackupScopes, Config, ScopeSet};
+use crate::dirfd::{Dir, Pinned, RenameMode};
+use crate::git::Repositories;
+use crate::limits::{self, TreeStats};
+use crate::operands::{Destination, Operands};
+use crate::progress::Progress;
+use crate::staging;
+use crate::update::{SkipReason, UpdateMode};
+use args::Args;
+use std::ffi::{OsStr, OsString};
 use std::fs;
+use std::io;
 #[cfg(unix)]
 use std::os::unix::fs::MetadataExt;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 #[cfg(target_os = "macos")]
 use trash::macos::{DeleteMethod, TrashContextExtMacos};
 
 pub mod args;
+pub mod cross_device;
+mod merge;
+
+/// mv 実行時に必要な引数と設定を保持するコンテキスト。
+pub struct ProcessContext {
+    pub args: Args,
+    pub config: Config,
+    /// 実行開始時に解決した許可スコープ。
+    pub scopes: ScopeSet,
+    /// 実行中に開いた git リポジトリ。`[git] protect_dirty` の判定に使い回す。
+    pub repositories: Repositories,
+    /// 実行開始時に解決した `[[backup.scopes]]`。
+    pub backup_scopes: BackupScopes,
+    pub progress: Progress,
+}
+
+impl ProcessContext {
+    /// mv 実行に必要な引数と設定をまとめたコンテキストを生成する。
+    pub fn new(args: Args, config: Config) -> Self {
+        let progress = Progress::new("mv", args.progress);
+        let scopes = config.scope_set();
+        let repositories = Repositories::new(&config.git);
+        let backup_scopes = config.backup.resolve_scopes();
+        Self {
+            args,
+            config,
+            scopes,
+            repositories,
+            backup_scopes,
+            progress,
+        }
+    }
+
+    /// `target` の上書きに適用するバックアップ方針を決める。
+    pub fn backup_policy(&self, target: &Path) -> BackupPolicy {
+        BackupPolicy::resolve(
+            self.args.backup,
+            self.args.suffix.as_deref(),
+            &self.config.backup,
+            &self.backup_scopes,
+            target,
+        )
+    }
+}
+
+/// ソースを一時退避先へ退避した方法を表す。
+enum StagedSource {
+    /// 同一デバイス内の rename で退避した。
+    Renamed,
+    /// 別デバイスのため複製した。ソースは移動完了後にゴミ箱へ移動する。
+    Copied,
+}
+
+/// 最終ターゲットへの配置結果を表す。
+enum Placement {
+    Moved,
+    /// `-n` 指定時、最終ターゲットが既に存在したため配置しなかった。
+    SkippedExisting,
+}
 
 /// 既存ターゲットの解決結果に応じた最終移動アクションを表す。
 enum DestinationAction {
@@ -15,37 +85,73 @@ enum DestinationAction {
 }
 
 /// mv コマンド全体を実行し、各ソースの処理結果に応じて終了コードを決定する。
-pub fn run(
-    sources: Vec<String>,
-    target: String,
-    _force: bool,
-    no_clobber: bool,
-    config: Config,
-) -> i32 {
-    let target_path = Path::new(&target);
+///
+/// `-t` 指定時は `args.files` をすべて移動元とし、それ以外は末尾を移動先として扱う。
+pub fn run(args: Args, config: Config) -> i32 {
+    let context = ProcessContext::new(args, config);
+    let operands = Operands::parse(
+        "mv",
+        &context.args.files,
+        context.args.target_directory.as_deref(),
+        context.args.no_target_directory,
+    );
+    let (sources, destination) =
+        match operands.and_then(|operands| Ok((operands.sources, operands.destination("mv")?))) {
+            Ok(resolved) => resolved,
+            Err(msg) => {
+                eprintln!("{msg}");
+                return 1;
+            }
+        };
     let mut exit_code = 0;
 
-    if sources.len() > 1 && !target_path.is_dir() {
-        eprintln!("mv: target '{target}' is not a directory");
-        return 1;
-    }
-
-    for source in &sources {
-        if let Err(msg) = process_source(source, target_path, no_clobber, &config) {
+    let mut scanned = prescan_for_progress(sources, &context).into_iter();
+    for source in sources {
+        if let Err(msg) = process_source(source, &destination, scanned.next().flatten(), &context) {
             eprintln!("{msg}");
             exit_code = 1;
         }
     }
 
+    context.progress.finish();
     exit_code
 }
 
+/// `--progress` 指定時、全ソースを事前走査して総量を設定し、ソースごとの集計結果を返す。
+///
+/// 走査できないソースは `None` とし、総量に含めない。
+fn prescan_for_progress(sources: &[String], context: &ProcessContext) -> Vec<Option<TreeStats>> {
+    if !context.progress.is_enabled() {
+        return Vec::new();
+    }
+    let scanned: Vec<_> = sources
+        .iter()
+        .map(|source| {
+            let source_path = Path::new(source);
+            if context.scopes.contains(source_path) {
+                limits::scan(source_path).ok()
+            } else {
+                None
+            }
+        })
+        .collect();
+    let mut totals = TreeStats::default();
+    for stats in scanned.iter().flatten() {
+        totals.files += stats.files;
+        totals.bytes += stats.bytes;
+    }
+    context.progress.set_totals(totals);
+    scanned
+}
+
 /// 単一ソースの移動を検証付きで実行する。
+///
+/// `scanned` は `--progress` 用に走査済みのソースの集計結果で、rename で移動した場合にまとめて報告する。
 fn process_source(
     source: &str,
-    target_path: &Path,
-    no_clobber: bool,
-    config: &Config,
+    destination: &Destination,
+    scanned: Option<TreeStats>,
+    context: &ProcessContext,
 ) -> Result<(), String> {
     let source_path = Path::new(source);
 
@@ -60,29 +166,195 @@ fn process_source(
         }
     })?;
 
-    if !config.is_path_allowed(source_path) {
+    if !context.scopes.contains(source_path) {
         return Err(format!(
             "mv: cannot move '{}': path is outside allowed scope",
             source_path.display()
         ));
     }
 
-    let final_target = resolve_final_target(source_path, target_path)?;
-    validate_target_scope(&final_target, config)?;
-    if no_clobber && path_entry_exists(&final_target) {
+    let final_target = destination.final_target("mv", source_path, source_meta.is_dir())?;
+    validate_target_scope(&final_target, &context.scopes)?;
+    let merges = context.args.merge
+        && source_meta.is_dir()
+        && fs::symlink_metadata(&final_target).is_ok_and(|meta| meta.is_dir());
+    // 最終的な上書き可否は finalize_move の RENAME_NOREPLACE で判定し、ここでは無駄な退避を省く。
+    if context.args.no_clobber && !merges && path_entry_exists(&final_target) {
         return Ok(());
     }
     ensure_not_same_file(source_path, &final_target)?;
 
-    let staged_source = stage_source_for_destination(source_path, &final_target)?;
-    if let Err(e) = finalize_move(&staged_source, source_path, &final_target, &source_meta) {
-        rollback_staged_source(&staged_source, source_path);
-        return Err(e);
+    let source_pin = context
+        .scopes
+        .open_parent(source_path)
+        .map_err(|e| format!("mv: cannot move '{}': {}", source_path.display(), e))?;
+    let target_pin = context
+        .scopes
+        .open_parent(&final_target)
+        .map_err(|e| format!("mv: cannot move to '{}': {}", final_target.display(), e))?;
+    if context.args.exchange {
+        exchange_entries(&source_pin, &target_pin, source_path, &final_target)?;
+        if let Some(stats) = scanned {
+            context.progress.advance_by(source_path, stats);
+        }
+        return Ok(());
+    }
+    // 判定後にディレクトリが差し替えられていれば、マージせず通常の移動として扱う。
+    if merges
+        && target_pin
+            .dir
+            .symlink_metadata(&target_pin.name)
+            .is_ok_and(|meta| meta.is_dir())
+    {
+        return merge::merge_into(
+            &source_pin,
+            &target_pin,
+            source_path,
+            &final_target,
+            context,
+        );
     }
 
+    let stage = Pinned {
+        dir: target_pin
+            .dir
+            .try_clone()
+            .map_err(|e| format!("mv: cannot move to '{}': {}", final_target.display(), e))?,
+        name: build_staging_name(&target_pin.dir, &final_target)?,
+    };
+    move_entry(
+        &source_pin,
+        &target_pin,
+        &stage,
+        source_path,
+        &final_target,
+        scanned,
+        context,
+    )
+}
+
+/// ソースを一時退避先 `stage` へ退避してから最終ターゲットへ配置する。
+///
+/// `stage` は最終ターゲットと同じファイルシステム上の、まだ存在しないエントリとする。
+/// 配置できなかった場合は一時退避したソースを元の位置へ巻き戻す。`--merge` の各エントリの移動にも使う。
+/// `--update` の条件を満たさない既存ターゲットがあれば何もしない。
+fn move_entry(
+    source_pin: &Pinned,
+    target_pin: &Pinned,
+    stage: &Pinned,
+    source_path: &Path,
+    final_target: &Path,
+    scanned: Option<TreeStats>,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    let source_meta = source_pin
+        .dir
+        .symlink_metadata(&source_pin.name)
+        .map_err(|e| format!("mv: cannot access '{}': {}", source_path.display(), e))?;
+    if let Some(reason) =
+        update_skip_reason(source_pin, target_pin, source_path, final_target, context)?
+    {
+        if context.args.verbose {
+            println!(
+                "skipped '{}' -> '{}' ({reason})",
+                source_path.display(),
+                final_target.display()
+            );
+        }
+        return Ok(());
+    }
+    let staged_source =
+        stage_source_for_destination(source_pin, stage, source_path, final_target, context)?;
+    match finalize_move(
+        stage,
+        target_pin,
+        source_path,
+        final_target,
+        &source_meta,
+        context,
+    ) {
+        Ok(Placement::Moved) => {}
+        Ok(Placement::SkippedExisting) => {
+            rollback_staged_source(&staged_source, stage, source_pin);
+            return Ok(());
+        }
+        Err(e) => {
+            rollback_staged_source(&staged_source, stage, source_pin);
+            return Err(e);
+        }
+    }
+
+    match staged_source {
+        StagedSource::Renamed => {
+            if let Some(stats) = scanned {
+                context.progress.advance_by(source_path, stats);
+            }
+        }
+        StagedSource::Copied => {
+            trash_pinned_entry(source_pin).map_err(|e| {
+                format!(
+                    "mv: copied '{}' to '{}' but failed to move source to trash: {e}",
+                    source_path.display(),
+                    final_target.display()
+                )
+            })?;
+        }
+    }
+    if context.args.verbose {
+        println!(
+            "renamed '{}' -> '{}'",
+            source_path.display(),
+            final_target.display()
+        );
+    }
     Ok(())
 }
 
+/// `--update` の条件で既存ターゲットを置き換えない場合に、その理由を返す。
+fn update_skip_reason(
+    source_pin: &Pinned,
+    target_pin: &Pinned,
+    source_path: &Path,
+    final_target: &Path,
+    context: &ProcessContext,
+) -> Result<Option<SkipReason>, String> {
+    if context.args.update == UpdateMode::All {
+        return Ok(None);
+    }
+    let target_meta = match target_pin.dir.symlink_metadata(&target_pin.name) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(format!(
+                "mv: cannot access '{}': {}",
+                final_target.display(),
+                e
+            ));
+        }
+    };
+    let source_meta = source_pin
+        .dir
+        .symlink_metadata(&source_pin.name)
+        .map_err(|e| format!("mv: cannot access '{}': {}", source_path.display(), e))?;
+    context
+        .args
+        .update
+        .skip_reason(
+            &source_pin.entry_path(),
+            &source_meta,
+            &target_pin.entry_path(),
+            &target_meta,
+        )
+        .map_err(|e| {
+            format!(
+                "mv: cannot compare '{}' with '{}': {}",
+                source_path.display(),
+                final_target.display(),
+                e
+            )
+        })
+}
+
 /// ソースと最終ターゲットが同一実体かを判定し、同一ならエラーにする。
 fn ensure_not_same_file(source_path: &Path, final_target: &Path) -> Result<(), String> {
     let target_meta = match fs::symlink_metadata(final_target) {
@@ -122,43 +394,87 @@ fn ensure_not_same_file(source_path: &Path, final_target: &Path) -> Result<(), S
 }
 
 /// 一時退避経由でターゲットへの最終移動を完了させる。
+///
+/// 退避先と最終ターゲットはどちらも開いた移動先の親ディレクトリ `target_pin` 直下で扱う。
+/// `-n` 指定時は `RENAME_NOREPLACE` で配置し、直前に作成されたターゲットも置き換えない。
 fn finalize_move(
-    staged_source: &Path,
+    stage: &Pinned,
+    target_pin: &Pinned,
     source_path: &Path,
     final_target: &Path,
     source_meta: &fs::Metadata,
-) -> Result<(), String> {
-    match handle_existing_target(source_path, final_target, source_meta)? {
-        DestinationAction::RenameDirectly => {}
-        DestinationAction::ReplaceEmptyDirectory => {
-            fs::remove_dir(final_target).map_err(|e| {
-                format!(
-                    "mv: cannot move '{}' to '{}': {}",
-                    source_path.display(),
-                    final_target.display(),
-                    e
-                )
-            })?;
-        }
-    }
-
-    fs::rename(staged_source, final_target).map_err(|e| {
+    context: &ProcessContext,
+) -> Result<Placement, String> {
+    let dir = &target_pin.dir;
+    let move_error = |e: io::Error| {
         format!(
             "mv: cannot move '{}' to '{}': {}",
             source_path.display(),
             final_target.display(),
             e
         )
-    })
+    };
+    if context.args.no_clobber {
+        return match stage
+            .dir
+            .rename(&stage.name, dir, &target_pin.name, RenameMode::NoReplace)
+        {
+            Ok(()) => Ok(Placement::Moved),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(Placement::SkippedExisting),
+            Err(e) => Err(move_error(e)),
+        };
+    }
+
+    match handle_existing_target(source_path, target_pin, final_target, source_meta, context)? {
+        DestinationAction::RenameDirectly => {}
+        DestinationAction::ReplaceEmptyDirectory => {
+            dir.remove_dir(&target_pin.name).map_err(move_error)?;
+        }
+    }
+
+    stage
+        .dir
+        .rename(&stage.name, dir, &target_pin.name, RenameMode::Replace)
+        .map(|()| Placement::Moved)
+        .map_err(move_error)
+}
+
+/// `--exchange` 指定時、ソースと最終ターゲットを `RENAME_EXCHANGE` で不可分に入れ替える。
+///
+/// どちらのエントリも残るため、既存ターゲットのゴミ箱への退避は行わない。
+fn exchange_entries(
+    source_pin: &Pinned,
+    target_pin: &Pinned,
+    source_path: &Path,
+    final_target: &Path,
+) -> Result<(), String> {
+    source_pin
+        .dir
+        .rename(
+            &source_pin.name,
+            &target_pin.dir,
+            &target_pin.name,
+            RenameMode::Exchange,
+        )
+        .map_err(|e| {
+            format!(
+                "mv: cannot exchange '{}' and '{}': {}",
+                source_path.display(),
+                final_target.display(),
+                e
+            )
+        })
 }
 
 /// 既存ターゲットの衝突解決として trash を実行する。
 fn handle_existing_target(
     source_path: &Path,
+    target_pin: &Pinned,
     final_target: &Path,
     source_meta: &fs::Metadata,
+    context: &ProcessContext,
 ) -> Result<DestinationAction, String> {
-    let target_meta = match fs::symlink_metadata(final_target) {
+    let target_meta = match target_pin.dir.symlink_metadata(&target_pin.name) {
         Ok(meta) => meta,
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
             return Ok(DestinationAction::RenameDirectly);
@@ -182,7 +498,7 @@ fn handle_existing_target(
 
     if target_meta.file_type().is_dir() {
         if source_meta.is_dir() {
-            let mut entries = fs::read_dir(final_target)
+            let mut entries = fs::read_dir(target_pin.entry_path())
                 .map_err(|e| format!("mv: cannot access '{}': {}", final_target.display(), e))?;
             if entries.next().is_some() {
                 return Err(format!(
@@ -200,65 +516,146 @@ fn handle_existing_target(
         ));
     }
 
-    move_existing_target_to_trash(final_target)?;
+    context
+        .repositories
+        .ensure_clean("mv", final_target, context.args.allow_dirty)?;
+    limits::ensure_within(
+        "mv",
+        final_target,
+        &context.config.limits,
+        context.args.yes_i_mean_it,
+    )?;
+    retire_existing_target(target_pin, final_target, context)?;
     Ok(DestinationAction::RenameDirectly)
 }
 
-/// 既存ターゲットをシステムのゴミ箱へ移動する。
-fn move_existing_target_to_trash(target: &Path) -> Result<(), String> {
+/// 上書きされる既存ターゲットを、バックアップ方針に従って退避する。
+///
+/// バックアップが有効なら同じディレクトリにハードリンク（できなければ rename）で残し、
+/// 続く rename で置き換える。無効ならゴミ箱へ移動する。
+fn retire_existing_target(
+    target: &Pinned,
+    final_target: &Path,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    let policy = context.backup_policy(final_target);
+    if !policy.makes_backup() {
+        return move_existing_target_to_trash(target);
+    }
+    backup::back_up(
+        &target.dir,
+        &target.name,
+        &policy,
+        &context.scopes,
+        |path| trash_path(path).map_err(|e| io::Error::other(e.to_string())),
+    )
+    .map(|_| ())
+    .map_err(|e| format!("mv: cannot back up '{}': {}", final_target.display(), e))
+}
+
+/// 既存ターゲットを、パスが差し替えられていないことを確認してシステムのゴミ箱へ移動する。
+fn move_existing_target_to_trash(target: &Pinned) -> Result<(), String> {
+    trash_pinned_entry(target)
+        .map_err(|e| format!("mv: failed to move existing file to trash: {e}"))
+}
+
+/// 開いた親ディレクトリ直下のエントリを、ディスクリプタ基準でステージングディレクトリへ移してからゴミ箱へ移動する。
+///
+/// 親ディレクトリのパスが差し替えられても範囲外のエントリへ届かないよう `staging::trash_entry`
+/// を経由し、ゴミ箱には元の場所を記録する。
+fn trash_pinned_entry(pinned: &Pinned) -> Result<(), String> {
+    staging::trash_entry(&pinned.dir, &pinned.name, |staged| {
+        trash_path(staged).map_err(|e| e.to_string())?;
+        let _ = staging::record_original_path(staged);
+        Ok(())
+    })
+}
+
+/// パスをシステムのゴミ箱へ移動する。
+fn trash_path(path: &Path) -> Result<(), trash::Error> {
     #[cfg(target_os = "macos")]
     {
         // Finder 経由の削除は権限ダイアログを誘発しうるため、テスト時も安定する実装を使う。
         let mut context = trash::TrashContext::new();
         context.set_delete_method(DeleteMethod::NsFileManager);
-        context
-            .delete(target)
-            .map_err(|e| format!("mv: failed to move existing file to trash: {e}"))
+        context.delete(path)
     }
 
     #[cfg(not(target_os = "macos"))]
     {
-        trash::delete(target).map_err(|e| format!("mv: failed to move existing file to trash: {e}"))
+        trash::delete(path)
     }
 }
 
-/// ソースを最終ターゲットの親ディレクトリへ一時退避する。
+/// ソースを最終ターゲットの親ディレクトリ `target_dir` の `staged_source` へ一時退避する。
+///
+/// 開いた親ディレクトリ間で `RENAME_NOREPLACE` により rename するため、退避先の名前を
+/// 検査後に横取りされても上書きしない。
+/// 別デバイスで rename できない場合はソースを残したまま複製し、`--verify` 指定時は検証する。
 fn stage_source_for_destination(
+    source_pin: &Pinned,
+    stage: &Pinned,
     source_path: &Path,
     final_target: &Path,
-) -> Result<PathBuf, String> {
-    let staged_source = build_staging_path(final_target)?;
-    fs::rename(source_path, &staged_source).map_err(|e| {
-        format!(
+    context: &ProcessContext,
+) -> Result<StagedSource, String> {
+    match source_pin.dir.rename(
+        &source_pin.name,
+        &stage.dir,
+        &stage.name,
+        RenameMode::NoReplace,
+    ) {
+        Ok(()) => Ok(StagedSource::Renamed),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = cross_device::copy_tree(
+                source_path,
+                &stage.entry_path(),
+                context.args.verify,
+                &context.progress,
+            ) {
+                remove_staged_copy(&stage.dir, &stage.name);
+                return Err(e);
+            }
+            Ok(StagedSource::Copied)
+        }
+        Err(e) => Err(format!(
             "mv: cannot move '{}' to '{}': {}",
             source_path.display(),
             final_target.display(),
             e
-        )
-    })?;
-    Ok(staged_source)
+        )),
+    }
 }
 
 /// 一時退避後の失敗時に元ソース位置への巻き戻しを試みる。
-fn rollback_staged_source(staged_source: &Path, source_path: &Path) {
-    let _ = fs::rename(staged_source, source_path);
+///
+/// 複製で退避した場合はソースが残っているため、複製を削除する。
+fn rollback_staged_source(staged_source: &StagedSource, stage: &Pinned, source_pin: &Pinned) {
+    match staged_source {
+        StagedSource::Renamed => {
+            let _ = stage.dir.rename(
+                &stage.name,
+                &source_pin.dir,
+                &source_pin.name,
+                RenameMode::NoReplace,
+            );
+        }
+        StagedSource::Copied => remove_staged_copy(&stage.dir, &stage.name),
+    }
 }
 
-/// ソースとターゲット指定から最終移動先を決定する。
-fn resolve_final_target(source_path: &Path, target_path: &Path) -> Result<PathBuf, String> {
-    if target_path.is_dir() {
-        let file_name = source_path
-            .file_name()
-            .ok_or_else(|| format!("mv: invalid source path: '{}'", source_path.display()))?;
-        Ok(target_path.join(file_name))
-    } else {
-        Ok(target_path.to_path_buf())
-    }
+/// 別デバイスへの複製で作成した一時退避先を削除する。
+fn remove_staged_copy(dir: &Dir, staged_source: &OsStr) {
+    let _ = match dir.symlink_metadata(staged_source) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(dir.entry_path(staged_source)),
+        Ok(_) => dir.remove_file(staged_source),
+        Err(_) => return,
+    };
 }
 
 /// 移動先パスが許可範囲内かを検証する。
-fn validate_target_scope(final_target: &Path, config: &Config) -> Result<(), String> {
-    if !config.is_path_allowed(final_target) {
+fn validate_target_scope(final_target: &Path, scopes: &ScopeSet) -> Result<(), String> {
+    if !scopes.contains(final_target) {
         return Err(format!(
             "mv: cannot move to '{}': path is outside allowed scope",
             final_target.display()
@@ -272,19 +669,12 @@ fn path_entry_exists(path: &Path) -> bool {
     fs::symlink_metadata(path).is_ok()
 }
 
-/// 最終ターゲットの親ディレクトリ配下に一時退避先を作る。
-fn build_staging_path(final_target: &Path) -> Result<PathBuf, String> {
-    let parent = final_target.parent().ok_or_else(|| {
-        format!(
-            "mv: cannot move to '{}': invalid destination path",
-            final_target.display()
-        )
-    })?;
-
+/// 最終ターゲットの親ディレクトリ `dir` 直下の一時退避先の名前を決める。
+fn build_staging_name(dir: &Dir, final_target: &Path) -> Result<OsString, String> {
     let process_id = std::process::id();
     for attempt in 0..256 {
-        let candidate = parent.join(format!(".safecmd-mv-stage-{process_id}-{attempt}"));
-        if !path_entry_exists(&candidate) {
+        let candidate = OsString::from(format!(".safecmd-mv-stage-{process_id}-{attempt}"));
+        if !dir.contains(&candidate) {
             return Ok(candidate);
         }
     }
@@ -294,3 +684,97 @@ fn build_staging_path(final_target: &Path) -> Result<PathBuf, String> {
         final_target.display()
     ))
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn trash_pinned_entry_refuses_parent_swapped_for_symlink() {
+        // 親ディレクトリを開いた後にパスをシンボリックリンクへ差し替えた場合、
+        // リンク先の同名エントリをゴミ箱へ移動せずに失敗することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let scope = temp_dir.path().join("scope");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(scope.join("dir")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        fs::write(scope.join("dir").join("existing.txt"), "inside").unwrap();
+        fs::write(outside.join("existing.txt"), "outside").unwrap();
+        let pinned = Pinned {
+            dir: Dir::open(&scope.join("dir")).unwrap(),
+            name: OsString::from("existing.txt"),
+        };
+
+        fs::rename(scope.join("dir"), scope.join("moved")).unwrap();
+        std::os::unix::fs::symlink(&outside, scope.join("dir")).unwrap();
+        let err = move_existing_target_to_trash(&pinned).unwrap_err();
+
+        assert!(err.contains("was replaced during the operation"), "{err}");
+        assert!(outside.join("existing.txt").exists());
+        assert!(scope.join("moved").join("existing.txt").exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn trash_pinned_entry_never_trashes_outside_entry_while_parent_is_swapped_concurrently() {
+        // 親ディレクトリのパスを別スレッドでシンボリックリンクと入れ替え続けても、
+        // リンク先の同名エントリをゴミ箱へ移動しないことを確認する。
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        let temp_dir = TempDir::new().unwrap();
+        let scope = temp_dir.path().join("scope");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(scope.join("dir")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        let dir = Dir::open(&scope.join("dir")).unwrap();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let swapper = {
+            let stop = Arc::clone(&stop);
+            let scope = scope.clone();
+            let outside = outside.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    fs::rename(scope.join("dir"), scope.join("moved")).unwrap();
+                    std::os::unix::fs::symlink(&outside, scope.join("dir")).unwrap();
+                    thread::yield_now();
+                    fs::remove_file(scope.join("dir")).unwrap();
+                    fs::rename(scope.join("moved"), scope.join("dir")).unwrap();
+                    thread::yield_now();
+                }
+            })
+        };
+
+        for i in 0..50 {
+            let name = OsString::from(format!("victim-{i}.txt"));
+            fs::write(dir.entry_path(&name), "inside").unwrap();
+            fs::write(outside.join(&name), "outside").unwrap();
+            let pinned = Pinned {
+                dir: dir.try_clone().unwrap(),
+                name,
+            };
+            let _ = move_existing_target_to_trash(&pinned);
+        }
+        stop.store(true, Ordering::Relaxed);
+        swapper.join().unwrap();
+
+        let root = temp_dir.path().canonicalize().unwrap();
+        let trashed: Vec<_> = trash::os_limited::list()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|item| item.original_parent.starts_with(&root))
+            .collect();
+        let _ = trash::os_limited::purge_all(trashed);
+        for i in 0..50 {
+            let name = format!("victim-{i}.txt");
+            assert_eq!(
+                fs::read_to_string(outside.join(&name)).unwrap(),
+                "outside",
+                "{name}"
+            );
+        }
+    }
+}
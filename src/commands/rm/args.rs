@@ -14,6 +14,21 @@ pub struct Args {
     /// Recursively remove directories
     #[arg(short = 'R', short_alias = 'r')]
     pub recursive: bool,
+    /// Trash paths with uncommitted git changes when `[git] protect_dirty` is enabled
+    #[arg(long = "allow-dirty")]
+    pub allow_dirty: bool,
+    /// Explain what is being done
+    #[arg(short = 'v', long = "verbose")]
+    pub verbose: bool,
+    /// Report what would be trashed without trashing anything
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+    /// Report files and bytes trashed on stderr (a live line on a terminal, JSON records otherwise)
+    #[arg(long = "progress")]
+    pub progress: bool,
+    /// Confirm trashing a tree above the `[limits]` settings by echoing its exact file count
+    #[arg(long = "yes-i-mean-it", value_name = "COUNT")]
+    pub yes_i_mean_it: Option<u64>,
     /// Paths to files or directories to trash
     pub path: Vec<PathBuf>,
 }
@@ -1,15 +1,35 @@
+use crate::config::ScopeSet;
+use crate::dirfd::Pinned;
+use crate::git::Repositories;
+use crate::progress::Progress;
+use crate::staging;
 use crate::{commands::rm::args::Args, config::Config};
+use std::io;
 use std::path::Path;
 
 pub struct ProcessContext {
     pub args: Args,
     pub config: Config,
+    /// 実行開始時に解決した許可スコープ。
+    pub scopes: ScopeSet,
+    /// 実行中に開いた git リポジトリ。`[git] protect_dirty` の判定に使い回す。
+    pub repositories: Repositories,
+    pub progress: Progress,
 }
 
 impl ProcessContext {
     /// rm 実行に必要な引数と設定をまとめたコンテキストを生成する。
     pub fn new(args: Args, config: Config) -> Self {
-        Self { args, config }
+        let progress = Progress::new("rm", args.progress);
+        let scopes = config.scope_set();
+        let repositories = Repositories::new(&config.git);
+        Self {
+            args,
+            config,
+            scopes,
+            repositories,
+            progress,
+        }
     }
 }
 
@@ -22,32 +42,58 @@ pub enum RemovalKind {
     NonExistentFile,
 }
 
-/// 処理種別ごとの前提条件を検証し、実行可否を判定する。
-pub fn validate(kind: &RemovalKind, path: &Path, _context: &ProcessContext) -> Result<(), String> {
+/// 削除の前提条件を満たさない理由を表す。
+#[derive(Debug)]
+pub enum Rejection {
+    NotADirectory,
+    NotEmpty,
+    IsDirectory,
+    Inaccessible(io::Error),
+}
+
+/// 処理種別ごとの前提条件を検証し、満たさない場合はその理由を返す。
+///
+/// `rm` と `rmdir` で共有し、エラーメッセージはそれぞれの呼び出し側で組み立てる。
+pub fn check(kind: &RemovalKind, path: &Path) -> Result<(), Rejection> {
     match kind {
         RemovalKind::File => Ok(()),
         RemovalKind::RecursiveDirectory => Ok(()),
-        RemovalKind::EmptyDirectory => match std::fs::read_dir(path) {
-            Ok(mut entries) => {
-                if entries.next().is_some() {
-                    Err(format!("rm: {}: Directory not empty", path.display()))
-                } else {
-                    Ok(())
-                }
+        RemovalKind::EmptyDirectory => {
+            let meta = std::fs::symlink_metadata(path).map_err(Rejection::Inaccessible)?;
+            if !meta.is_dir() {
+                return Err(Rejection::NotADirectory);
+            }
+            match is_empty_directory(path) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(Rejection::NotEmpty),
+                Err(e) => Err(Rejection::Inaccessible(e)),
             }
-            Err(e) => Err(format!("rm: cannot access '{}': {}", path.display(), e)),
-        },
-        RemovalKind::DirectoryError => Err(format!("rm: {}: is a directory", path.display())),
+        }
+        RemovalKind::DirectoryError => Err(Rejection::IsDirectory),
         RemovalKind::NonExistentFile => Ok(()),
     }
 }
 
+/// 処理種別ごとの前提条件を検証し、実行可否を判定する。
+pub fn validate(kind: &RemovalKind, path: &Path, _context: &ProcessContext) -> Result<(), String> {
+    check(kind, path).map_err(|rejection| match rejection {
+        Rejection::NotADirectory => format!("rm: {}: Not a directory", path.display()),
+        Rejection::NotEmpty => format!("rm: {}: Directory not empty", path.display()),
+        Rejection::IsDirectory => format!("rm: {}: is a directory", path.display()),
+        Rejection::Inaccessible(e) => {
+            format!("rm: cannot access '{}': {}", path.display(), e)
+        }
+    })
+}
+
 /// 処理種別に応じて実際の削除処理を実行する。
-pub fn execute(kind: &RemovalKind, path: &Path, _context: &ProcessContext) -> Result<(), String> {
+///
+/// 対象の親ディレクトリを許可スコープのルートからシンボリックリンクを辿らずに開き、
+/// そのパスが今も同じディレクトリを指していることを確認してからゴミ箱へ移動する。
+pub fn execute(kind: &RemovalKind, path: &Path, context: &ProcessContext) -> Result<(), String> {
     match kind {
         RemovalKind::File | RemovalKind::RecursiveDirectory | RemovalKind::EmptyDirectory => {
-            trash::delete(path)
-                .map_err(|e| format!("rm: failed to remove '{}': {}", path.display(), e))
+            trash_pinned("rm", path, &context.scopes)
         }
         RemovalKind::DirectoryError => {
             unreachable!("DirectoryError should fail at validation")
@@ -55,3 +101,126 @@ pub fn execute(kind: &RemovalKind, path: &Path, _context: &ProcessContext) -> Re
         RemovalKind::NonExistentFile => Ok(()),
     }
 }
+
+/// `RemovalKind::EmptyDirectory` の前提となる、ディレクトリが空かどうかを判定する。
+pub fn is_empty_directory(path: &Path) -> io::Result<bool> {
+    Ok(std::fs::read_dir(path)?.next().is_none())
+}
+
+/// 対象の親ディレクトリを許可スコープのルートから開き直し、同じ実体であることを確認してからゴミ箱へ移動する。
+pub fn trash_pinned(command: &str, path: &Path, scopes: &ScopeSet) -> Result<(), String> {
+    let pinned = scopes
+        .open_parent(path)
+        .map_err(|e| format!("{command}: cannot remove '{}': {}", path.display(), e))?;
+    trash_verified(command, path, &pinned)
+}
+
+/// 開いた親ディレクトリ直下のエントリを、ディスクリプタ基準でステージングディレクトリへ移してからゴミ箱へ移動する。
+///
+/// `trash` crate はパスしか受け付けないため、親ディレクトリのパスが差し替えられても範囲外の
+/// エントリへ届かないよう `staging::trash_entry` を経由する。ゴミ箱には元の場所を記録する。
+fn trash_verified(command: &str, path: &Path, pinned: &Pinned) -> Result<(), String> {
+    staging::trash_entry(&pinned.dir, &pinned.name, |staged| {
+        trash::delete(staged).map_err(|e| e.to_string())?;
+        let _ = staging::record_original_path(staged);
+        Ok(())
+    })
+    .map_err(|e| format!("{command}: failed to remove '{}': {}", path.display(), e))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::dirfd::Dir;
+    use std::ffi::{OsStr, OsString};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn trash_verified_refuses_parent_swapped_for_symlink() {
+        // 親ディレクトリを開いた後にパスをシンボリックリンクへ差し替えた場合、
+        // リンク先の同名エントリをゴミ箱へ移動せずに失敗することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let scope = temp_dir.path().join("scope");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(scope.join("dir")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        fs::write(scope.join("dir").join("victim.txt"), "inside").unwrap();
+        fs::write(outside.join("victim.txt"), "outside").unwrap();
+        let pinned = Pinned {
+            dir: Dir::open(&scope.join("dir")).unwrap(),
+            name: OsStr::new("victim.txt").to_os_string(),
+        };
+
+        fs::rename(scope.join("dir"), scope.join("moved")).unwrap();
+        std::os::unix::fs::symlink(&outside, scope.join("dir")).unwrap();
+        let err = trash_verified("rm", &scope.join("dir").join("victim.txt"), &pinned).unwrap_err();
+
+        assert!(err.contains("was replaced during the operation"), "{err}");
+        assert!(outside.join("victim.txt").exists());
+        assert!(scope.join("moved").join("victim.txt").exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn trash_verified_never_trashes_outside_entry_while_parent_is_swapped_concurrently() {
+        // 親ディレクトリのパスを別スレッドでシンボリックリンクと入れ替え続けても、
+        // リンク先の同名エントリをゴミ箱へ移動しないことを確認する。
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        let temp_dir = TempDir::new().unwrap();
+        let scope = temp_dir.path().join("scope");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(scope.join("dir")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        let dir = Dir::open(&scope.join("dir")).unwrap();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let swapper = {
+            let stop = Arc::clone(&stop);
+            let scope = scope.clone();
+            let outside = outside.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    fs::rename(scope.join("dir"), scope.join("moved")).unwrap();
+                    std::os::unix::fs::symlink(&outside, scope.join("dir")).unwrap();
+                    thread::yield_now();
+                    fs::remove_file(scope.join("dir")).unwrap();
+                    fs::rename(scope.join("moved"), scope.join("dir")).unwrap();
+                    thread::yield_now();
+                }
+            })
+        };
+
+        for i in 0..50 {
+            let name = OsString::from(format!("victim-{i}.txt"));
+            fs::write(dir.entry_path(&name), "inside").unwrap();
+            fs::write(outside.join(&name), "outside").unwrap();
+            let pinned = Pinned {
+                dir: dir.try_clone().unwrap(),
+                name,
+            };
+            let _ = trash_verified("rm", &scope.join("dir").join(&pinned.name), &pinned);
+        }
+        stop.store(true, Ordering::Relaxed);
+        swapper.join().unwrap();
+
+        let root = temp_dir.path().canonicalize().unwrap();
+        let trashed: Vec<_> = trash::os_limited::list()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|item| item.original_parent.starts_with(&root))
+            .collect();
+        let _ = trash::os_limited::purge_all(trashed);
+        for i in 0..50 {
+            let name = format!("victim-{i}.txt");
+            assert_eq!(
+                fs::read_to_string(outside.join(&name)).unwrap(),
+                "outside",
+                "{name}"
+            );
+        }
+    }
+}
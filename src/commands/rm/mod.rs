@@ -2,6 +2,7 @@ pub mod args;
 pub mod handlers;
 
 use crate::config::Config;
+use crate::limits::{self, TreeStats};
 use crate::notifications::{CommandKind, CommandResultCounter};
 use args::Args;
 use handlers::{ProcessContext, RemovalKind};
@@ -12,9 +13,10 @@ pub fn run(args: Args, config: Config) -> i32 {
     let mut exit_code = 0;
     let mut counter = CommandResultCounter::new(CommandKind::Rm);
     let context = ProcessContext::new(args, config);
+    let mut scanned = prescan_for_progress(&context).into_iter();
 
     for path in &context.args.path {
-        if let Err(msg) = process_path(path, &context) {
+        if let Err(msg) = process_path(path, scanned.next().flatten(), &context) {
             eprintln!("{msg}");
             exit_code = 1;
             counter.record_failure();
@@ -23,6 +25,7 @@ pub fn run(args: Args, config: Config) -> i32 {
         }
     }
 
+    context.progress.finish();
     if context.config.notify.macos_notify {
         counter.notify();
     }
@@ -31,8 +34,14 @@ pub fn run(args: Args, config: Config) -> i32 {
 }
 
 /// 単一パスに対して許可範囲確認・ハンドラ選択・実行までを一貫して行う。
-fn process_path(path: &Path, context: &ProcessContext) -> Result<(), String> {
-    if !context.config.is_path_allowed(path) {
+///
+/// `scanned` は `--progress` 用に走査済みの集計結果で、指定時は再走査しない。
+fn process_path(
+    path: &Path,
+    scanned: Option<TreeStats>,
+    context: &ProcessContext,
+) -> Result<(), String> {
+    if !context.scopes.contains(path) {
         return Err(format!(
             "rm: cannot remove '{}': path is outside allowed scope",
             path.display()
@@ -51,7 +60,102 @@ fn process_path(path: &Path, context: &ProcessContext) -> Result<(), String> {
 
     let handler = determine_handler(path, context)?;
     handlers::validate(&handler, path, context)?;
-    handlers::execute(&handler, path, context)
+    context
+        .repositories
+        .ensure_clean("rm", path, context.args.allow_dirty)?;
+    let stats = prescan(&handler, path, scanned, context)?;
+    let within_limits = match stats {
+        Some(stats) => limits::enforce(&context.config.limits, &stats, context.args.yes_i_mean_it),
+        None => Ok(()),
+    };
+
+    if context.args.dry_run {
+        println!("{}", describe_removal("would remove", path, stats));
+        if let Err(reason) = within_limits {
+            println!(
+                "rm: would refuse to remove '{}': {}",
+                path.display(),
+                reason
+            );
+        }
+        return Ok(());
+    }
+    within_limits
+        .map_err(|reason| format!("rm: refusing to remove '{}': {}", path.display(), reason))?;
+
+    handlers::execute(&handler, path, context)?;
+    if let Some(stats) = stats {
+        context.progress.advance_by(path, stats);
+    }
+    if context.args.verbose {
+        println!("{}", describe_removal("removed", path, stats));
+    }
+    Ok(())
+}
+
+/// `--progress` 指定時、全パスを事前走査して総量を設定し、パスごとの集計結果を返す。
+///
+/// 走査できないパスは `None` とし、総量に含めない。
+fn prescan_for_progress(context: &ProcessContext) -> Vec<Option<TreeStats>> {
+    if !context.progress.is_enabled() {
+        return Vec::new();
+    }
+    let scanned: Vec<_> = context
+        .args
+        .path
+        .iter()
+        .map(|path| {
+            if context.scopes.contains(path) {
+                limits::scan(path).ok()
+            } else {
+                None
+            }
+        })
+        .collect();
+    let mut totals = TreeStats::default();
+    for stats in scanned.iter().flatten() {
+        totals.files += stats.files;
+        totals.bytes += stats.bytes;
+    }
+    context.progress.set_totals(totals);
+    scanned
+}
+
+/// `[limits]` の検証や `-v`/`--dry-run`/`--progress` の表示に必要な場合のみ削除対象を事前走査する。
+///
+/// 上限の検証は呼び出し側で行い、`--dry-run` 時は拒否せずに超過を表示する。
+fn prescan(
+    kind: &RemovalKind,
+    path: &Path,
+    scanned: Option<TreeStats>,
+    context: &ProcessContext,
+) -> Result<Option<TreeStats>, String> {
+    if matches!(kind, RemovalKind::NonExistentFile) {
+        return Ok(None);
+    }
+    let limits_configured = context.config.limits.is_configured();
+    if !limits_configured
+        && !context.args.verbose
+        && !context.args.dry_run
+        && !context.progress.is_enabled()
+    {
+        return Ok(None);
+    }
+
+    let stats = match scanned {
+        Some(stats) => stats,
+        None => limits::scan(path)
+            .map_err(|e| format!("rm: cannot scan '{}': {}", path.display(), e))?,
+    };
+    Ok(Some(stats))
+}
+
+/// `-v`/`--dry-run` 向けに削除対象と事前走査結果を1行で表す。
+fn describe_removal(action: &str, path: &Path, stats: Option<TreeStats>) -> String {
+    match stats {
+        Some(stats) => format!("{action} '{}' ({stats})", path.display()),
+        None => format!("{action} '{}'", path.display()),
+    }
 }
 
 /// 対象パスの種類とオプションに応じて適切な削除ハンドラを選択する。
@@ -104,6 +208,11 @@ mod tests {
                 allow_dir: false,
                 force: false,
                 recursive,
+                allow_dirty: false,
+                verbose: false,
+                dry_run: false,
+                progress: false,
+                yes_i_mean_it: None,
                 path: vec![],
             },
             Config {
@@ -111,6 +220,7 @@ mod tests {
                     paths: vec![std::path::PathBuf::from("/")],
                 },
                 notify: NotifyConfig { macos_notify: true },
+                ..Config::default()
             },
         )
     }
@@ -169,6 +279,11 @@ mod tests {
             allow_dir: false,
             force: true,
             recursive: false,
+            allow_dirty: false,
+            verbose: false,
+            dry_run: false,
+            progress: false,
+            yes_i_mean_it: None,
             path: vec![path.to_path_buf()],
         };
         let config = Config {
@@ -176,6 +291,7 @@ mod tests {
                 paths: vec![std::path::PathBuf::from("/")],
             },
             notify: NotifyConfig { macos_notify: true },
+            ..Config::default()
         };
 
         notification_store()
@@ -210,6 +326,11 @@ mod tests {
             allow_dir: false,
             force: true,
             recursive: false,
+            allow_dirty: false,
+            verbose: false,
+            dry_run: false,
+            progress: false,
+            yes_i_mean_it: None,
             path: vec![path.to_path_buf()],
         };
         let config = Config {
@@ -219,6 +340,7 @@ mod tests {
             notify: NotifyConfig {
                 macos_notify: false,
             },
+            ..Config::default()
         };
 
         notification_store()
@@ -0,0 +1,23 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(name = "rmdir")]
+#[command(about = "Safely remove empty directories by moving them to the trash", long_about = None)]
+pub struct Args {
+    /// Remove DIRECTORY and its ancestors, stopping at the allowed scope boundary
+    #[arg(short = 'p', long = "parents")]
+    pub parents: bool,
+
+    /// Ignore each failure that is solely because a directory is non-empty
+    #[arg(long = "ignore-fail-on-non-empty")]
+    pub ignore_fail_on_non_empty: bool,
+
+    /// Output a diagnostic for every directory processed
+    #[arg(short = 'v', long = "verbose")]
+    pub verbose: bool,
+
+    /// Empty directories to trash
+    #[arg(required = true, num_args = 1..)]
+    pub directories: Vec<PathBuf>,
+}
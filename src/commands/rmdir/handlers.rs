@@ -0,0 +1,123 @@
+use crate::commands::rm::handlers::{self as rm_handlers, Rejection, RemovalKind};
+use crate::commands::rmdir::args::Args;
+use crate::config::{Config, ScopeSet};
+use std::io;
+use std::path::Path;
+
+/// rmdir 実行時に必要な引数と設定を保持するコンテキスト。
+pub struct ProcessContext {
+    pub args: Args,
+    pub config: Config,
+    /// 実行開始時に解決した許可スコープ。
+    pub scopes: ScopeSet,
+}
+
+impl ProcessContext {
+    /// rmdir 実行に必要な引数と設定をまとめたコンテキストを生成する。
+    pub fn new(args: Args, config: Config) -> Self {
+        let scopes = config.scope_set();
+        Self {
+            args,
+            config,
+            scopes,
+        }
+    }
+}
+
+/// 削除前にパス許可範囲・ルート保護を確認し、`rm -d` と共通の空ディレクトリ判定を行う。
+///
+/// 削除できる場合は `RemovalKind::EmptyDirectory` を返す。`--ignore-fail-on-non-empty`
+/// 指定時に空でないディレクトリだった場合は `None` を返し、エラーにしない。
+pub fn validate(path: &Path, context: &ProcessContext) -> Result<Option<RemovalKind>, String> {
+    let failure = |reason: &dyn std::fmt::Display| {
+        format!("rmdir: failed to remove '{}': {}", path.display(), reason)
+    };
+    // GNU と同じく、最後の要素が `.` または `..` のオペランドは EINVAL として拒否する
+    if names_dot_entry(path) {
+        return Err(failure(&"Invalid argument"));
+    }
+    if !context.scopes.contains(path) {
+        return Err(failure(&"path is outside allowed scope"));
+    }
+    if path
+        .canonicalize()
+        .is_ok_and(|resolved| resolved == Path::new("/"))
+    {
+        return Err(format!(
+            "rmdir: refusing to remove '{}': it is the root directory",
+            path.display()
+        ));
+    }
+    if is_scope_root(path, &context.scopes) {
+        return Err(format!(
+            "rmdir: refusing to remove '{}': it is the root of an allowed scope",
+            path.display()
+        ));
+    }
+
+    let kind = RemovalKind::EmptyDirectory;
+    match rm_handlers::check(&kind, path) {
+        Ok(()) => Ok(Some(kind)),
+        Err(Rejection::NotEmpty) if context.args.ignore_fail_on_non_empty => Ok(None),
+        Err(Rejection::NotEmpty) => Err(failure(&"Directory not empty")),
+        Err(Rejection::NotADirectory | Rejection::IsDirectory) => Err(failure(&"Not a directory")),
+        Err(Rejection::Inaccessible(e)) if e.kind() == io::ErrorKind::NotFound => {
+            Err(failure(&"No such file or directory"))
+        }
+        Err(Rejection::Inaccessible(e)) => Err(failure(&e)),
+    }
+}
+
+/// 検証済みの空ディレクトリをゴミ箱へ移動する。
+pub fn execute(kind: &RemovalKind, path: &Path, context: &ProcessContext) -> Result<(), String> {
+    debug_assert!(matches!(kind, RemovalKind::EmptyDirectory));
+    if context.args.verbose {
+        println!("rmdir: removing directory, '{}'", path.display());
+    }
+    rm_handlers::trash_pinned("rmdir", path, &context.scopes)
+}
+
+/// `-p` で遡る親ディレクトリが許可範囲の境界に達しているかを判定する。
+///
+/// 許可範囲外、またはスコープのルート自身の場合は境界とみなし、それ以上は削除しない。
+pub fn reaches_scope_boundary(parent: &Path, scopes: &ScopeSet) -> bool {
+    !scopes.contains(parent) || is_scope_root(parent, scopes)
+}
+
+/// パスが許可スコープのルート自身に解決されるかを判定する。
+fn is_scope_root(path: &Path, scopes: &ScopeSet) -> bool {
+    path.canonicalize()
+        .is_ok_and(|resolved| scopes.scopes().contains(&resolved))
+}
+
+/// 末尾の `/` を除いた最後の要素が `.` または `..` かを判定する。
+///
+/// `Path::components` は途中や末尾の `.` を読み飛ばすため、バイト列で判定する。
+fn names_dot_entry(path: &Path) -> bool {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    let trimmed = match bytes.iter().rposition(|&b| b != b'/') {
+        Some(last) => &bytes[..=last],
+        None => return false,
+    };
+    let name = match trimmed.iter().rposition(|&b| b == b'/') {
+        Some(slash) => &trimmed[slash + 1..],
+        None => trimmed,
+    };
+    name == b"." || name == b".."
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_dot_entry_detects_trailing_dot_components() {
+        // 最後の要素が `.`/`..` のオペランドだけを検出し、末尾の `/` は無視することを確認する。
+        for path in [".", "..", "./", "a/.", "a/..//", "/tmp/x/."] {
+            assert!(names_dot_entry(Path::new(path)), "{path}");
+        }
+        for path in ["a", "./a", "../a", ".hidden", "a/..b", "/"] {
+            assert!(!names_dot_entry(Path::new(path)), "{path}");
+        }
+    }
+}
@@ -0,0 +1,86 @@
+pub mod args;
+pub mod handlers;
+
+use crate::config::Config;
+use crate::notifications::{CommandKind, CommandResultCounter};
+use args::Args;
+use handlers::ProcessContext;
+use std::path::Path;
+
+/// rmdir コマンド全体を実行し、各ディレクトリの処理結果に応じて終了コードを決定する。
+pub fn run(args: Args, config: Config) -> i32 {
+    let mut exit_code = 0;
+    let mut counter = CommandResultCounter::new(CommandKind::Rmdir);
+    let context = ProcessContext::new(args, config);
+
+    for path in &context.args.directories {
+        if let Err(msg) = process_directory(path, &context) {
+            eprintln!("{msg}");
+            exit_code = 1;
+            counter.record_failure();
+        } else {
+            counter.record_success();
+        }
+    }
+
+    if context.config.notify.macos_notify {
+        counter.notify();
+    }
+
+    exit_code
+}
+
+/// 単一のディレクトリを削除し、`-p` 指定時は許可範囲の境界まで親ディレクトリも削除する。
+fn process_directory(path: &Path, context: &ProcessContext) -> Result<(), String> {
+    if !remove_one(path, context)? || !context.args.parents {
+        return Ok(());
+    }
+
+    for parent in ancestors_to_remove(path) {
+        if handlers::reaches_scope_boundary(parent, &context.scopes)
+            || !remove_one(parent, context)?
+        {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// ディレクトリを1つ削除する。空でないため削除しなかった場合は `false` を返す。
+fn remove_one(path: &Path, context: &ProcessContext) -> Result<bool, String> {
+    match handlers::validate(path, context)? {
+        Some(kind) => {
+            handlers::execute(&kind, path, context)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// `-p` で削除を試みる親ディレクトリを、オペランドの文字列から近い順に返す。
+///
+/// GNU と同じくオペランドに含まれる要素だけを遡り、ルートディレクトリは含めない。
+fn ancestors_to_remove(path: &Path) -> impl Iterator<Item = &Path> {
+    path.ancestors()
+        .skip(1)
+        .take_while(|parent| !parent.as_os_str().is_empty() && parent.parent().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ancestors_to_remove_walks_operand_components_only() {
+        // オペランドに書かれた親要素だけを近い順に返し、空パスとルートは含めないことを確認する。
+        assert_eq!(
+            ancestors_to_remove(Path::new("a/b/c")).collect::<Vec<_>>(),
+            vec![Path::new("a/b"), Path::new("a")]
+        );
+        assert_eq!(
+            ancestors_to_remove(Path::new("/x/y/")).collect::<Vec<_>>(),
+            vec![Path::new("/x")]
+        );
+        assert!(ancestors_to_remove(Path::new("a")).next().is_none());
+    }
+}
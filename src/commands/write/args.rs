@@ -0,0 +1,18 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Atomically replace FILE with standard input, moving the previous version to the trash.
+#[derive(Parser, Debug)]
+#[command(name = "write")]
+pub struct Args {
+    /// Refuse to replace FILE if it already exists
+    #[arg(short = 'n', long = "no-clobber")]
+    pub no_clobber: bool,
+
+    /// Print the replaced file name
+    #[arg(short = 'v', long = "verbose")]
+    pub verbose: bool,
+
+    /// File to write
+    pub path: PathBuf,
+}
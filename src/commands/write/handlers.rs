@@ -0,0 +1,235 @@
+use crate::commands::cp::{handlers as cp_handlers, replace};
+use crate::commands::write::args::Args;
+use crate::config::{Config, ScopeSet};
+use crate::dirfd::{Dir, RenameMode};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// write 実行時に必要な引数と設定を保持するコンテキスト。
+pub struct ProcessContext {
+    pub args: Args,
+    pub config: Config,
+    /// 実行開始時に解決した許可スコープ。
+    pub scopes: ScopeSet,
+}
+
+impl ProcessContext {
+    /// write 実行に必要な引数と設定をまとめたコンテキストを生成する。
+    pub fn new(args: Args, config: Config) -> Self {
+        let scopes = config.scope_set();
+        Self {
+            args,
+            config,
+            scopes,
+        }
+    }
+}
+
+/// 書き込み先が許可範囲内であることを検証する。
+pub fn validate(path: &Path, context: &ProcessContext) -> Result<(), String> {
+    if context.scopes.contains(path) {
+        return Ok(());
+    }
+    Err(format!(
+        "write: cannot write '{}': path is outside allowed scope",
+        path.display()
+    ))
+}
+
+/// `input` の内容を同じディレクトリの隠し一時ファイルへ書き出して fsync し、書き込み先を置き換える。
+///
+/// 既存ファイルは `cp` と同じ置き換え手順で、古い内容を元のパスのままゴミ箱（失敗時は
+/// フォールバック先）へ移動してから新しい内容を配置する。入力の読み込みに失敗した場合は
+/// 既存ファイルに触れない。
+/// 既存ファイルのパーミッションは新しいファイルへ引き継ぐ。ディレクトリとシンボリックリンクは置き換えない。
+/// 書き込んだバイト数を返す。
+pub fn execute<R: Read>(
+    path: &Path,
+    input: &mut R,
+    context: &ProcessContext,
+) -> Result<u64, String> {
+    let write_error = |e: io::Error| format!("write: cannot write '{}': {}", path.display(), e);
+    let pinned = context.scopes.open_parent(path).map_err(write_error)?;
+    let existing = match pinned.dir.symlink_metadata(&pinned.name) {
+        Ok(meta) => Some(meta),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(write_error(e)),
+    };
+    if let Some(meta) = &existing {
+        if meta.file_type().is_symlink() {
+            return Err(format!(
+                "write: cannot overwrite symbolic link '{}'",
+                path.display()
+            ));
+        }
+        if meta.is_dir() {
+            return Err(format!(
+                "write: cannot overwrite directory '{}'",
+                path.display()
+            ));
+        }
+        if context.args.no_clobber {
+            return Err(format!(
+                "write: cannot overwrite '{}': File exists",
+                path.display()
+            ));
+        }
+    }
+
+    let permissions = existing
+        .as_ref()
+        .filter(|meta| meta.is_file())
+        .map(fs::Metadata::permissions);
+    let staged = replace::sibling_temp_name(&pinned.dir, &pinned.name).map_err(write_error)?;
+    let written = write_staged(&pinned.dir, &staged, input, permissions).map_err(|e| {
+        replace::remove_staged(&pinned.dir, &staged);
+        write_error(e)
+    })?;
+
+    if existing.is_none() {
+        return pinned
+            .dir
+            .rename(&staged, &pinned.dir, &pinned.name, RenameMode::NoReplace)
+            .and_then(|()| pinned.dir.sync_all())
+            .map(|()| written)
+            .map_err(|e| {
+                replace::remove_staged(&pinned.dir, &staged);
+                write_error(e)
+            });
+    }
+    replace::swap_in_as("write", &pinned.dir, &staged, &pinned.name, path, |old| {
+        cp_handlers::move_existing_file_to_trash_as("write", old)
+    })?;
+    Ok(written)
+}
+
+/// 一時ファイルを作成して入力を書き出し、ディスクへ同期する。
+fn write_staged<R: Read>(
+    dir: &Dir,
+    staged: &OsStr,
+    input: &mut R,
+    permissions: Option<fs::Permissions>,
+) -> io::Result<u64> {
+    let mut file = dir.create_file(staged, default_mode(permissions.as_ref()))?;
+    let written = io::copy(input, &mut file)?;
+    // 作成時のモードは umask の影響を受けるため、既存ファイルのパーミッションは明示的に設定する。
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    file.sync_all()?;
+    Ok(written)
+}
+
+/// 一時ファイル作成時のモードを返す。新規作成時はシェルのリダイレクトと同じ `0o666` とする。
+fn default_mode(permissions: Option<&fs::Permissions>) -> u32 {
+    #[cfg(unix)]
+    {
+        permissions.map_or(0o666, |permissions| permissions.mode() & 0o7777)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = permissions;
+        0o666
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AdditionalAllowedDirectories;
+    use tempfile::TempDir;
+
+    /// テスト用の最小コンテキストを生成する。
+    fn build_context(no_clobber: bool, allowed: &Path) -> ProcessContext {
+        ProcessContext::new(
+            Args {
+                no_clobber,
+                verbose: false,
+                path: allowed.join("unused"),
+            },
+            Config {
+                additional_allowed_directories: AdditionalAllowedDirectories {
+                    paths: vec![allowed.to_path_buf()],
+                },
+                ..Config::default()
+            },
+        )
+    }
+
+    #[test]
+    fn execute_creates_missing_file_without_leaving_temporary_entries() {
+        // 存在しないファイルは入力内容で作成し、一時エントリを残さないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("out.txt");
+        let context = build_context(false, temp_dir.path());
+
+        let written = execute(&target, &mut io::Cursor::new("hello"), &context).unwrap();
+
+        assert_eq!(written, 5);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "hello");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn execute_keeps_existing_file_when_input_fails() {
+        // 入力の読み込みに失敗した場合は既存ファイルを変更せず、一時ファイルも削除することを確認する。
+        struct FailingReader;
+        impl Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken pipe"))
+            }
+        }
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("out.txt");
+        fs::write(&target, "old").unwrap();
+        let context = build_context(false, temp_dir.path());
+
+        let err = execute(&target, &mut FailingReader, &context).unwrap_err();
+
+        assert!(err.contains("broken pipe"));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn execute_refuses_symlink_target() {
+        // 書き込み先がシンボリックリンクの場合、リンクもリンク先も変更しないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let real = temp_dir.path().join("real.txt");
+        let link = temp_dir.path().join("link.txt");
+        fs::write(&real, "old").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        let context = build_context(false, temp_dir.path());
+
+        let err = execute(&link, &mut io::Cursor::new("new"), &context).unwrap_err();
+
+        assert!(err.contains("cannot overwrite symbolic link"), "{err}");
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&real).unwrap(), "old");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn execute_refuses_existing_file_with_no_clobber() {
+        // -n 指定時は既存ファイルを置き換えないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("out.txt");
+        fs::write(&target, "old").unwrap();
+        let context = build_context(true, temp_dir.path());
+
+        let err = execute(&target, &mut io::Cursor::new("new"), &context).unwrap_err();
+
+        assert!(err.contains("File exists"));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+    }
+}
@@ -0,0 +1,40 @@
+pub mod args;
+pub mod handlers;
+
+use crate::config::Config;
+use crate::notifications::{CommandKind, CommandResultCounter};
+use args::Args;
+use handlers::ProcessContext;
+use std::io;
+
+/// 標準入力の内容で書き込み先を置き換え、結果に応じて終了コードを決定する。
+///
+/// シェルの `>` リダイレクトの代わりに使い、上書きされる古い内容をゴミ箱へ残す。
+pub fn run(args: Args, config: Config) -> i32 {
+    let mut counter = CommandResultCounter::new(CommandKind::Write);
+    let context = ProcessContext::new(args, config);
+    let path = &context.args.path;
+
+    let result = handlers::validate(path, &context)
+        .and_then(|()| handlers::execute(path, &mut io::stdin().lock(), &context));
+    let exit_code = match result {
+        Ok(written) => {
+            if context.args.verbose {
+                println!("wrote '{}' ({written} bytes)", path.display());
+            }
+            counter.record_success();
+            0
+        }
+        Err(msg) => {
+            eprintln!("{msg}");
+            counter.record_failure();
+            1
+        }
+    };
+
+    if context.config.notify.macos_notify {
+        counter.notify();
+    }
+
+    exit_code
+}
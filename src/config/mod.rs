@@ -1,14 +1,29 @@
-use serde::{Deserialize, Serialize};
+use crate::backup::{self, BackupControl};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fs;
 use std::path::{Component, Path, PathBuf};
 
+mod scope;
+
+pub use scope::ScopeSet;
+
 const DEFAULT_CONFIG_TEMPLATE: &str = include_str!("../../config.example.toml");
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    pub implicit_cwd_scope: ImplicitCwdScope,
     pub additional_allowed_directories: AdditionalAllowedDirectories,
     #[serde(default)]
     pub notify: NotifyConfig,
+    #[serde(default)]
+    pub git: GitConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub ln: LnConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,11 +37,155 @@ pub struct NotifyConfig {
     pub macos_notify: bool,
 }
 
+/// git リポジトリ内の未コミット内容を保護する設定。
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct GitConfig {
+    /// 未コミットの変更・未追跡ファイルを含む対象の trash を `--allow-dirty` なしでは拒否する。
+    pub protect_dirty: bool,
+}
+
+/// trash 対象ツリーの規模上限を表す設定。未設定の項目は無制限として扱う。
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// trash できるファイル（ディレクトリ以外のエントリ）数の上限。
+    pub max_files: Option<u64>,
+    /// trash できる合計バイト数の上限。
+    pub max_bytes: Option<u64>,
+}
+
+impl LimitsConfig {
+    /// いずれかの上限が設定されているかを返す。
+    pub fn is_configured(&self) -> bool {
+        self.max_files.is_some() || self.max_bytes.is_some()
+    }
+}
+
+/// `ln` の作成するリンクに関する設定。
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct LnConfig {
+    /// リンク先が許可範囲外になるシンボリックリンクの作成を拒否する。
+    pub confine_symlink_targets: bool,
+}
+
+/// 上書きされる既存ターゲットを、ゴミ箱の代わりに同じディレクトリのバックアップとして残す設定。
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// 既定のバックアップ方式。未設定または `"none"` ならゴミ箱へ移動する。
+    pub control: Option<BackupControl>,
+    /// 単純バックアップの接尾辞。未設定なら `~`。
+    pub suffix: Option<String>,
+    /// ディレクトリごとの設定。対象を含む最も深いディレクトリの設定を優先する。
+    pub scopes: Vec<BackupScopeConfig>,
+}
+
+/// `[[backup.scopes]]` の1件。`path` 配下の上書きに適用する。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupScopeConfig {
+    pub path: PathBuf,
+    pub control: BackupControl,
+    #[serde(default)]
+    pub suffix: Option<String>,
+}
+
+impl BackupConfig {
+    /// `[[backup.scopes]]` のルートを正規化し、判定に使い回せる `BackupScopes` を構築する。
+    pub fn resolve_scopes(&self) -> BackupScopes {
+        let scopes = self
+            .scopes
+            .iter()
+            .map(|scope| BackupScopeConfig {
+                path: Config::canonicalize_with_missing(&scope.path),
+                control: scope.control,
+                suffix: scope.suffix.clone(),
+            })
+            .collect();
+        BackupScopes { scopes }
+    }
+}
+
+/// ルートを実行開始時に一度だけ正規化した `[[backup.scopes]]` の一覧。
+#[derive(Debug, Default)]
+pub struct BackupScopes {
+    /// `path` を正規化済みのスコープ。
+    scopes: Vec<BackupScopeConfig>,
+}
+
+impl BackupScopes {
+    /// `target` を含む最も深いスコープを返す。
+    pub fn scope_for(&self, target: &Path) -> Option<&BackupScopeConfig> {
+        if self.scopes.is_empty() {
+            return None;
+        }
+        let target = std::path::absolute(target).unwrap_or_else(|_| target.to_path_buf());
+        let target = Config::canonicalize_preserving_symlink_leaf(&target);
+        self.scopes
+            .iter()
+            .filter(|scope| target.starts_with(&scope.path))
+            .max_by_key(|scope| scope.path.components().count())
+    }
+}
+
+/// カレントディレクトリから導出する暗黙スコープの扱いを表す。
+///
+/// 設定ファイルでは `false` / `"cwd"` / `"git-root"` のいずれかで指定する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImplicitCwdScope {
+    /// カレントディレクトリ配下を許可する（既定）。
+    #[default]
+    Cwd,
+    /// カレントディレクトリを含む git リポジトリのルート配下を許可する。
+    GitRoot,
+    /// 暗黙スコープを無効化し、明示設定のスコープのみを許可する。
+    Disabled,
+}
+
+impl Serialize for ImplicitCwdScope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Cwd => serializer.serialize_str("cwd"),
+            Self::GitRoot => serializer.serialize_str("git-root"),
+            Self::Disabled => serializer.serialize_bool(false),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ImplicitCwdScope {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bool(bool),
+            Name(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Bool(true) => Ok(Self::Cwd),
+            Raw::Bool(false) => Ok(Self::Disabled),
+            Raw::Name(name) => match name.as_str() {
+                "cwd" => Ok(Self::Cwd),
+                "git-root" => Ok(Self::GitRoot),
+                other => Err(serde::de::Error::custom(format!(
+                    "invalid implicit_cwd_scope '{other}': expected false, \"cwd\" or \"git-root\""
+                ))),
+            },
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
+            implicit_cwd_scope: ImplicitCwdScope::default(),
             additional_allowed_directories: AdditionalAllowedDirectories { paths: vec![] },
             notify: NotifyConfig::default(),
+            git: GitConfig::default(),
+            limits: LimitsConfig::default(),
+            backup: BackupConfig::default(),
+            ln: LnConfig::default(),
         }
     }
 }
@@ -44,7 +203,7 @@ impl Config {
                 additional_allowed_directories: AdditionalAllowedDirectories {
                     paths: vec![PathBuf::from("/")],
                 },
-                notify: NotifyConfig::default(),
+                ..Self::default()
             });
         }
 
@@ -88,39 +247,37 @@ impl Config {
             }
         }
 
+        for (index, scope) in self.backup.scopes.iter().enumerate() {
+            if !scope.path.is_absolute() {
+                return Err(format!(
+                    "Invalid config: backup.scopes[{index}].path must be an absolute path: {}",
+                    scope.path.display()
+                ));
+            }
+        }
+        let suffixes = self
+            .backup
+            .scopes
+            .iter()
+            .filter_map(|scope| scope.suffix.as_deref())
+            .chain(self.backup.suffix.as_deref());
+        for suffix in suffixes {
+            backup::validate_suffix(suffix).map_err(|e| format!("Invalid config: {e}"))?;
+        }
+
         Ok(())
     }
 
     /// 指定パスが操作可能範囲に含まれるかを判定する。
     ///
     /// # 判定ルール
-    /// - 実行時のカレントディレクトリ配下は常に許可
+    /// - `implicit_cwd_scope` に応じてカレントディレクトリ（または git ルート）配下を許可
     /// - `additional_allowed_directories.paths` 配下は追加で許可
+    ///
+    /// 呼び出しごとにスコープを解決し直すため、コマンドの実行中は `scope_set` で構築した
+    /// `ScopeSet` を使い回す。判定のみを行い、スコープのルートは開かない。
     pub fn is_path_allowed(&self, path: &Path) -> bool {
-        let Some(resolved_target) = Self::resolve_target_path_without_symlink_resolution(path)
-        else {
-            return false;
-        };
-
-        for scope in self.allowed_scopes() {
-            if resolved_target.starts_with(scope) {
-                return true;
-            }
-        }
-
-        false
-    }
-
-    /// 判定対象パスを絶対パスへ解決する。
-    fn resolve_target_path_without_symlink_resolution(path: &Path) -> Option<PathBuf> {
-        let absolute_path = if path.is_absolute() {
-            path.to_path_buf()
-        } else {
-            let cwd = std::env::current_dir().ok()?;
-            cwd.join(path)
-        };
-
-        Some(Self::canonicalize_preserving_symlink_leaf(&absolute_path))
+        ScopeSet::without_roots(self).contains(path)
     }
 
     /// 最終要素がシンボリックリンクの場合はリンク先へ辿らず、親ディレクトリのみ実体解決する。
@@ -176,26 +333,6 @@ impl Config {
         Self::normalize_lexically(&resolved)
     }
 
-    /// 許可された操作スコープ一覧を構築する。
-    fn allowed_scopes(&self) -> Vec<PathBuf> {
-        let mut scopes = Vec::new();
-
-        if let Ok(cwd) = std::env::current_dir() {
-            scopes.push(cwd.canonicalize().unwrap_or(cwd));
-        }
-
-        for dir in &self.additional_allowed_directories.paths {
-            let resolved = if dir.exists() {
-                dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
-            } else {
-                dir.to_path_buf()
-            };
-            scopes.push(resolved);
-        }
-
-        scopes
-    }
-
     /// `.` と `..` を語彙的に解決し、比較可能なパスへ正規化する。
     fn normalize_lexically(path: &Path) -> PathBuf {
         let mut normalized = PathBuf::new();
@@ -317,7 +454,7 @@ mod tests {
             additional_allowed_directories: AdditionalAllowedDirectories {
                 paths: vec![external.clone()],
             },
-            notify: NotifyConfig::default(),
+            ..Config::default()
         };
 
         assert!(config.is_path_allowed(&external_file));
@@ -349,7 +486,7 @@ mod tests {
             additional_allowed_directories: AdditionalAllowedDirectories {
                 paths: vec![external],
             },
-            notify: NotifyConfig::default(),
+            ..Config::default()
         };
 
         assert!(!config.is_path_allowed(&forbidden_file));
@@ -357,6 +494,98 @@ mod tests {
         std::env::set_current_dir(original).unwrap();
     }
 
+    #[test]
+    fn test_is_path_allowed_denies_cwd_when_implicit_scope_disabled() {
+        // implicit_cwd_scope=false の場合はカレント配下でも明示スコープ外なら拒否することを確認する。
+        let _guard = TEST_MUTEX.lock().unwrap();
+        setup_test_env();
+
+        let temp_dir = TempDir::new().unwrap();
+        let cwd = temp_dir.path().join("workspace");
+        let external = temp_dir.path().join("external");
+        fs::create_dir(&cwd).unwrap();
+        fs::create_dir(&external).unwrap();
+        fs::write(cwd.join("target.txt"), "content").unwrap();
+
+        let original = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&cwd).unwrap();
+
+        let config = Config {
+            implicit_cwd_scope: ImplicitCwdScope::Disabled,
+            additional_allowed_directories: AdditionalAllowedDirectories {
+                paths: vec![external.clone()],
+            },
+            ..Config::default()
+        };
+
+        assert!(!config.is_path_allowed(Path::new("target.txt")));
+        assert!(config.is_path_allowed(&external.join("extra.txt")));
+
+        std::env::set_current_dir(original).unwrap();
+    }
+
+    #[test]
+    fn test_is_path_allowed_uses_git_root_as_implicit_scope() {
+        // implicit_cwd_scope="git-root" の場合はサブディレクトリからでもリポジトリ全体を許可することを確認する。
+        let _guard = TEST_MUTEX.lock().unwrap();
+        setup_test_env();
+
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        let subdir = repo.join("sub");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(&subdir).unwrap();
+        let sibling_file = repo.join("README.md");
+        fs::write(&sibling_file, "readme").unwrap();
+        let outside_file = temp_dir.path().join("outside.txt");
+        fs::write(&outside_file, "outside").unwrap();
+
+        let original = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&subdir).unwrap();
+
+        let config = Config {
+            implicit_cwd_scope: ImplicitCwdScope::GitRoot,
+            ..Config::default()
+        };
+
+        assert!(config.is_path_allowed(&sibling_file));
+        assert!(!config.is_path_allowed(&outside_file));
+
+        std::env::set_current_dir(original).unwrap();
+    }
+
+    #[test]
+    fn test_is_path_allowed_git_root_mode_has_no_implicit_scope_outside_repository() {
+        // implicit_cwd_scope="git-root" でリポジトリ外にいる場合はカレント配下も拒否することを確認する。
+        let _guard = TEST_MUTEX.lock().unwrap();
+        setup_test_env();
+
+        let temp_dir = TempDir::new().unwrap();
+        let cwd = temp_dir.path().join("workspace");
+        fs::create_dir(&cwd).unwrap();
+        fs::write(cwd.join("target.txt"), "content").unwrap();
+        // 一時ディレクトリの上位に `.git` がある環境ではリポジトリ外を用意できないため確認しない。
+        let canonical_cwd = cwd.canonicalize().unwrap();
+        if canonical_cwd
+            .ancestors()
+            .any(|dir| fs::symlink_metadata(dir.join(".git")).is_ok())
+        {
+            return;
+        }
+
+        let original = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&cwd).unwrap();
+
+        let config = Config {
+            implicit_cwd_scope: ImplicitCwdScope::GitRoot,
+            ..Config::default()
+        };
+        let allowed = config.is_path_allowed(Path::new("target.txt"));
+
+        std::env::set_current_dir(original).unwrap();
+        assert!(!allowed);
+    }
+
     #[cfg(unix)]
     #[test]
     fn test_is_path_allowed_uses_symlink_path_instead_of_target() {
@@ -387,6 +616,43 @@ mod tests {
         std::env::set_current_dir(original).unwrap();
     }
 
+    #[test]
+    fn test_backup_scope_for_prefers_deepest_matching_scope() {
+        // 対象を含む [[backup.scopes]] のうち最も深いものが選ばれ、含まない場合は None となることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let outer = temp_dir.path().join("outer");
+        let inner = outer.join("inner");
+        fs::create_dir_all(&inner).unwrap();
+        let config: Config = toml::from_str(&format!(
+            r#"[additional_allowed_directories]
+paths = []
+
+[[backup.scopes]]
+path = "{}"
+control = "numbered"
+
+[[backup.scopes]]
+path = "{}"
+control = "simple"
+suffix = ".bak"
+"#,
+            outer.display(),
+            inner.display()
+        ))
+        .unwrap();
+
+        let scopes = config.backup.resolve_scopes();
+        let scope = scopes.scope_for(&inner.join("file.txt")).unwrap();
+        assert_eq!(scope.control, BackupControl::Simple);
+        let scope = scopes.scope_for(&outer.join("file.txt")).unwrap();
+        assert_eq!(scope.control, BackupControl::Numbered);
+        assert!(
+            scopes
+                .scope_for(&temp_dir.path().join("file.txt"))
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_load_allows_empty_additional_paths() {
         // `paths = []` を許容し、追加許可なし設定として読み込めることを確認する。
@@ -410,6 +676,7 @@ paths = []
         let loaded = Config::load().unwrap();
         assert!(loaded.additional_allowed_directories.paths.is_empty());
         assert!(!loaded.notify.macos_notify);
+        assert_eq!(loaded.implicit_cwd_scope, ImplicitCwdScope::Cwd);
     }
 
     #[test]
@@ -439,6 +706,125 @@ macos_notify = true
         assert!(loaded.notify.macos_notify);
     }
 
+    #[test]
+    fn test_load_accepts_git_protect_dirty_setting() {
+        // git.protect_dirty を設定ファイルから読み込め、未指定時は無効であることを確認する。
+        let _guard = TEST_MUTEX.lock().unwrap();
+        setup_test_env();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        unsafe {
+            std::env::set_var("SAFECMD_CONFIG_PATH", &config_path);
+        }
+
+        fs::write(
+            &config_path,
+            r#"[additional_allowed_directories]
+paths = []
+"#,
+        )
+        .unwrap();
+        assert!(!Config::load().unwrap().git.protect_dirty);
+
+        fs::write(
+            &config_path,
+            r#"[additional_allowed_directories]
+paths = []
+
+[git]
+protect_dirty = true
+"#,
+        )
+        .unwrap();
+        assert!(Config::load().unwrap().git.protect_dirty);
+    }
+
+    #[test]
+    fn test_load_accepts_limits_settings() {
+        // limits.max_files / limits.max_bytes を読み込め、未指定時は無制限であることを確認する。
+        let _guard = TEST_MUTEX.lock().unwrap();
+        setup_test_env();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"[additional_allowed_directories]
+paths = []
+
+[limits]
+max_files = 1000
+"#,
+        )
+        .unwrap();
+
+        unsafe {
+            std::env::set_var("SAFECMD_CONFIG_PATH", &config_path);
+        }
+
+        let loaded = Config::load().unwrap();
+        assert_eq!(loaded.limits.max_files, Some(1000));
+        assert_eq!(loaded.limits.max_bytes, None);
+        assert!(loaded.limits.is_configured());
+    }
+
+    #[test]
+    fn test_load_accepts_implicit_cwd_scope_values() {
+        // implicit_cwd_scope に false / "cwd" / "git-root" を指定できることを確認する。
+        let _guard = TEST_MUTEX.lock().unwrap();
+        setup_test_env();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        unsafe {
+            std::env::set_var("SAFECMD_CONFIG_PATH", &config_path);
+        }
+
+        for (value, expected) in [
+            ("false", ImplicitCwdScope::Disabled),
+            ("\"cwd\"", ImplicitCwdScope::Cwd),
+            ("\"git-root\"", ImplicitCwdScope::GitRoot),
+        ] {
+            fs::write(
+                &config_path,
+                format!(
+                    "implicit_cwd_scope = {value}\n\n[additional_allowed_directories]\npaths = []\n"
+                ),
+            )
+            .unwrap();
+
+            let loaded = Config::load().unwrap();
+            assert_eq!(loaded.implicit_cwd_scope, expected);
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_implicit_cwd_scope() {
+        // implicit_cwd_scope に未知の値を指定した場合は設定エラーとなることを確認する。
+        let _guard = TEST_MUTEX.lock().unwrap();
+        setup_test_env();
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"implicit_cwd_scope = "home"
+
+[additional_allowed_directories]
+paths = []
+"#,
+        )
+        .unwrap();
+
+        unsafe {
+            std::env::set_var("SAFECMD_CONFIG_PATH", &config_path);
+        }
+
+        let err = Config::load().unwrap_err();
+        assert!(err.contains("invalid implicit_cwd_scope"));
+    }
+
     #[test]
     fn test_load_rejects_relative_additional_path() {
         // 相対パス指定を設定エラーとして拒否することを確認する。
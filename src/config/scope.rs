@@ -0,0 +1,345 @@
+use crate::config::{Config, ImplicitCwdScope};
+use crate::dirfd::{Dir, Pinned};
+use crate::git;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// 実行開始時に解決した許可スコープを保持し、パスの判定に使い回す。
+///
+/// スコープは構築時に一度だけ `canonicalize()` し、パス要素の接頭辞木で判定する。
+/// 実行中にスコープのディレクトリが変更されても判定結果は変わらず、
+/// 最新の状態を反映するには `refresh` を明示的に呼ぶ。
+/// 存在するスコープのルートは構築時に一度だけ開き、書き込み先の親ディレクトリは
+/// `open_parent` でそこからシンボリックリンクを辿らずに開き直す。
+#[derive(Debug, Clone, Default)]
+pub struct ScopeSet {
+    implicit_cwd_scope: ImplicitCwdScope,
+    additional: Vec<PathBuf>,
+    /// 相対パスの解決に使う、構築時のカレントディレクトリ。
+    cwd: Option<PathBuf>,
+    scopes: Vec<PathBuf>,
+    /// `scopes` と同じ順で、開いたスコープのルート。存在しないスコープは `None`。
+    roots: Vec<Option<Arc<Dir>>>,
+    tree: ScopeNode,
+}
+
+/// スコープのパス要素を辿る接頭辞木のノード。
+#[derive(Debug, Clone, Default)]
+struct ScopeNode {
+    /// このノードまでの要素がいずれかのスコープと一致する。
+    terminal: bool,
+    children: HashMap<OsString, ScopeNode>,
+}
+
+impl ScopeSet {
+    /// 設定からスコープを解決して構築する。
+    pub fn new(config: &Config) -> Self {
+        let mut scopes = Self {
+            implicit_cwd_scope: config.implicit_cwd_scope,
+            additional: config.additional_allowed_directories.paths.clone(),
+            ..Self::default()
+        };
+        scopes.refresh();
+        scopes
+    }
+
+    /// 設定からスコープを解決し、ルートを開かずに構築する。
+    ///
+    /// `contains` による判定だけに使い、ルートのディスクリプタを確保しない。
+    /// ルートを持たないため `open_parent` には使えない。
+    pub(crate) fn without_roots(config: &Config) -> Self {
+        let mut scopes = Self {
+            implicit_cwd_scope: config.implicit_cwd_scope,
+            additional: config.additional_allowed_directories.paths.clone(),
+            ..Self::default()
+        };
+        scopes.resolve();
+        scopes
+    }
+
+    /// カレントディレクトリと各スコープを解決し直す。
+    pub fn refresh(&mut self) {
+        self.resolve();
+        self.roots = self
+            .scopes
+            .iter()
+            .map(|scope| Dir::open(scope).ok().map(Arc::new))
+            .collect();
+    }
+
+    /// カレントディレクトリと各スコープを解決し、判定用の接頭辞木を作り直す。
+    fn resolve(&mut self) {
+        self.cwd = std::env::current_dir().ok();
+        self.scopes = self.resolve_scopes();
+        self.tree = ScopeNode::default();
+        for scope in &self.scopes {
+            self.tree.insert(scope);
+        }
+    }
+
+    /// 解決済みのスコープ一覧を返す。
+    pub fn scopes(&self) -> &[PathBuf] {
+        &self.scopes
+    }
+
+    /// 指定パスがいずれかのスコープ配下にあるかを判定する。
+    ///
+    /// 判定対象パスの最終要素がシンボリックリンクの場合はリンク先へ辿らない。
+    pub fn contains(&self, path: &Path) -> bool {
+        let Some(absolute_path) = self.absolutize(path) else {
+            return false;
+        };
+        let resolved_target = Config::canonicalize_preserving_symlink_leaf(&absolute_path);
+        self.tree.contains_prefix_of(&resolved_target)
+    }
+
+    /// 指定パスの親ディレクトリを、それを含むスコープのルートからシンボリックリンクを辿らずに開く。
+    ///
+    /// 返した `Pinned` 経由の操作は、その後に途中のパス要素がシンボリックリンクへ差し替えられても
+    /// 開いたディレクトリに対して行われる。親ディレクトリの実体がスコープ外の場合や、
+    /// ルートから辿る途中でシンボリックリンクに当たった場合は失敗する。
+    /// スコープのルート自身を対象とする場合のみ、スコープ外の親ディレクトリをそのまま開く。
+    pub fn open_parent(&self, path: &Path) -> io::Result<Pinned> {
+        let absolute_path = self.absolutize(path).ok_or_else(outside_scope)?;
+        let name = absolute_path.file_name().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid path '{}'", path.display()),
+            )
+        })?;
+        let parent = absolute_path
+            .parent()
+            .unwrap_or(Path::new("/"))
+            .canonicalize()?;
+
+        let containing_root = self
+            .scopes
+            .iter()
+            .zip(&self.roots)
+            .filter_map(|(scope, root)| Some((parent.strip_prefix(scope).ok()?, root.as_ref()?)))
+            .min_by_key(|(relative, _)| relative.components().count());
+        let dir = match containing_root {
+            Some((relative, root)) => root.open_beneath(relative).map_err(|e| {
+                if is_symlink_error(&e) {
+                    outside_scope()
+                } else {
+                    e
+                }
+            })?,
+            None if self.tree.contains_prefix_of(&parent.join(name)) => Dir::open(&parent)?,
+            None => return Err(outside_scope()),
+        };
+        Ok(Pinned {
+            dir,
+            name: name.to_os_string(),
+        })
+    }
+
+    /// 相対パスを構築時のカレントディレクトリ基準の絶対パスにする。
+    fn absolutize(&self, path: &Path) -> Option<PathBuf> {
+        if path.is_absolute() {
+            return Some(path.to_path_buf());
+        }
+        Some(self.cwd.as_ref()?.join(path))
+    }
+
+    /// 暗黙スコープと追加許可ディレクトリを実体パスへ解決する。
+    fn resolve_scopes(&self) -> Vec<PathBuf> {
+        let mut scopes = Vec::new();
+
+        if let Some(scope) = self.implicit_scope() {
+            scopes.push(scope);
+        }
+
+        for dir in &self.additional {
+            let resolved = if dir.exists() {
+                dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+            } else {
+                dir.to_path_buf()
+            };
+            scopes.push(resolved);
+        }
+
+        scopes
+    }
+
+    /// `implicit_cwd_scope` に応じてカレントディレクトリ由来の暗黙スコープを決定する。
+    ///
+    /// `git-root` 指定時にリポジトリ外で実行された場合は暗黙スコープを持たない。
+    fn implicit_scope(&self) -> Option<PathBuf> {
+        if self.implicit_cwd_scope == ImplicitCwdScope::Disabled {
+            return None;
+        }
+
+        let cwd = self.cwd.clone()?;
+        let cwd = cwd.canonicalize().unwrap_or(cwd);
+
+        match self.implicit_cwd_scope {
+            ImplicitCwdScope::Cwd => Some(cwd),
+            ImplicitCwdScope::GitRoot => git::find_work_tree_root(&cwd),
+            ImplicitCwdScope::Disabled => None,
+        }
+    }
+}
+
+impl ScopeNode {
+    fn insert(&mut self, scope: &Path) {
+        let mut node = self;
+        for component in scope.components() {
+            node = node.children.entry(component_key(component)).or_default();
+        }
+        node.terminal = true;
+    }
+
+    /// パスの先頭からいずれかのスコープと要素単位で一致するかを返す。
+    fn contains_prefix_of(&self, path: &Path) -> bool {
+        let mut node = self;
+        if node.terminal {
+            return true;
+        }
+        for component in path.components() {
+            let Some(child) = node.children.get(component.as_os_str()) else {
+                return false;
+            };
+            if child.terminal {
+                return true;
+            }
+            node = child;
+        }
+        false
+    }
+}
+
+fn component_key(component: Component<'_>) -> OsString {
+    component.as_os_str().to_os_string()
+}
+
+fn outside_scope() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "path is outside allowed scope",
+    )
+}
+
+/// `O_NOFOLLOW` で開いた要素がシンボリックリンクだった場合のエラーかを判定する。
+fn is_symlink_error(err: &io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        err.raw_os_error() == Some(libc::ELOOP)
+    }
+    #[cfg(not(unix))]
+    {
+        err.kind() == io::ErrorKind::NotADirectory
+    }
+}
+
+impl Config {
+    /// 許可スコープを解決し、判定に使い回せる `ScopeSet` を構築する。
+    pub fn scope_set(&self) -> ScopeSet {
+        ScopeSet::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AdditionalAllowedDirectories;
+    use tempfile::TempDir;
+
+    fn scope_set_for(paths: Vec<PathBuf>) -> ScopeSet {
+        ScopeSet::new(&Config {
+            implicit_cwd_scope: ImplicitCwdScope::Disabled,
+            additional_allowed_directories: AdditionalAllowedDirectories { paths },
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn contains_matches_whole_path_components_only() {
+        // スコープとの一致をパス要素単位で判定し、名前の前方一致だけでは許可しないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let allowed = temp_dir.path().join("data");
+        let sibling = temp_dir.path().join("data-other");
+        std::fs::create_dir_all(allowed.join("nested")).unwrap();
+        std::fs::create_dir_all(&sibling).unwrap();
+        let scopes = scope_set_for(vec![allowed.clone()]);
+
+        assert!(scopes.contains(&allowed));
+        assert!(scopes.contains(&allowed.join("nested").join("missing.txt")));
+        assert!(!scopes.contains(&sibling.join("file.txt")));
+        assert!(!scopes.contains(temp_dir.path()));
+    }
+
+    #[test]
+    fn without_roots_judges_paths_without_opening_roots() {
+        // ルートを開かずに構築しても、開いた場合と同じ判定になることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let allowed = temp_dir.path().join("data");
+        std::fs::create_dir(&allowed).unwrap();
+        let scopes = ScopeSet::without_roots(&Config {
+            implicit_cwd_scope: ImplicitCwdScope::Disabled,
+            additional_allowed_directories: AdditionalAllowedDirectories {
+                paths: vec![allowed.clone()],
+            },
+            ..Config::default()
+        });
+
+        assert!(scopes.roots.is_empty());
+        assert!(scopes.contains(&allowed.join("file.txt")));
+        assert!(!scopes.contains(&temp_dir.path().join("file.txt")));
+    }
+
+    #[test]
+    fn scopes_are_fixed_until_refresh() {
+        // 構築後にスコープのディレクトリが作成されても、refresh するまでは構築時の解決結果を使うことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let real = temp_dir.path().join("real");
+        let alias = temp_dir.path().join("alias");
+        std::fs::create_dir(&real).unwrap();
+        let mut scopes = scope_set_for(vec![alias.clone()]);
+        assert_eq!(scopes.scopes(), std::slice::from_ref(&alias));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&real, &alias).unwrap();
+            assert!(!scopes.contains(&real.join("file.txt")));
+
+            scopes.refresh();
+
+            assert_eq!(scopes.scopes(), [real.canonicalize().unwrap()]);
+            assert!(scopes.contains(&real.join("file.txt")));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn open_parent_refuses_symlinked_component_inside_scope() {
+        // スコープ内の途中要素が外部へのシンボリックリンクの場合、親ディレクトリを開かないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let allowed = temp_dir.path().join("allowed");
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir_all(allowed.join("real")).unwrap();
+        std::fs::create_dir(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, allowed.join("link")).unwrap();
+        let scopes = scope_set_for(vec![allowed.clone()]);
+
+        let pinned = scopes
+            .open_parent(&allowed.join("real").join("new.txt"))
+            .unwrap();
+        assert_eq!(pinned.name, "new.txt");
+        assert_eq!(
+            pinned.dir.path(),
+            allowed.canonicalize().unwrap().join("real")
+        );
+        assert!(
+            scopes
+                .open_parent(&allowed.join("link").join("new.txt"))
+                .is_err()
+        );
+        assert!(scopes.open_parent(&outside.join("new.txt")).is_err());
+        assert!(scopes.open_parent(&allowed).is_ok());
+    }
+}
@@ -0,0 +1,744 @@
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+#[cfg(unix)]
+use std::ffi::CString;
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+
+/// rename 時に既存の移動先をどう扱うかを表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameMode {
+    /// 既存の移動先を置き換える（`renameat`）。
+    Replace,
+    /// 移動先が存在する場合は `AlreadyExists` で失敗する（`RENAME_NOREPLACE`）。
+    NoReplace,
+    /// 移動元と移動先を不可分に入れ替える（`RENAME_EXCHANGE`）。両方が存在する必要がある。
+    Exchange,
+}
+
+/// 開いたディレクトリのファイルディスクリプタを保持し、配下の操作をそこからの相対で行う。
+///
+/// 一度開いたディレクトリは、途中のパス要素がシンボリックリンクへ差し替えられても同じ実体を指し続ける。
+/// 配下の操作は `openat`・`mkdirat`・`renameat2` 等でこのディスクリプタを基準に行うため、
+/// 検証後にパスを差し替えられても許可範囲外へ書き込まない。
+#[derive(Debug)]
+pub struct Dir {
+    #[cfg(unix)]
+    fd: OwnedFd,
+    path: PathBuf,
+}
+
+/// 許可範囲内で開いた親ディレクトリと、その中のエントリ名の組。
+#[derive(Debug)]
+pub struct Pinned {
+    pub dir: Dir,
+    pub name: OsString,
+}
+
+impl Pinned {
+    /// エントリをディスクリプタ経由で指すパスを返す。
+    pub fn entry_path(&self) -> PathBuf {
+        self.dir.entry_path(&self.name)
+    }
+
+    /// 開いた時点のパス表記でエントリを指すパスを返す。
+    pub fn display_path(&self) -> PathBuf {
+        self.dir.path().join(&self.name)
+    }
+
+    /// 同じ親ディレクトリとエントリ名を指す別のハンドルを返す。
+    pub fn try_clone(&self) -> io::Result<Pinned> {
+        Ok(Pinned {
+            dir: self.dir.try_clone()?,
+            name: self.name.clone(),
+        })
+    }
+}
+
+impl Dir {
+    /// 指定パスのディレクトリを開く。最終要素のシンボリックリンクも辿る。
+    ///
+    /// 正規化済みのスコープルートを開く際に使う。
+    pub fn open(path: &Path) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            let fd = open_at(
+                libc::AT_FDCWD,
+                path.as_os_str(),
+                libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC,
+                0,
+            )?;
+            Ok(Self {
+                fd,
+                path: path.to_path_buf(),
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            if !fs::metadata(path)?.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotADirectory,
+                    "not a directory",
+                ));
+            }
+            Ok(Self {
+                path: path.to_path_buf(),
+            })
+        }
+    }
+
+    /// 開いた時点のパス表記を返す。メッセージ表示用で、操作には使わない。
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 直下のディレクトリをシンボリックリンクを辿らずに開く。
+    pub fn open_dir(&self, name: &OsStr) -> io::Result<Dir> {
+        let name = checked_name(name)?;
+        #[cfg(unix)]
+        {
+            let fd = open_at(
+                self.fd.as_raw_fd(),
+                name,
+                libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+                0,
+            )?;
+            Ok(Dir {
+                fd,
+                path: self.path.join(name),
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            let path = self.path.join(name);
+            if !fs::symlink_metadata(&path)?.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotADirectory,
+                    "not a directory",
+                ));
+            }
+            Ok(Dir { path })
+        }
+    }
+
+    /// 直下の通常ファイルをシンボリックリンクを辿らずに読み込み用に開く。
+    ///
+    /// 確認後に FIFO 等へ差し替えられても開く処理で停止しないよう `O_NONBLOCK` で開き、
+    /// 開いたものが通常ファイルでなければ失敗する。
+    pub fn open_file(&self, name: &OsStr) -> io::Result<fs::File> {
+        let name = checked_name(name)?;
+        #[cfg(unix)]
+        let file = fs::File::from(open_at(
+            self.fd.as_raw_fd(),
+            name,
+            libc::O_RDONLY | libc::O_NOFOLLOW | libc::O_NONBLOCK | libc::O_CLOEXEC,
+            0,
+        )?);
+        #[cfg(not(unix))]
+        let file = {
+            let path = self.path.join(name);
+            if fs::symlink_metadata(&path)?.file_type().is_symlink() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "is a symbolic link",
+                ));
+            }
+            fs::File::open(path)?
+        };
+        if !file.metadata()?.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a regular file",
+            ));
+        }
+        Ok(file)
+    }
+
+    /// 相対パスの各要素をシンボリックリンクを辿らずに開き、配下のディレクトリを返す。
+    ///
+    /// `..` や絶対パスの要素は配下から抜け出せるため拒否する。
+    pub fn open_beneath(&self, relative: &Path) -> io::Result<Dir> {
+        let mut names = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(name) => names.push(name),
+                Component::CurDir => {}
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "path escapes the directory",
+                    ));
+                }
+            }
+        }
+        let Some((first, rest)) = names.split_first() else {
+            return self.try_clone();
+        };
+        let mut dir = self.open_dir(first)?;
+        for name in rest {
+            dir = dir.open_dir(name)?;
+        }
+        Ok(dir)
+    }
+
+    /// 同じディレクトリを指す別のハンドルを返す。
+    pub fn try_clone(&self) -> io::Result<Dir> {
+        Ok(Dir {
+            #[cfg(unix)]
+            fd: self.fd.try_clone()?,
+            path: self.path.clone(),
+        })
+    }
+
+    /// ディレクトリエントリの更新を永続化するため fsync する。
+    pub fn sync_all(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            fs::File::from(self.fd.try_clone()?).sync_all()
+        }
+        #[cfg(not(unix))]
+        {
+            Ok(())
+        }
+    }
+
+    /// 直下のエントリをディスクリプタ経由で指すパスを返す。
+    ///
+    /// Linux では `/proc/self/fd/<fd>/<name>` を返すため、パスを受け取る既存の処理へ渡しても
+    /// 親ディレクトリのパスを辿り直さない。それ以外では開いた時点のパス表記を返す。
+    pub fn entry_path(&self, name: &OsStr) -> PathBuf {
+        #[cfg(target_os = "linux")]
+        if Path::new("/proc/self/fd").is_dir() {
+            return PathBuf::from(format!("/proc/self/fd/{}", self.fd.as_raw_fd())).join(name);
+        }
+        self.path.join(name)
+    }
+
+    /// 開いたディレクトリ自身のメタデータを取得する。
+    pub fn metadata(&self) -> io::Result<fs::Metadata> {
+        #[cfg(unix)]
+        {
+            fs::File::from(self.fd.try_clone()?).metadata()
+        }
+        #[cfg(not(unix))]
+        {
+            fs::metadata(&self.path)
+        }
+    }
+
+    /// 直下のエントリ名をファイル名順に返す。
+    pub fn entry_names(&self) -> io::Result<Vec<OsString>> {
+        let mut names = fs::read_dir(self.entry_path(OsStr::new(".")))?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<io::Result<Vec<_>>>()?;
+        names.sort();
+        Ok(names)
+    }
+
+    /// 直下のエントリのメタデータをシンボリックリンクを辿らずに取得する。
+    pub fn symlink_metadata(&self, name: &OsStr) -> io::Result<fs::Metadata> {
+        fs::symlink_metadata(self.entry_path(checked_name(name)?))
+    }
+
+    /// 直下のエントリが存在するか（壊れたリンクを含む）を返す。
+    pub fn contains(&self, name: &OsStr) -> bool {
+        self.symlink_metadata(name).is_ok()
+    }
+
+    /// 直下へ新しいファイルを作成する。既存エントリやシンボリックリンクがある場合は失敗する。
+    pub fn create_file(&self, name: &OsStr, mode: u32) -> io::Result<fs::File> {
+        let name = checked_name(name)?;
+        #[cfg(unix)]
+        {
+            let fd = open_at(
+                self.fd.as_raw_fd(),
+                name,
+                libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+                mode,
+            )?;
+            Ok(fs::File::from(fd))
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = mode;
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(self.path.join(name))
+        }
+    }
+
+    /// 直下へディレクトリを作成する。
+    pub fn create_dir(&self, name: &OsStr, mode: u32) -> io::Result<()> {
+        let name = checked_name(name)?;
+        #[cfg(unix)]
+        {
+            let c_name = c_string(name)?;
+            // SAFETY: fd は有効なディレクトリで、c_name は NUL 終端済みの文字列。
+            let result = unsafe {
+                libc::mkdirat(self.fd.as_raw_fd(), c_name.as_ptr(), mode as libc::mode_t)
+            };
+            cvt(result)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = mode;
+            fs::create_dir(self.path.join(name))
+        }
+    }
+
+    /// 直下へシンボリックリンクを作成する。
+    pub fn symlink(&self, target: &Path, name: &OsStr) -> io::Result<()> {
+        let name = checked_name(name)?;
+        #[cfg(unix)]
+        {
+            let c_target = c_string(target.as_os_str())?;
+            let c_name = c_string(name)?;
+            // SAFETY: fd は有効なディレクトリで、両文字列は NUL 終端済み。
+            let result =
+                unsafe { libc::symlinkat(c_target.as_ptr(), self.fd.as_raw_fd(), c_name.as_ptr()) };
+            cvt(result)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = target;
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "cannot create symbolic link '{}'",
+                    self.path.join(name).display()
+                ),
+            ))
+        }
+    }
+
+    /// 直下のファイルやリンクを削除する。
+    pub fn remove_file(&self, name: &OsStr) -> io::Result<()> {
+        self.unlink(name, false)
+    }
+
+    /// 直下の空ディレクトリを削除する。
+    pub fn remove_dir(&self, name: &OsStr) -> io::Result<()> {
+        self.unlink(name, true)
+    }
+
+    fn unlink(&self, name: &OsStr, dir: bool) -> io::Result<()> {
+        let name = checked_name(name)?;
+        #[cfg(unix)]
+        {
+            let c_name = c_string(name)?;
+            let flags = if dir { libc::AT_REMOVEDIR } else { 0 };
+            // SAFETY: fd は有効なディレクトリで、c_name は NUL 終端済みの文字列。
+            let result = unsafe { libc::unlinkat(self.fd.as_raw_fd(), c_name.as_ptr(), flags) };
+            cvt(result)
+        }
+        #[cfg(not(unix))]
+        {
+            let path = self.path.join(name);
+            if dir {
+                fs::remove_dir(path)
+            } else {
+                fs::remove_file(path)
+            }
+        }
+    }
+
+    /// 直下のエントリを `to` ディレクトリ直下へハードリンクする。リンク自体は辿らない。
+    pub fn hard_link(&self, name: &OsStr, to: &Dir, to_name: &OsStr) -> io::Result<()> {
+        let name = checked_name(name)?;
+        let to_name = checked_name(to_name)?;
+        #[cfg(unix)]
+        {
+            let c_name = c_string(name)?;
+            let c_to_name = c_string(to_name)?;
+            // SAFETY: 両ディスクリプタは有効なディレクトリで、両文字列は NUL 終端済み。
+            let result = unsafe {
+                libc::linkat(
+                    self.fd.as_raw_fd(),
+                    c_name.as_ptr(),
+                    to.fd.as_raw_fd(),
+                    c_to_name.as_ptr(),
+                    0,
+                )
+            };
+            cvt(result)
+        }
+        #[cfg(not(unix))]
+        {
+            fs::hard_link(self.path.join(name), to.path.join(to_name))
+        }
+    }
+
+    /// 直下のエントリを `to` ディレクトリ直下へ rename する。
+    ///
+    /// `RenameMode::NoReplace` は Linux では `renameat2(RENAME_NOREPLACE)`、macOS では
+    /// `renameatx_np(RENAME_EXCL)` により、存在確認と rename を不可分に行う。
+    /// ファイルシステムやカーネルがフラグに対応しない場合は、`linkat` で移動先を排他的に作成してから
+    /// 移動元を `unlinkat` する。ハードリンクできないディレクトリ等に限り、存在確認後に rename する。
+    /// `RenameMode::Exchange` は Linux の `RENAME_EXCHANGE`、macOS の `RENAME_SWAP` を使い、
+    /// 代替手段はない。
+    pub fn rename(
+        &self,
+        name: &OsStr,
+        to: &Dir,
+        to_name: &OsStr,
+        mode: RenameMode,
+    ) -> io::Result<()> {
+        let name = checked_name(name)?;
+        let to_name = checked_name(to_name)?;
+        #[cfg(unix)]
+        {
+            let c_name = c_string(name)?;
+            let c_to_name = c_string(to_name)?;
+            match rename_at(
+                self.fd.as_raw_fd(),
+                &c_name,
+                to.fd.as_raw_fd(),
+                &c_to_name,
+                mode,
+            ) {
+                Err(e) if mode == RenameMode::NoReplace && is_flag_unsupported(&e) => {
+                    self.rename_without_flags(name, to, to_name)
+                }
+                Err(e) if mode == RenameMode::Exchange && is_flag_unsupported(&e) => {
+                    Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "atomic exchange is not supported on this file system",
+                    ))
+                }
+                result => result,
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            match mode {
+                RenameMode::Replace => fs::rename(self.path.join(name), to.path.join(to_name)),
+                RenameMode::NoReplace => self.rename_without_flags(name, to, to_name),
+                RenameMode::Exchange => Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "atomic exchange is not supported on this platform",
+                )),
+            }
+        }
+    }
+
+    /// `RENAME_NOREPLACE` を使えない場合の代替として、移動先を上書きせずに移動する。
+    ///
+    /// ハードリンクは既存の移動先を置き換えないため、`linkat` の成功後に移動元を削除する。
+    /// 移動元がディレクトリの場合やハードリンクに対応しないファイルシステムでは、
+    /// 存在確認と rename の間に作成された移動先を置き換えうる。
+    fn rename_without_flags(&self, name: &OsStr, to: &Dir, to_name: &OsStr) -> io::Result<()> {
+        let is_dir = self.symlink_metadata(name)?.is_dir();
+        if !is_dir {
+            match self.hard_link(name, to, to_name) {
+                Ok(()) => return self.remove_file(name),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Err(e),
+                Err(_) => {}
+            }
+        }
+        if to.contains(to_name) {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        #[cfg(unix)]
+        {
+            let c_name = c_string(name)?;
+            let c_to_name = c_string(to_name)?;
+            rename_at(
+                self.fd.as_raw_fd(),
+                &c_name,
+                to.fd.as_raw_fd(),
+                &c_to_name,
+                RenameMode::Replace,
+            )
+        }
+        #[cfg(not(unix))]
+        {
+            fs::rename(self.path.join(name), to.path.join(to_name))
+        }
+    }
+
+    /// 開いた時点のパス表記が、今もこのディレクトリを指しているかを確認してパスを返す。
+    ///
+    /// パスしか受け付けない処理（ゴミ箱への移動等）へ渡す直前に呼び、差し替えを検出した場合は失敗する。
+    pub fn verified_path(&self, name: &OsStr) -> io::Result<PathBuf> {
+        let name = checked_name(name)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let by_path = fs::metadata(&self.path)?;
+            let pinned = self.metadata()?;
+            if (by_path.dev(), by_path.ino()) != (pinned.dev(), pinned.ino()) {
+                return Err(io::Error::other(format!(
+                    "'{}' was replaced during the operation",
+                    self.path.display()
+                )));
+            }
+        }
+        Ok(self.path.join(name))
+    }
+}
+
+/// エントリ名として1要素の通常の名前のみを受け付ける。
+fn checked_name(name: &OsStr) -> io::Result<&OsStr> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(normal)), None) if normal == name => Ok(name),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid entry name '{}'", Path::new(name).display()),
+        )),
+    }
+}
+
+#[cfg(unix)]
+fn c_string(value: &OsStr) -> io::Result<CString> {
+    CString::new(value.as_bytes()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "path contains an interior NUL byte",
+        )
+    })
+}
+
+#[cfg(unix)]
+fn cvt(result: libc::c_int) -> io::Result<()> {
+    if result == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn open_at(dirfd: libc::c_int, name: &OsStr, flags: libc::c_int, mode: u32) -> io::Result<OwnedFd> {
+    let c_name = c_string(name)?;
+    // SAFETY: c_name は NUL 終端済みで、dirfd は有効なディレクトリか AT_FDCWD。
+    let fd = unsafe { libc::openat(dirfd, c_name.as_ptr(), flags, mode as libc::c_uint) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: openat が返した fd は呼び出し元が単独で所有する。
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// rename のフラグにファイルシステムやカーネルが対応していないことを示すエラーかを判定する。
+#[cfg(unix)]
+fn is_flag_unsupported(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::Unsupported
+        || matches!(
+            err.raw_os_error(),
+            Some(libc::EINVAL | libc::ENOSYS | libc::ENOTSUP)
+        )
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn rename_at(
+    from_dir: libc::c_int,
+    from: &CString,
+    to_dir: libc::c_int,
+    to: &CString,
+    mode: RenameMode,
+) -> io::Result<()> {
+    let flags = match mode {
+        RenameMode::Replace => 0,
+        RenameMode::NoReplace => libc::RENAME_NOREPLACE as libc::c_uint,
+        RenameMode::Exchange => libc::RENAME_EXCHANGE as libc::c_uint,
+    };
+    // SAFETY: 両ディスクリプタは有効なディレクトリで、両文字列は NUL 終端済み。
+    let result = unsafe { libc::renameat2(from_dir, from.as_ptr(), to_dir, to.as_ptr(), flags) };
+    cvt(result)
+}
+
+#[cfg(target_os = "macos")]
+fn rename_at(
+    from_dir: libc::c_int,
+    from: &CString,
+    to_dir: libc::c_int,
+    to: &CString,
+    mode: RenameMode,
+) -> io::Result<()> {
+    let flags = match mode {
+        RenameMode::Replace => 0,
+        RenameMode::NoReplace => libc::RENAME_EXCL,
+        RenameMode::Exchange => libc::RENAME_SWAP,
+    };
+    // SAFETY: 両ディスクリプタは有効なディレクトリで、両文字列は NUL 終端済み。
+    let result = unsafe { libc::renameatx_np(from_dir, from.as_ptr(), to_dir, to.as_ptr(), flags) };
+    cvt(result)
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "linux", target_os = "android", target_os = "macos"))
+))]
+fn rename_at(
+    from_dir: libc::c_int,
+    from: &CString,
+    to_dir: libc::c_int,
+    to: &CString,
+    mode: RenameMode,
+) -> io::Result<()> {
+    if mode != RenameMode::Replace {
+        return Err(io::Error::from(io::ErrorKind::Unsupported));
+    }
+    // SAFETY: 両ディスクリプタは有効なディレクトリで、両文字列は NUL 終端済み。
+    let result = unsafe { libc::renameat(from_dir, from.as_ptr(), to_dir, to.as_ptr()) };
+    cvt(result)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn open_beneath_refuses_symlinked_components() {
+        // 途中の要素がシンボリックリンクの場合は辿らずに失敗することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(root.join("real")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let dir = Dir::open(&root).unwrap();
+
+        assert!(dir.open_beneath(Path::new("real")).is_ok());
+        assert!(dir.open_beneath(Path::new("link")).is_err());
+        assert!(dir.open_beneath(Path::new("../outside")).is_err());
+    }
+
+    #[test]
+    fn pinned_directory_survives_path_swap() {
+        // 開いたディレクトリのパスをシンボリックリンクへ差し替えても、作成先は元の実体のままであることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let scope = temp_dir.path().join("scope");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(scope.join("sub")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        let sub = Dir::open(&scope)
+            .unwrap()
+            .open_dir(OsStr::new("sub"))
+            .unwrap();
+
+        fs::rename(scope.join("sub"), scope.join("moved")).unwrap();
+        std::os::unix::fs::symlink(&outside, scope.join("sub")).unwrap();
+        let mut file = sub.create_file(OsStr::new("data.txt"), 0o644).unwrap();
+        file.write_all(b"payload").unwrap();
+        fs::write(sub.entry_path(OsStr::new("other.txt")), "via path").unwrap();
+
+        assert_eq!(fs::read_dir(&outside).unwrap().count(), 0);
+        assert_eq!(
+            fs::read_to_string(scope.join("moved").join("data.txt")).unwrap(),
+            "payload"
+        );
+        assert!(scope.join("moved").join("other.txt").exists());
+        assert!(sub.verified_path(OsStr::new("data.txt")).is_err());
+    }
+
+    #[test]
+    fn open_file_refuses_symlinks_and_non_regular_files() {
+        // 直下のシンボリックリンクやディレクトリは辿らず、通常ファイルのみ開くことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let outside = temp_dir.path().join("outside.txt");
+        let root = temp_dir.path().join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(&outside, "secret").unwrap();
+        fs::write(root.join("data.txt"), "data").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let dir = Dir::open(&root).unwrap();
+
+        let contents = io::read_to_string(dir.open_file(OsStr::new("data.txt")).unwrap());
+        assert_eq!(contents.unwrap(), "data");
+        assert!(dir.open_file(OsStr::new("link")).is_err());
+        assert!(dir.open_file(OsStr::new("sub")).is_err());
+    }
+
+    #[test]
+    fn create_file_does_not_follow_existing_symlink() {
+        // 作成先に置かれたシンボリックリンクを辿って書き込まないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let victim = temp_dir.path().join("victim.txt");
+        fs::write(&victim, "keep").unwrap();
+        std::os::unix::fs::symlink(&victim, temp_dir.path().join("trap")).unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+
+        assert!(dir.create_file(OsStr::new("trap"), 0o644).is_err());
+        assert_eq!(fs::read_to_string(&victim).unwrap(), "keep");
+    }
+
+    #[test]
+    fn rename_no_replace_refuses_existing_target() {
+        // NoReplace の rename は既存の移動先を置き換えずに失敗することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a"), "a").unwrap();
+        fs::write(temp_dir.path().join("b"), "b").unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+
+        let err = dir
+            .rename(
+                OsStr::new("a"),
+                &dir,
+                OsStr::new("b"),
+                RenameMode::NoReplace,
+            )
+            .unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(temp_dir.path().join("b")).unwrap(), "b");
+        dir.rename(
+            OsStr::new("a"),
+            &dir,
+            OsStr::new("c"),
+            RenameMode::NoReplace,
+        )
+        .unwrap();
+        assert!(temp_dir.path().join("c").exists());
+    }
+
+    #[test]
+    fn rename_without_flags_links_then_unlinks_without_replacing() {
+        // RENAME_NOREPLACE を使えない場合の代替でも、既存の移動先を置き換えないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a"), "a").unwrap();
+        fs::write(temp_dir.path().join("b"), "b").unwrap();
+        fs::create_dir(temp_dir.path().join("dir")).unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+
+        let err = dir
+            .rename_without_flags(OsStr::new("a"), &dir, OsStr::new("b"))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(temp_dir.path().join("a")).unwrap(), "a");
+
+        dir.rename_without_flags(OsStr::new("a"), &dir, OsStr::new("c"))
+            .unwrap();
+        assert!(!temp_dir.path().join("a").exists());
+        assert_eq!(fs::read_to_string(temp_dir.path().join("c")).unwrap(), "a");
+        dir.rename_without_flags(OsStr::new("dir"), &dir, OsStr::new("moved"))
+            .unwrap();
+        assert!(temp_dir.path().join("moved").is_dir());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn rename_exchange_swaps_entries() {
+        // Exchange の rename で2つのエントリが入れ替わることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a"), "a").unwrap();
+        fs::create_dir(temp_dir.path().join("b")).unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+
+        dir.rename(OsStr::new("a"), &dir, OsStr::new("b"), RenameMode::Exchange)
+            .unwrap();
+
+        assert!(temp_dir.path().join("a").is_dir());
+        assert_eq!(fs::read_to_string(temp_dir.path().join("b")).unwrap(), "a");
+    }
+}
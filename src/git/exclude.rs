@@ -0,0 +1,156 @@
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// リポジトリ内の `.gitignore`・`.git/info/exclude`・グローバル除外設定を評価する。
+///
+/// `.gitignore` はディレクトリごとに遅延読み込みし、深い階層の設定を優先する。
+/// 並列コピーから共有できるよう、読み込み済みのルールはロックで保護する。
+pub struct ExcludeRules {
+    work_tree: PathBuf,
+    info_exclude: Gitignore,
+    global: Gitignore,
+    per_directory: Mutex<HashMap<PathBuf, Gitignore>>,
+}
+
+impl ExcludeRules {
+    /// ワークツリーと git ディレクトリから除外ルールを構築する。
+    ///
+    /// git ディレクトリが無い場合は各ディレクトリの `.gitignore` のみを評価する。
+    pub fn new(work_tree: &Path, git_dir: Option<&Path>) -> Self {
+        let (info_exclude, global) = match git_dir {
+            Some(git_dir) => {
+                let mut builder = GitignoreBuilder::new(work_tree);
+                builder.add(git_dir.join("info").join("exclude"));
+                let info_exclude = builder.build().unwrap_or_else(|_| Gitignore::empty());
+                (info_exclude, Gitignore::global().0)
+            }
+            None => (Gitignore::empty(), Gitignore::empty()),
+        };
+
+        Self {
+            work_tree: work_tree.to_path_buf(),
+            info_exclude,
+            global,
+            per_directory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// ワークツリー相対パスが除外対象かを判定する。
+    ///
+    /// 親ディレクトリが除外されている場合は配下も除外対象とみなす。
+    pub fn is_excluded(&self, relative: &Path, is_dir: bool) -> bool {
+        // 深い階層の .gitignore から順に評価し、最初に決着したルールを採用する。
+        for dir in relative.ancestors().skip(1) {
+            let Ok(relative_to_dir) = relative.strip_prefix(dir) else {
+                continue;
+            };
+            match self.with_directory_rules(dir, |rules| {
+                decision(rules.matched_path_or_any_parents(relative_to_dir, is_dir))
+            }) {
+                Some(excluded) => return excluded,
+                None => continue,
+            }
+        }
+
+        if let Some(excluded) = decision(
+            self.info_exclude
+                .matched_path_or_any_parents(relative, is_dir),
+        ) {
+            return excluded;
+        }
+
+        decision(self.global.matched_path_or_any_parents(relative, is_dir)).unwrap_or(false)
+    }
+
+    /// 指定ディレクトリの `.gitignore` を読み込み（キャッシュ済みなら再利用し）評価する。
+    fn with_directory_rules<T>(&self, dir: &Path, f: impl FnOnce(&Gitignore) -> T) -> T {
+        let mut cache = self
+            .per_directory
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let rules = cache.entry(dir.to_path_buf()).or_insert_with(|| {
+            let absolute = self.work_tree.join(dir);
+            let mut builder = GitignoreBuilder::new(&absolute);
+            builder.add(absolute.join(".gitignore"));
+            builder.build().unwrap_or_else(|_| Gitignore::empty())
+        });
+        f(rules)
+    }
+}
+
+/// マッチ結果を「除外する / しない / 未決定」に変換する。
+fn decision<T>(matched: Match<T>) -> Option<bool> {
+    match matched {
+        Match::None => None,
+        Match::Ignore(_) => Some(true),
+        Match::Whitelist(_) => Some(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn is_excluded_applies_nested_gitignore_with_priority() {
+        // 深い階層の .gitignore が浅い階層の設定より優先されることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".git").join("info")).unwrap();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join("sub").join(".gitignore"), "!keep.log\n").unwrap();
+
+        let rules = ExcludeRules::new(root, Some(&root.join(".git")));
+
+        assert!(rules.is_excluded(Path::new("debug.log"), false));
+        assert!(rules.is_excluded(Path::new("sub/other.log"), false));
+        assert!(!rules.is_excluded(Path::new("sub/keep.log"), false));
+        assert!(!rules.is_excluded(Path::new("sub/main.rs"), false));
+    }
+
+    #[test]
+    fn is_excluded_covers_children_of_excluded_directory() {
+        // 除外ディレクトリ配下のファイルも除外対象と判定されることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".git").join("info")).unwrap();
+        fs::write(root.join(".gitignore"), "/target/\n").unwrap();
+
+        let rules = ExcludeRules::new(root, Some(&root.join(".git")));
+
+        assert!(rules.is_excluded(Path::new("target/debug/app"), false));
+        assert!(!rules.is_excluded(Path::new("src/target.rs"), false));
+    }
+
+    #[test]
+    fn is_excluded_reads_info_exclude() {
+        // .git/info/exclude の設定も除外ルールとして扱うことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".git").join("info")).unwrap();
+        fs::write(root.join(".git").join("info").join("exclude"), "scratch/\n").unwrap();
+
+        let rules = ExcludeRules::new(root, Some(&root.join(".git")));
+
+        assert!(rules.is_excluded(Path::new("scratch/notes.txt"), false));
+    }
+
+    #[test]
+    fn is_excluded_without_git_dir_uses_only_gitignore_files() {
+        // git ディレクトリが無い場合でも .gitignore の設定は評価されることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(".gitignore"), "node_modules/\n").unwrap();
+
+        let rules = ExcludeRules::new(root, None);
+
+        assert!(rules.is_excluded(Path::new("node_modules/pkg/index.js"), false));
+        assert!(!rules.is_excluded(Path::new("src/index.js"), false));
+    }
+}
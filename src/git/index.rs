@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// シンボリックリンクを表す index エントリのモード上位ビット。
+pub const MODE_SYMLINK: u32 = 0o120000;
+/// サブモジュール（gitlink）を表す index エントリのモード上位ビット。
+pub const MODE_GITLINK: u32 = 0o160000;
+/// index エントリのオブジェクト種別を取り出すマスク。
+pub const MODE_TYPE_MASK: u32 = 0o170000;
+
+const ENTRY_FIXED_LEN: usize = 62;
+const OID_LEN: usize = 20;
+const FLAG_EXTENDED: u16 = 0x4000;
+const EXTENDED_INTENT_TO_ADD: u16 = 0x2000;
+
+/// index に記録された単一パスの stat 情報とオブジェクト ID を保持する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub mtime_secs: u32,
+    pub mtime_nanos: u32,
+    pub mode: u32,
+    pub size: u32,
+    pub oid: [u8; OID_LEN],
+    /// マージ衝突中（stage 1〜3 が存在する）かどうか。
+    pub conflicted: bool,
+    /// `git add -N` で追加予定として登録されただけのエントリかどうか。
+    pub intent_to_add: bool,
+}
+
+/// `.git/index` を読み込んだ結果を保持する。
+#[derive(Debug, Default)]
+pub struct Index {
+    /// リポジトリルートからの `/` 区切り相対パスをキーとするエントリ一覧。
+    pub entries: BTreeMap<Vec<u8>, IndexEntry>,
+    /// index ファイル自身の更新時刻。racy clean 判定に使う。
+    pub modified: Option<(u64, u32)>,
+}
+
+impl Index {
+    /// index ファイルを読み込む。存在しない場合は空の index として扱う。
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+
+        let modified = fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| (duration.as_secs(), duration.subsec_nanos()));
+
+        let entries = parse(&data)?;
+        Ok(Self { entries, modified })
+    }
+
+    /// 指定キー配下（キー自身を含む）のエントリを列挙する。
+    pub fn entries_under<'a>(
+        &'a self,
+        prefix: &'a [u8],
+    ) -> impl Iterator<Item = (&'a Vec<u8>, &'a IndexEntry)> + 'a {
+        self.entries
+            .range(prefix.to_vec()..)
+            .take_while(move |(key, _)| key.starts_with(prefix))
+            .filter(move |(key, _)| {
+                prefix.is_empty() || key.len() == prefix.len() || key[prefix.len()] == b'/'
+            })
+    }
+
+    /// 指定キー配下に追跡対象のエントリが存在するかを返す。
+    pub fn has_entries_under(&self, prefix: &[u8]) -> bool {
+        self.entries_under(prefix).next().is_some()
+    }
+}
+
+/// index バイナリ（version 2〜4）を解析する。
+fn parse(data: &[u8]) -> io::Result<BTreeMap<Vec<u8>, IndexEntry>> {
+    let mut reader = Reader { data, offset: 0 };
+
+    if reader.take(4)? != b"DIRC" {
+        return Err(invalid("missing index signature"));
+    }
+    let version = reader.u32()?;
+    if !(2..=4).contains(&version) {
+        return Err(invalid(&format!("unsupported index version {version}")));
+    }
+    let count = reader.u32()?;
+
+    let mut entries: BTreeMap<Vec<u8>, IndexEntry> = BTreeMap::new();
+    let mut previous_path: Vec<u8> = Vec::new();
+
+    for _ in 0..count {
+        let entry_start = reader.offset;
+        let _ctime_secs = reader.u32()?;
+        let _ctime_nanos = reader.u32()?;
+        let mtime_secs = reader.u32()?;
+        let mtime_nanos = reader.u32()?;
+        let _dev = reader.u32()?;
+        let _ino = reader.u32()?;
+        let mode = reader.u32()?;
+        let _uid = reader.u32()?;
+        let _gid = reader.u32()?;
+        let size = reader.u32()?;
+        let mut oid = [0u8; OID_LEN];
+        oid.copy_from_slice(reader.take(OID_LEN)?);
+        let flags = reader.u16()?;
+
+        let mut header_len = ENTRY_FIXED_LEN;
+        let mut intent_to_add = false;
+        if flags & FLAG_EXTENDED != 0 {
+            if version < 3 {
+                return Err(invalid("extended flags in version 2 index"));
+            }
+            let extended = reader.u16()?;
+            intent_to_add = extended & EXTENDED_INTENT_TO_ADD != 0;
+            header_len += 2;
+        }
+
+        let path = if version == 4 {
+            let strip = reader.varint()?;
+            if strip > previous_path.len() {
+                return Err(invalid("corrupt path prefix in index"));
+            }
+            let mut path = previous_path[..previous_path.len() - strip].to_vec();
+            path.extend_from_slice(reader.until_nul()?);
+            path
+        } else {
+            let path = reader.until_nul()?.to_vec();
+            // NUL を含めて 8 バイト境界まで埋められている。
+            let entry_len = (header_len + path.len() + 8) & !7;
+            reader.offset = entry_start + entry_len;
+            if reader.offset > data.len() {
+                return Err(invalid("truncated index entry"));
+            }
+            path
+        };
+
+        let stage = (flags >> 12) & 0x3;
+        let entry = IndexEntry {
+            mtime_secs,
+            mtime_nanos,
+            mode,
+            size,
+            oid,
+            conflicted: stage != 0,
+            intent_to_add,
+        };
+        entries
+            .entry(path.clone())
+            .and_modify(|existing| existing.conflicted |= entry.conflicted)
+            .or_insert(entry);
+        previous_path = path;
+    }
+
+    Ok(entries)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("git index: {message}"))
+}
+
+/// index バイナリを先頭から順に読み進める。
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| invalid("unexpected end of index"))?;
+        let slice = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn until_nul(&mut self) -> io::Result<&'a [u8]> {
+        let rest = &self.data[self.offset..];
+        let len = rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| invalid("unterminated path in index"))?;
+        self.offset += len + 1;
+        Ok(&rest[..len])
+    }
+
+    /// git の offset 形式 varint を読む（version 4 のパス圧縮で使用）。
+    fn varint(&mut self) -> io::Result<usize> {
+        let mut byte = self.take(1)?[0];
+        let mut value = usize::from(byte & 0x7f);
+        while byte & 0x80 != 0 {
+            byte = self.take(1)?[0];
+            value = value
+                .checked_add(1)
+                .and_then(|v| v.checked_mul(128))
+                .and_then(|v| v.checked_add(usize::from(byte & 0x7f)))
+                .ok_or_else(|| invalid("varint overflow"))?;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用に version 2 形式のエントリを組み立てる。
+    fn encode_v2_entry(path: &[u8], stage: u16) -> Vec<u8> {
+        let mut entry = Vec::new();
+        for value in [0u32, 0, 10, 20, 0, 0, 0o100644, 0, 0, 5] {
+            entry.extend_from_slice(&value.to_be_bytes());
+        }
+        entry.extend_from_slice(&[0xab; OID_LEN]);
+        let flags = (stage << 12) | path.len() as u16;
+        entry.extend_from_slice(&flags.to_be_bytes());
+        entry.extend_from_slice(path);
+        let padded = (ENTRY_FIXED_LEN + path.len() + 8) & !7;
+        entry.resize(padded, 0);
+        entry
+    }
+
+    fn encode_v2_index(entries: &[(&[u8], u16)]) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        for (path, stage) in entries {
+            data.extend_from_slice(&encode_v2_entry(path, *stage));
+        }
+        data
+    }
+
+    #[test]
+    fn parse_reads_version_2_entries() {
+        // version 2 の index からパス・stat 情報・オブジェクト ID を読み取れることを確認する。
+        let data = encode_v2_index(&[(b"README.md", 0), (b"src/lib.rs", 0)]);
+
+        let entries = parse(&data).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        let entry = &entries[b"src/lib.rs".as_slice()];
+        assert_eq!(entry.mtime_secs, 10);
+        assert_eq!(entry.mtime_nanos, 20);
+        assert_eq!(entry.size, 5);
+        assert_eq!(entry.mode, 0o100644);
+        assert_eq!(entry.oid, [0xab; OID_LEN]);
+        assert!(!entry.conflicted);
+    }
+
+    #[test]
+    fn parse_marks_conflict_stages() {
+        // stage 1〜3 のエントリを衝突中として扱うことを確認する。
+        let data = encode_v2_index(&[(b"file.txt", 1), (b"file.txt", 2), (b"file.txt", 3)]);
+
+        let entries = parse(&data).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert!(entries[b"file.txt".as_slice()].conflicted);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_signature() {
+        // DIRC シグネチャが無いデータをエラーとして扱うことを確認する。
+        let err = parse(b"XXXX\0\0\0\x02\0\0\0\0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn entries_under_matches_only_whole_path_components() {
+        // `src` 配下の列挙に `src2` のような前方一致だけのパスを含めないことを確認する。
+        let data = encode_v2_index(&[(b"src/a.rs", 0), (b"src2/b.rs", 0), (b"src", 0)]);
+        let index = Index {
+            entries: parse(&data).unwrap(),
+            modified: None,
+        };
+
+        let keys: Vec<_> = index
+            .entries_under(b"src")
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        assert_eq!(keys, vec![b"src".to_vec(), b"src/a.rs".to_vec()]);
+    }
+}
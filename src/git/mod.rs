@@ -0,0 +1,697 @@
+mod exclude;
+mod index;
+
+use crate::config::GitConfig;
+use exclude::ExcludeRules;
+use index::{Index, IndexEntry, MODE_GITLINK, MODE_SYMLINK, MODE_TYPE_MASK};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 指定パスを含む git ワークツリーのルートを探索する。
+///
+/// `.git` はディレクトリ（通常リポジトリ）とファイル（worktree / submodule）の両方を受け付ける。
+pub fn find_work_tree_root(start: &Path) -> Option<PathBuf> {
+    let mut current = Some(start);
+
+    while let Some(dir) = current {
+        if std::fs::symlink_metadata(dir.join(".git")).is_ok() {
+            return Some(dir.to_path_buf());
+        }
+        current = dir.parent();
+    }
+
+    None
+}
+
+/// ディレクトリ走査時に `.gitignore`・`.git/info/exclude`・グローバル除外設定を評価する。
+pub struct IgnoreRules {
+    root: PathBuf,
+    rules: ExcludeRules,
+}
+
+impl IgnoreRules {
+    /// 指定ディレクトリを走査するための除外ルールを構築する。
+    ///
+    /// ディレクトリが git ワークツリー内にあればリポジトリルートを基準とし、
+    /// そうでなければ指定ディレクトリ自身を基準に `.gitignore` のみを評価する。
+    pub fn for_directory(dir: &Path) -> io::Result<Self> {
+        let dir = dir.canonicalize()?;
+        let (root, git_dir) = match find_work_tree_root(&dir) {
+            Some(work_tree) => {
+                let git_dir = resolve_git_dir(&work_tree).ok();
+                (work_tree, git_dir)
+            }
+            None => (dir, None),
+        };
+        let rules = ExcludeRules::new(&root, git_dir.as_deref());
+
+        Ok(Self { root, rules })
+    }
+
+    /// 実体解決済みの絶対パスが除外対象かを判定する。基準ディレクトリ外のパスは除外しない。
+    pub fn is_ignored(&self, absolute: &Path, is_dir: bool) -> bool {
+        match absolute.strip_prefix(&self.root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => {
+                self.rules.is_excluded(relative, is_dir)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// 未コミットの内容を含むと判定されたパスとその理由を表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirtyEntry {
+    pub path: PathBuf,
+    pub reason: DirtyReason,
+}
+
+/// 未コミットと判定した理由を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirtyReason {
+    /// index に登録された内容とワークツリーの内容が異なる。
+    Modified,
+    /// index に登録されておらず、除外設定にも該当しない。
+    Untracked,
+    /// マージ衝突が未解決のまま残っている。
+    Conflicted,
+}
+
+impl fmt::Display for DirtyEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.display();
+        match self.reason {
+            DirtyReason::Modified => write!(f, "'{path}' has uncommitted changes"),
+            DirtyReason::Untracked => write!(f, "'{path}' is untracked"),
+            DirtyReason::Conflicted => write!(f, "'{path}' has unresolved conflicts"),
+        }
+    }
+}
+
+/// 1回の実行中に開いたリポジトリを保持し、`[git] protect_dirty` の判定に使い回す。
+///
+/// index の読み込みと除外ルールの構築はワークツリーごとに最初の1回だけ行う。
+#[derive(Default)]
+pub struct Repositories {
+    protect_dirty: bool,
+    opened: Mutex<HashMap<PathBuf, Arc<Repository>>>,
+}
+
+impl Repositories {
+    /// `[git]` 設定から判定に使うキャッシュを生成する。
+    pub fn new(config: &GitConfig) -> Self {
+        Self {
+            protect_dirty: config.protect_dirty,
+            ..Self::default()
+        }
+    }
+
+    /// `[git] protect_dirty` 有効時、未コミット内容を含むパスをゴミ箱へ移動する操作を拒否する。
+    ///
+    /// `allow_dirty`（`--allow-dirty`）指定時は判定しない。
+    pub fn ensure_clean(
+        &self,
+        command: &str,
+        path: &Path,
+        allow_dirty: bool,
+    ) -> Result<(), String> {
+        if !self.protect_dirty || allow_dirty {
+            return Ok(());
+        }
+
+        match self.find_dirty_entry(path) {
+            Ok(None) => Ok(()),
+            Ok(Some(dirty)) => Err(format!(
+                "{command}: refusing to trash '{}': {} (use --allow-dirty to override)",
+                path.display(),
+                dirty
+            )),
+            Err(e) => Err(format!(
+                "{command}: refusing to trash '{}': failed to inspect git status: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+
+    /// 対象パス配下に未コミットの内容（変更・未追跡・衝突）が含まれるかを調べる。
+    ///
+    /// git ワークツリー外のパスは常に `None` を返す。
+    /// `git` コマンドは呼び出さず、index とワークツリーを直接比較する。
+    pub fn find_dirty_entry(&self, target: &Path) -> io::Result<Option<DirtyEntry>> {
+        let absolute = absolute_without_leaf_resolution(target)?;
+        let meta = match fs::symlink_metadata(&absolute) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let search_start = if meta.is_dir() {
+            absolute.as_path()
+        } else {
+            match absolute.parent() {
+                Some(parent) => parent,
+                None => return Ok(None),
+            }
+        };
+        let Some(work_tree) = find_work_tree_root(search_start) else {
+            return Ok(None);
+        };
+
+        self.open(&work_tree)?
+            .find_dirty_entry(&absolute, &meta, self)
+    }
+
+    /// ワークツリーのリポジトリを開く。既に開いていればそれを返す。
+    fn open(&self, work_tree: &Path) -> io::Result<Arc<Repository>> {
+        let mut opened = self
+            .opened
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(repository) = opened.get(work_tree) {
+            return Ok(Arc::clone(repository));
+        }
+        let repository = Arc::new(Repository::open(work_tree)?);
+        opened.insert(work_tree.to_path_buf(), Arc::clone(&repository));
+        Ok(repository)
+    }
+}
+
+/// 親ディレクトリのみ実体解決し、最終要素はシンボリックリンクでも辿らない絶対パスを返す。
+fn absolute_without_leaf_resolution(path: &Path) -> io::Result<PathBuf> {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => Ok(parent.canonicalize()?.join(name)),
+        _ => absolute.canonicalize(),
+    }
+}
+
+/// 単一リポジトリの index と除外ルールを保持する。
+struct Repository {
+    work_tree: PathBuf,
+    index: Index,
+    excludes: ExcludeRules,
+}
+
+impl Repository {
+    /// ワークツリーのルートからリポジトリ情報を読み込む。
+    fn open(work_tree: &Path) -> io::Result<Self> {
+        let git_dir = resolve_git_dir(work_tree)?;
+        let index = Index::load(&git_dir.join("index"))?;
+        let excludes = ExcludeRules::new(work_tree, Some(&git_dir));
+
+        Ok(Self {
+            work_tree: work_tree.to_path_buf(),
+            index,
+            excludes,
+        })
+    }
+
+    /// 対象パス（ファイルまたはディレクトリ）配下で最初に見つかった未コミット項目を返す。
+    fn find_dirty_entry(
+        &self,
+        absolute: &Path,
+        meta: &fs::Metadata,
+        repositories: &Repositories,
+    ) -> io::Result<Option<DirtyEntry>> {
+        let relative = absolute
+            .strip_prefix(&self.work_tree)
+            .map_err(|_| io::Error::other("path is outside of the git work tree"))?;
+        let key = index_key(relative);
+
+        // 追跡済みエントリの変更・衝突を検出する。
+        for (entry_key, entry) in self.index.entries_under(&key) {
+            let entry_relative = path_from_index_key(entry_key);
+            if let Some(reason) = self.check_tracked(&entry_relative, entry)? {
+                return Ok(Some(DirtyEntry {
+                    path: self.work_tree.join(entry_relative),
+                    reason,
+                }));
+            }
+        }
+
+        // index に無く、除外設定にも該当しないファイルを未追跡として検出する。
+        self.find_untracked(relative, meta, repositories)
+    }
+
+    /// 追跡済みエントリとワークツリー上の実体を比較する。
+    fn check_tracked(
+        &self,
+        relative: &Path,
+        entry: &IndexEntry,
+    ) -> io::Result<Option<DirtyReason>> {
+        if entry.conflicted {
+            return Ok(Some(DirtyReason::Conflicted));
+        }
+        if entry.intent_to_add {
+            return Ok(Some(DirtyReason::Modified));
+        }
+        if entry.mode & MODE_TYPE_MASK == MODE_GITLINK {
+            return Ok(None);
+        }
+
+        let path = self.work_tree.join(relative);
+        let meta = match fs::symlink_metadata(&path) {
+            Ok(meta) => meta,
+            // 削除済みのファイルは内容がリポジトリに残っているため失われない。
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let is_symlink_entry = entry.mode & MODE_TYPE_MASK == MODE_SYMLINK;
+        if meta.file_type().is_symlink() != is_symlink_entry || meta.is_dir() {
+            return Ok(Some(DirtyReason::Modified));
+        }
+        if !is_symlink_entry && executable_bit_changed(&meta, entry.mode) {
+            return Ok(Some(DirtyReason::Modified));
+        }
+        if meta.len() as u32 != entry.size {
+            return Ok(Some(DirtyReason::Modified));
+        }
+        if self.stat_matches(&meta, entry) {
+            return Ok(None);
+        }
+
+        let oid = if is_symlink_entry {
+            hash_blob(&symlink_target_bytes(&path)?)
+        } else {
+            hash_file_blob(&path, meta.len())?
+        };
+        if oid == entry.oid {
+            Ok(None)
+        } else {
+            Ok(Some(DirtyReason::Modified))
+        }
+    }
+
+    /// index 作成後に更新されていない（racy clean でない）場合のみ stat 比較で変更なしとみなす。
+    fn stat_matches(&self, meta: &fs::Metadata, entry: &IndexEntry) -> bool {
+        let Some((mtime_secs, mtime_nanos)) = modified_time(meta) else {
+            return false;
+        };
+        if mtime_secs as u32 != entry.mtime_secs || mtime_nanos != entry.mtime_nanos {
+            return false;
+        }
+
+        match self.index.modified {
+            Some(index_time) => (mtime_secs, mtime_nanos) < index_time,
+            None => false,
+        }
+    }
+
+    /// ワークツリーを走査して未追跡かつ除外対象外のファイルを探す。
+    fn find_untracked(
+        &self,
+        relative: &Path,
+        meta: &fs::Metadata,
+        repositories: &Repositories,
+    ) -> io::Result<Option<DirtyEntry>> {
+        let key = index_key(relative);
+        if self.index.entries.contains_key(&key) {
+            return Ok(None);
+        }
+
+        if !meta.is_dir() {
+            if self.excludes.is_excluded(relative, false) {
+                return Ok(None);
+            }
+            return Ok(Some(DirtyEntry {
+                path: self.work_tree.join(relative),
+                reason: DirtyReason::Untracked,
+            }));
+        }
+
+        let absolute = self.work_tree.join(relative);
+        if !relative.as_os_str().is_empty() && absolute.join(".git").exists() {
+            // 入れ子のリポジトリはそのリポジトリの index で判定する。
+            return repositories
+                .open(&absolute)?
+                .find_dirty_entry(&absolute, meta, repositories);
+        }
+        if !relative.as_os_str().is_empty()
+            && !self.index.has_entries_under(&key)
+            && self.excludes.is_excluded(relative, true)
+        {
+            return Ok(None);
+        }
+
+        for entry in fs::read_dir(&absolute)? {
+            let entry = entry?;
+            if entry.file_name() == ".git" {
+                continue;
+            }
+            let child_meta = fs::symlink_metadata(entry.path())?;
+            let child_relative = relative.join(entry.file_name());
+            if let Some(dirty) = self.find_untracked(&child_relative, &child_meta, repositories)? {
+                return Ok(Some(dirty));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// ワークツリー直下の `.git` から実際の git ディレクトリを解決する。
+///
+/// worktree / submodule の `.git` ファイル（`gitdir: <path>`）にも対応する。
+fn resolve_git_dir(work_tree: &Path) -> io::Result<PathBuf> {
+    let dot_git = work_tree.join(".git");
+    if dot_git.is_dir() {
+        return Ok(dot_git);
+    }
+
+    let content = fs::read_to_string(&dot_git)?;
+    let git_dir = content
+        .lines()
+        .find_map(|line| line.strip_prefix("gitdir:"))
+        .map(str::trim)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid .git file"))?;
+
+    Ok(work_tree.join(git_dir))
+}
+
+/// git オブジェクト形式（`blob <len>\0<content>`）の SHA-1 を計算する。
+fn hash_blob(content: &[u8]) -> [u8; 20] {
+    let mut hasher = sha1_smol::Sha1::new();
+    hasher.update(format!("blob {}\0", content.len()).as_bytes());
+    hasher.update(content);
+    hasher.digest().bytes()
+}
+
+/// ファイル内容をストリームで読みながら blob の SHA-1 を計算する。
+fn hash_file_blob(path: &Path, len: u64) -> io::Result<[u8; 20]> {
+    let mut hasher = sha1_smol::Sha1::new();
+    hasher.update(format!("blob {len}\0").as_bytes());
+
+    let mut file = fs::File::open(path)?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.digest().bytes())
+}
+
+#[cfg(unix)]
+fn symlink_target_bytes(path: &Path) -> io::Result<Vec<u8>> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(fs::read_link(path)?.as_os_str().as_bytes().to_vec())
+}
+
+#[cfg(not(unix))]
+fn symlink_target_bytes(path: &Path) -> io::Result<Vec<u8>> {
+    Ok(fs::read_link(path)?
+        .to_string_lossy()
+        .replace('\\', "/")
+        .into_bytes())
+}
+
+#[cfg(unix)]
+fn executable_bit_changed(meta: &fs::Metadata, index_mode: u32) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    let on_disk = meta.permissions().mode() & 0o100 != 0;
+    let in_index = index_mode & 0o100 != 0;
+    on_disk != in_index
+}
+
+#[cfg(not(unix))]
+fn executable_bit_changed(_meta: &fs::Metadata, _index_mode: u32) -> bool {
+    false
+}
+
+fn modified_time(meta: &fs::Metadata) -> Option<(u64, u32)> {
+    let duration = meta
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    Some((duration.as_secs(), duration.subsec_nanos()))
+}
+
+/// ワークツリー相対パスを index のキー形式（`/` 区切りのバイト列）へ変換する。
+#[cfg(unix)]
+fn index_key(relative: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    relative.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn index_key(relative: &Path) -> Vec<u8> {
+    relative.to_string_lossy().replace('\\', "/").into_bytes()
+}
+
+/// index のキーをワークツリー相対パスへ戻す。
+#[cfg(unix)]
+fn path_from_index_key(key: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(key))
+}
+
+#[cfg(not(unix))]
+fn path_from_index_key(key: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(key).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    #[test]
+    fn find_work_tree_root_returns_enclosing_repository() {
+        // サブディレクトリから親方向へ辿り、`.git` を持つディレクトリを返すことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        let nested = repo.join("src").join("nested");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(find_work_tree_root(&nested), Some(repo));
+    }
+
+    #[test]
+    fn find_work_tree_root_accepts_git_file() {
+        // worktree 形式の `.git` ファイルもリポジトリルートとして扱うことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let worktree = temp_dir.path().join("worktree");
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(worktree.join(".git"), "gitdir: /elsewhere\n").unwrap();
+
+        assert_eq!(find_work_tree_root(&worktree), Some(worktree));
+    }
+
+    #[test]
+    fn hash_blob_matches_git_object_id() {
+        // `git hash-object` と同じ blob ID を計算できることを確認する。
+        let oid = hash_blob(b"hello\n");
+        let hex: String = oid.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(hex, "ce013625030ba8dba906f756967f9e9ca394464a");
+    }
+
+    /// git コマンドが使えるかを返す。使えない場合はテストをスキップする旨を表示する。
+    fn git_available() -> bool {
+        let available = Command::new("git").arg("--version").output().is_ok();
+        if !available {
+            eprintln!("skipping: git command is not available");
+        }
+        available
+    }
+
+    /// テスト用リポジトリで git コマンドを実行し、成功したかを返す。
+    fn git(repo: &Path, args: &[&str]) -> bool {
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "user.name=safecmd",
+                "-c",
+                "user.email=safecmd@example.com",
+            ])
+            .args(["-c", "commit.gpgsign=false", "-c", "core.autocrlf=false"])
+            .args(args)
+            .current_dir(repo)
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .status();
+        matches!(status, Ok(status) if status.success())
+    }
+
+    /// 1ファイルをコミット済みのリポジトリを作成する。git が無い環境では `None` を返す。
+    fn committed_repository() -> Option<TempDir> {
+        if !git_available() {
+            return None;
+        }
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path();
+        assert!(git(repo, &["init", "-q"]));
+        fs::create_dir_all(repo.join("src")).unwrap();
+        fs::write(repo.join("src").join("lib.rs"), "fn main() {}\n").unwrap();
+        fs::write(repo.join(".gitignore"), "/target/\n").unwrap();
+        assert!(git(repo, &["add", "."]));
+        assert!(git(repo, &["commit", "-q", "-m", "init"]));
+        Some(temp_dir)
+    }
+
+    #[test]
+    fn find_dirty_entry_returns_none_for_clean_tracked_file() {
+        // コミット済みで未変更のファイルは未コミット扱いしないことを確認する。
+        let Some(temp_dir) = committed_repository() else {
+            return;
+        };
+
+        let result = Repositories::default()
+            .find_dirty_entry(&temp_dir.path().join("src").join("lib.rs"))
+            .unwrap();
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn repositories_open_each_work_tree_once() {
+        // 同じワークツリー内の判定では、最初に開いたリポジトリを使い回すことを確認する。
+        let Some(temp_dir) = committed_repository() else {
+            return;
+        };
+        let repositories = Repositories::default();
+
+        for path in [
+            temp_dir.path().join("src"),
+            temp_dir.path().join(".gitignore"),
+        ] {
+            assert_eq!(repositories.find_dirty_entry(&path).unwrap(), None);
+        }
+
+        assert_eq!(repositories.opened.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn find_dirty_entry_detects_modified_file_in_directory() {
+        // ディレクトリ配下の追跡済みファイルの変更を検出できることを確認する。
+        let Some(temp_dir) = committed_repository() else {
+            return;
+        };
+        let file = temp_dir.path().join("src").join("lib.rs");
+        fs::write(&file, "fn main() { changed(); }\n").unwrap();
+
+        let result = Repositories::default()
+            .find_dirty_entry(&temp_dir.path().join("src"))
+            .unwrap();
+
+        let dirty = result.expect("modified file should be reported");
+        assert_eq!(dirty.reason, DirtyReason::Modified);
+        assert_eq!(dirty.path.file_name().unwrap(), "lib.rs");
+    }
+
+    #[test]
+    fn find_dirty_entry_detects_same_size_modification() {
+        // サイズが同じでも内容が変わったファイルを変更として検出できることを確認する。
+        let Some(temp_dir) = committed_repository() else {
+            return;
+        };
+        let file = temp_dir.path().join("src").join("lib.rs");
+        fs::write(&file, "fn mian() {}\n").unwrap();
+
+        let result = Repositories::default().find_dirty_entry(&file).unwrap();
+
+        assert_eq!(
+            result.map(|dirty| dirty.reason),
+            Some(DirtyReason::Modified)
+        );
+    }
+
+    #[test]
+    fn find_dirty_entry_reads_version_4_index() {
+        // パス圧縮された version 4 の index でも判定できることを確認する。
+        let Some(temp_dir) = committed_repository() else {
+            return;
+        };
+        let repo = temp_dir.path();
+        fs::write(repo.join("src").join("main.rs"), "fn main() {}\n").unwrap();
+        assert!(git(repo, &["add", "."]));
+        assert!(git(repo, &["update-index", "--index-version", "4"]));
+
+        assert_eq!(
+            Repositories::default()
+                .find_dirty_entry(&repo.join("src"))
+                .unwrap(),
+            None
+        );
+
+        fs::write(repo.join("src").join("main.rs"), "fn main() { 1; }\n").unwrap();
+        let result = Repositories::default()
+            .find_dirty_entry(&repo.join("src"))
+            .unwrap();
+        assert_eq!(
+            result.map(|dirty| dirty.reason),
+            Some(DirtyReason::Modified)
+        );
+    }
+
+    #[test]
+    fn find_dirty_entry_detects_untracked_file() {
+        // index に無く除外設定にも該当しないファイルを未追跡として検出することを確認する。
+        let Some(temp_dir) = committed_repository() else {
+            return;
+        };
+        let file = temp_dir.path().join("src").join("new.rs");
+        fs::write(&file, "// new\n").unwrap();
+
+        let result = Repositories::default()
+            .find_dirty_entry(&temp_dir.path().join("src"))
+            .unwrap();
+
+        assert_eq!(
+            result,
+            Some(DirtyEntry {
+                path: file.canonicalize().unwrap(),
+                reason: DirtyReason::Untracked,
+            })
+        );
+    }
+
+    #[test]
+    fn find_dirty_entry_ignores_excluded_files() {
+        // .gitignore で除外されたファイルは未追跡として扱わないことを確認する。
+        let Some(temp_dir) = committed_repository() else {
+            return;
+        };
+        let build_dir = temp_dir.path().join("target").join("debug");
+        fs::create_dir_all(&build_dir).unwrap();
+        fs::write(build_dir.join("app"), "binary").unwrap();
+
+        let result = Repositories::default()
+            .find_dirty_entry(&temp_dir.path().join("target"))
+            .unwrap();
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn find_dirty_entry_returns_none_outside_work_tree() {
+        // git ワークツリー外のパスは判定対象外として扱うことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("plain.txt");
+        fs::write(&file, "plain").unwrap();
+        if find_work_tree_root(temp_dir.path()).is_some() {
+            return;
+        }
+
+        assert_eq!(
+            Repositories::default().find_dirty_entry(&file).unwrap(),
+            None
+        );
+    }
+}
@@ -1,8 +1,22 @@
+pub mod backup;
 pub mod config;
+pub mod dirfd;
+pub mod git;
+pub mod limits;
+pub mod multicall;
 pub mod notifications;
+pub mod operands;
+pub mod progress;
+pub mod staging;
+pub mod update;
+pub mod verify;
+pub mod walk;
 
 pub mod commands {
     pub mod cp;
+    pub mod ln;
     pub mod mv;
     pub mod rm;
+    pub mod rmdir;
+    pub mod write;
 }
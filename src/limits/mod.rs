@@ -0,0 +1,213 @@
+use crate::config::LimitsConfig;
+use crate::walk::{Entry, Visit, Visitor, Walker};
+use std::fmt;
+use std::io;
+use std::ops::AddAssign;
+use std::path::Path;
+
+/// 事前走査で集計したツリーのファイル数と合計サイズを保持する。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeStats {
+    /// ディレクトリ以外のエントリ数（通常ファイル・シンボリックリンク等）。
+    pub files: u64,
+    /// ディレクトリ以外のエントリの合計バイト数。
+    pub bytes: u64,
+}
+
+impl AddAssign for TreeStats {
+    fn add_assign(&mut self, other: Self) {
+        self.files = self.files.saturating_add(other.files);
+        self.bytes = self.bytes.saturating_add(other.bytes);
+    }
+}
+
+impl fmt::Display for TreeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = if self.files == 1 { "file" } else { "files" };
+        write!(f, "{} {unit}, {} bytes", self.files, self.bytes)
+    }
+}
+
+/// 対象パス配下をシンボリックリンクを辿らずに走査し、ファイル数と合計サイズを集計する。
+pub fn scan(path: &Path) -> io::Result<TreeStats> {
+    let mut counter = Counter::default();
+    Walker::new(path).run(&mut counter)?;
+    Ok(counter.stats)
+}
+
+/// ディレクトリ以外のエントリを数えるビジター。
+#[derive(Default)]
+struct Counter {
+    stats: TreeStats,
+}
+
+impl Visitor for Counter {
+    type Error = io::Error;
+
+    fn enter_dir(&mut self, _entry: &Entry<'_>) -> io::Result<Visit> {
+        Ok(Visit::Continue)
+    }
+
+    fn visit(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+        self.stats.files += 1;
+        self.stats.bytes += entry.metadata.len();
+        Ok(Visit::Continue)
+    }
+
+    fn walk_error(&mut self, _path: &Path, err: io::Error) -> io::Error {
+        err
+    }
+}
+
+/// 集計結果が `[limits]` の上限を超えていないかを検証する。
+///
+/// 上限超過時でも `confirmed` が集計したファイル数と完全一致すれば許可する。
+/// エラー時はコマンド名を含まない理由文字列を返す。
+pub fn enforce(
+    limits: &LimitsConfig,
+    stats: &TreeStats,
+    confirmed: Option<u64>,
+) -> Result<(), String> {
+    let exceeded = match (limits.max_files, limits.max_bytes) {
+        (Some(max_files), _) if stats.files > max_files => {
+            format!("{stats} exceeds [limits] max_files = {max_files}")
+        }
+        (_, Some(max_bytes)) if stats.bytes > max_bytes => {
+            format!("{stats} exceeds [limits] max_bytes = {max_bytes}")
+        }
+        _ => return Ok(()),
+    };
+
+    if confirmed == Some(stats.files) {
+        return Ok(());
+    }
+
+    Err(format!(
+        "{exceeded}; re-run with --yes-i-mean-it={} to confirm",
+        stats.files
+    ))
+}
+
+/// `[limits]` 設定時、上書きで退避する既存ターゲットを事前走査して上限超過なら拒否する。
+///
+/// エラーメッセージには `command` を接頭辞として付ける。
+pub fn ensure_within(
+    command: &str,
+    target: &Path,
+    limits: &LimitsConfig,
+    confirmed: Option<u64>,
+) -> Result<(), String> {
+    if !limits.is_configured() {
+        return Ok(());
+    }
+
+    let stats = scan(target)
+        .map_err(|e| format!("{command}: cannot scan '{}': {}", target.display(), e))?;
+    ensure_stats_within(command, target, limits, &stats, confirmed)
+}
+
+/// 集計済みの退避対象が `[limits]` の上限内かを検証する。
+///
+/// 複数の既存エントリを上書きする場合は合計を渡し、`target` はメッセージに使う代表のパスとする。
+pub fn ensure_stats_within(
+    command: &str,
+    target: &Path,
+    limits: &LimitsConfig,
+    stats: &TreeStats,
+    confirmed: Option<u64>,
+) -> Result<(), String> {
+    enforce(limits, stats, confirmed).map_err(|reason| {
+        format!(
+            "{command}: refusing to overwrite '{}': {}",
+            target.display(),
+            reason
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn limits(max_files: Option<u64>, max_bytes: Option<u64>) -> LimitsConfig {
+        LimitsConfig {
+            max_files,
+            max_bytes,
+        }
+    }
+
+    #[test]
+    fn scan_counts_files_and_bytes_recursively() {
+        // 入れ子ディレクトリ配下のファイル数と合計サイズを集計できることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        fs::create_dir_all(root.join("nested").join("deeper")).unwrap();
+        fs::write(root.join("a.txt"), "12345").unwrap();
+        fs::write(root.join("nested").join("b.txt"), "123").unwrap();
+        fs::write(root.join("nested").join("deeper").join("c.txt"), "").unwrap();
+
+        let stats = scan(&root).unwrap();
+
+        assert_eq!(stats, TreeStats { files: 3, bytes: 8 });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_does_not_follow_symlinked_directories() {
+        // シンボリックリンク先のディレクトリは辿らずリンク1件として数えることを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("big.txt"), "x".repeat(100)).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        let stats = scan(&root).unwrap();
+
+        assert_eq!(stats.files, 1);
+        assert!(stats.bytes < 100);
+    }
+
+    #[test]
+    fn enforce_allows_stats_within_limits() {
+        // 上限以内、または上限未設定の場合は許可することを確認する。
+        let stats = TreeStats {
+            files: 10,
+            bytes: 100,
+        };
+
+        assert!(enforce(&limits(Some(10), Some(100)), &stats, None).is_ok());
+        assert!(enforce(&limits(None, None), &stats, None).is_ok());
+    }
+
+    #[test]
+    fn enforce_requires_exact_confirmation_count() {
+        // 上限超過時は集計件数と一致する確認値のみ受け付けることを確認する。
+        let stats = TreeStats {
+            files: 42,
+            bytes: 1,
+        };
+        let limits = limits(Some(10), None);
+
+        let err = enforce(&limits, &stats, None).unwrap_err();
+        assert!(err.contains("max_files = 10"));
+        assert!(err.contains("--yes-i-mean-it=42"));
+        assert!(enforce(&limits, &stats, Some(41)).is_err());
+        assert!(enforce(&limits, &stats, Some(42)).is_ok());
+    }
+
+    #[test]
+    fn enforce_rejects_when_bytes_exceed_limit() {
+        // 合計サイズが max_bytes を超えた場合も拒否することを確認する。
+        let stats = TreeStats {
+            files: 1,
+            bytes: 2048,
+        };
+
+        let err = enforce(&limits(None, Some(1024)), &stats, None).unwrap_err();
+        assert!(err.contains("max_bytes = 1024"));
+    }
+}
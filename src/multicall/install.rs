@@ -0,0 +1,97 @@
+use crate::commands::cp::handlers as cp_handlers;
+use crate::multicall::Applet;
+use clap::Args as ClapArgs;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(ClapArgs, Debug)]
+pub struct Args {
+    /// Directory to create the command links in (created if missing)
+    #[arg(long = "dir", value_name = "DIR")]
+    pub dir: PathBuf,
+
+    /// Move existing entries with the same names to the trash and replace them
+    #[arg(short = 'f', long = "force")]
+    pub force: bool,
+}
+
+/// `args.dir` に各コマンド名で実行中の `safecmd` を指すシンボリックリンクを作成する。
+///
+/// 既に `safecmd` を指すリンクはそのまま残す。別のエントリがある場合は `--force` 指定時のみ
+/// ゴミ箱へ移動してから置き換え、それ以外はエラーにして残りのコマンドの処理を続ける。
+pub fn run(args: Args) -> i32 {
+    let executable = match std::env::current_exe().and_then(|path| path.canonicalize()) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("install: cannot locate the safecmd executable: {e}");
+            return 1;
+        }
+    };
+    if let Err(e) = fs::create_dir_all(&args.dir) {
+        eprintln!(
+            "install: cannot create directory '{}': {}",
+            args.dir.display(),
+            e
+        );
+        return 1;
+    }
+
+    let mut exit_code = 0;
+    for applet in Applet::ALL {
+        let link = args.dir.join(applet.name());
+        match install_link(&executable, &link, args.force) {
+            Ok(true) => println!("'{}' -> '{}'", link.display(), executable.display()),
+            Ok(false) => {}
+            Err(msg) => {
+                eprintln!("{msg}");
+                exit_code = 1;
+            }
+        }
+    }
+    exit_code
+}
+
+/// `link` に `executable` を指すシンボリックリンクを作成する。既に同じリンクがあれば `false` を返す。
+fn install_link(executable: &Path, link: &Path, force: bool) -> Result<bool, String> {
+    let create_error = |e: io::Error| format!("install: cannot create '{}': {}", link.display(), e);
+    match fs::symlink_metadata(link) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(create_error(e)),
+        Ok(meta) => {
+            if meta.file_type().is_symlink()
+                && fs::read_link(link).is_ok_and(|target| target == executable)
+            {
+                return Ok(false);
+            }
+            if !force {
+                return Err(format!(
+                    "install: cannot create '{}': File exists (use --force to move it to the trash)",
+                    link.display()
+                ));
+            }
+            if meta.is_dir() {
+                return Err(format!(
+                    "install: cannot overwrite directory '{}'",
+                    link.display()
+                ));
+            }
+            cp_handlers::move_existing_file_to_trash_as("install", link)?;
+        }
+    }
+    create_symlink(executable, link).map_err(create_error)?;
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symbolic links are not supported on this platform",
+    ))
+}
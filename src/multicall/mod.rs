@@ -0,0 +1,99 @@
+pub mod install;
+
+use crate::commands::{cp, ln, mv, rm, rmdir};
+use crate::config::Config;
+use clap::Parser;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+/// multi-call 実行ファイル `safecmd` が `argv[0]` で呼び分けるコマンドを表す。
+///
+/// `safecmd write` はシステムの `write` コマンドと名前が衝突するため含めない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applet {
+    Rm,
+    Rmdir,
+    Cp,
+    Mv,
+    Ln,
+}
+
+impl Applet {
+    /// `safecmd install` でリンクを作成するコマンドの一覧。
+    pub const ALL: [Applet; 5] = [Self::Rm, Self::Rmdir, Self::Cp, Self::Mv, Self::Ln];
+
+    /// コマンド名を返す。
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rm => "rm",
+            Self::Rmdir => "rmdir",
+            Self::Cp => "cp",
+            Self::Mv => "mv",
+            Self::Ln => "ln",
+        }
+    }
+
+    /// `argv[0]` のファイル名からコマンドを判定する。該当しない場合は `None` を返す。
+    ///
+    /// シンボリックリンク経由の起動を想定し、ディレクトリ部分と実行ファイルの拡張子は無視する。
+    pub fn from_argv0(arg0: &OsStr) -> Option<Self> {
+        let file_name = Path::new(arg0).file_name()?.to_str()?;
+        let name = file_name
+            .strip_suffix(std::env::consts::EXE_SUFFIX)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(file_name);
+        Self::ALL.into_iter().find(|applet| applet.name() == name)
+    }
+
+    /// `argv` を解析して設定を読み込み、コマンドを実行して終了コードを返す。
+    ///
+    /// `argv` の先頭はプログラム名として扱う。引数の解析に失敗した場合は clap の規約どおり終了する。
+    pub fn run<I, T>(self, argv: I) -> i32
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        match self {
+            Self::Rm => run_with_config("rm", rm::args::Args::parse_from(argv), rm::run),
+            Self::Rmdir => {
+                run_with_config("rmdir", rmdir::args::Args::parse_from(argv), rmdir::run)
+            }
+            Self::Cp => run_with_config("cp", cp::args::Args::parse_from(argv), cp::run),
+            Self::Mv => run_with_config("mv", mv::args::Args::parse_from(argv), mv::run),
+            Self::Ln => run_with_config("ln", ln::args::Args::parse_from(argv), ln::run),
+        }
+    }
+}
+
+/// 設定ファイルを読み込んでコマンドを実行する。
+///
+/// 読み込みに失敗した場合はコマンド名を付けて報告し、終了コード `1` を返す。
+pub fn run_with_config<A>(command: &str, args: A, run: fn(A, Config) -> i32) -> i32 {
+    match Config::load() {
+        Ok(config) => run(args, config),
+        Err(e) => {
+            eprintln!("{command}: {e}");
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_argv0_uses_file_name_of_invoked_path() {
+        // 起動パスのファイル名だけでコマンドを判定し、該当しない名前は None になることを確認する。
+        assert_eq!(
+            Applet::from_argv0(OsStr::new("/home/user/bin/rmdir")),
+            Some(Applet::Rmdir)
+        );
+        assert_eq!(Applet::from_argv0(OsStr::new("cp")), Some(Applet::Cp));
+        assert_eq!(
+            Applet::from_argv0(OsStr::new("/usr/local/bin/safecmd")),
+            None
+        );
+        assert_eq!(Applet::from_argv0(OsStr::new("write")), None);
+    }
+}
@@ -7,6 +7,9 @@ use std::{cell::Cell, thread_local};
 pub enum CommandKind {
     Rm,
     Cp,
+    Ln,
+    Rmdir,
+    Write,
 }
 
 impl CommandKind {
@@ -15,6 +18,9 @@ impl CommandKind {
         match self {
             Self::Rm => "rm",
             Self::Cp => "cp",
+            Self::Ln => "ln",
+            Self::Rmdir => "rmdir",
+            Self::Write => "write",
         }
     }
 }
@@ -159,8 +165,7 @@ pub(crate) fn with_test_notifier<T>(notifier: TestNotifier, f: impl FnOnce() ->
         previous
     });
     let _restore_guard = RestoreGuard { previous };
-    let result = f();
-    result
+    f()
 }
 
 #[cfg(not(target_os = "macos"))]
@@ -0,0 +1,282 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// 移動先・コピー先オペランドの解釈方法を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TargetMode {
+    /// 末尾のオペランドが既存ディレクトリならその中へ、そうでなければそのパスへ配置する。
+    Auto,
+    /// `-t` で指定したディレクトリの中へ配置する。
+    Directory,
+    /// `-T` 指定時、ディレクトリが存在してもオペランドそのものを最終ターゲットとする。
+    File,
+}
+
+/// コマンドラインのファイルオペランドをソースと移動先・コピー先に分けたもの。
+#[derive(Debug)]
+pub struct Operands<'a> {
+    pub sources: &'a [String],
+    target: &'a str,
+    mode: TargetMode,
+}
+
+/// 各ソースの最終ターゲットを決めるための移動先・コピー先。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Destination {
+    path: PathBuf,
+    into_directory: bool,
+    /// 末尾が `/` のため、ディレクトリとしてのみ作成できる。
+    requires_directory: bool,
+}
+
+impl<'a> Operands<'a> {
+    /// `-t`/`-T` の指定に従ってファイルオペランドを分ける。
+    ///
+    /// オペランドの数が足りない・多すぎる場合は GNU と同じ使用法エラーを返す。
+    pub fn parse(
+        command: &str,
+        files: &'a [String],
+        target_directory: Option<&'a str>,
+        no_target_directory: bool,
+    ) -> Result<Self, String> {
+        if let Some(target) = target_directory {
+            if files.is_empty() {
+                return Err(usage_error(command, "missing file operand"));
+            }
+            return Ok(Self {
+                sources: files,
+                target,
+                mode: TargetMode::Directory,
+            });
+        }
+
+        let (target, sources) = match files {
+            [] => return Err(usage_error(command, "missing file operand")),
+            [only] => {
+                return Err(usage_error(
+                    command,
+                    &format!("missing destination file operand after '{only}'"),
+                ));
+            }
+            [.., target] => (target.as_str(), &files[..files.len() - 1]),
+        };
+        if no_target_directory {
+            if let [_, _, extra, ..] = files {
+                return Err(usage_error(command, &format!("extra operand '{extra}'")));
+            }
+            return Ok(Self {
+                sources,
+                target,
+                mode: TargetMode::File,
+            });
+        }
+        Ok(Self {
+            sources,
+            target,
+            mode: TargetMode::Auto,
+        })
+    }
+
+    /// 移動先・コピー先を解決する。
+    ///
+    /// `-t` 指定時と複数ソース時はディレクトリである必要がある。末尾が `/` の既存エントリも
+    /// ディレクトリでなければエラーにする。
+    pub fn destination(&self, command: &str) -> Result<Destination, String> {
+        let path = Path::new(self.target);
+        let into_directory = match self.mode {
+            TargetMode::Directory => true,
+            TargetMode::File => false,
+            TargetMode::Auto => self.sources.len() > 1 || path.is_dir(),
+        };
+        let requires_directory = self.target.ends_with('/');
+        // 末尾が `/` のパスの stat は非ディレクトリで ENOTDIR になるため、取り除いて存在を確認する。
+        let exists = fs::symlink_metadata(path.components().collect::<PathBuf>()).is_ok();
+        if (into_directory || requires_directory && exists) && !path.is_dir() {
+            return Err(not_a_directory(command, path));
+        }
+        Ok(Destination {
+            path: path.to_path_buf(),
+            into_directory,
+            requires_directory,
+        })
+    }
+}
+
+impl Destination {
+    /// 各ソースを既存ディレクトリの中へ配置するかを返す。
+    pub fn is_directory(&self) -> bool {
+        self.into_directory
+    }
+
+    /// `--parents` 指定時の最終ターゲットとして、ディレクトリへソースのパス全体を付け足したパスを返す。
+    ///
+    /// ルートと `.` は取り除く。`..` を含むパスや、取り除くと何も残らないパスはエラーにする。
+    pub fn parents_target(&self, command: &str, source: &Path) -> Result<PathBuf, String> {
+        let invalid = || {
+            format!(
+                "{command}: invalid source path with --parents: '{}'",
+                source.display()
+            )
+        };
+        let mut target = self.path.clone();
+        let mut appended = false;
+        for component in source.components() {
+            match component {
+                Component::Normal(name) => {
+                    target.push(name);
+                    appended = true;
+                }
+                Component::ParentDir => return Err(invalid()),
+                Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            }
+        }
+        if !appended {
+            return Err(invalid());
+        }
+        Ok(target)
+    }
+
+    /// ソース `source` の最終ターゲットを返す。
+    ///
+    /// ディレクトリの中へ配置する場合はソースと同じ名前、そうでなければオペランドそのものとする。
+    /// 末尾が `/` の存在しないパスへは、ディレクトリのソースのみ配置できる。
+    pub fn final_target(
+        &self,
+        command: &str,
+        source: &Path,
+        source_is_dir: bool,
+    ) -> Result<PathBuf, String> {
+        if self.into_directory {
+            let file_name = source
+                .file_name()
+                .ok_or_else(|| format!("{command}: invalid source path: '{}'", source.display()))?;
+            return Ok(self.path.join(file_name));
+        }
+        if self.requires_directory && !source_is_dir && !self.path.is_dir() {
+            return Err(not_a_directory(command, &self.path));
+        }
+        // 末尾の `/` を取り除き、親ディレクトリとエントリ名で扱えるようにする。
+        Ok(self.path.components().collect())
+    }
+}
+
+fn not_a_directory(command: &str, path: &Path) -> String {
+    format!("{command}: target '{}' is not a directory", path.display())
+}
+
+fn usage_error(command: &str, message: &str) -> String {
+    format!("{command}: {message}\nTry '{command} --help' for more information.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn target_directory_takes_every_operand_as_source() {
+        // -t 指定時はすべてのオペランドをソースとし、指定ディレクトリの中へ配置することを確認する。
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().to_str().unwrap();
+        let files = strings(&["a", "b"]);
+
+        let operands = Operands::parse("cp", &files, Some(dir), false).unwrap();
+        assert_eq!(operands.sources, files.as_slice());
+        let destination = operands.destination("cp").unwrap();
+        assert_eq!(
+            destination
+                .final_target("cp", Path::new("x/a"), false)
+                .unwrap(),
+            temp_dir.path().join("a")
+        );
+    }
+
+    #[test]
+    fn no_target_directory_uses_existing_directory_as_is() {
+        // -T 指定時は既存ディレクトリでも中へ配置せず、3つ以上のオペランドを拒否することを確認する。
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().to_str().unwrap();
+        let files = strings(&["a", dir]);
+
+        let destination = Operands::parse("mv", &files, None, true)
+            .unwrap()
+            .destination("mv")
+            .unwrap();
+        assert_eq!(
+            destination
+                .final_target("mv", Path::new("a"), false)
+                .unwrap(),
+            temp_dir.path()
+        );
+
+        let files = strings(&["a", "b", dir]);
+        let err = Operands::parse("mv", &files, None, true).unwrap_err();
+        assert!(
+            err.starts_with(&format!("mv: extra operand '{dir}'")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parents_target_appends_whole_source_path() {
+        // --parents ではルートを除いたソースのパス全体を付け足し、`..` を含むパスを拒否することを確認する。
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().to_str().unwrap();
+        let files = strings(&["src/a/b.rs", dir]);
+        let destination = Operands::parse("cp", &files, None, false)
+            .unwrap()
+            .destination("cp")
+            .unwrap();
+
+        assert_eq!(
+            destination
+                .parents_target("cp", Path::new("./src/a/b.rs"))
+                .unwrap(),
+            temp_dir.path().join("src/a/b.rs")
+        );
+        assert_eq!(
+            destination
+                .parents_target("cp", Path::new("/etc/hosts"))
+                .unwrap(),
+            temp_dir.path().join("etc/hosts")
+        );
+        assert!(destination.parents_target("cp", Path::new("../x")).is_err());
+        assert!(destination.parents_target("cp", Path::new(".")).is_err());
+    }
+
+    #[test]
+    fn trailing_slash_requires_directory() {
+        // 末尾が `/` の移動先は、既存ファイルならエラー、存在しなければディレクトリのソースのみ許可することを確認する。
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("file");
+        fs::write(&file, b"x").unwrap();
+        let files = strings(&["a", &format!("{}/", file.display())]);
+        let err = Operands::parse("mv", &files, None, false)
+            .unwrap()
+            .destination("mv")
+            .unwrap_err();
+        assert!(err.ends_with("is not a directory"), "{err}");
+
+        let missing = temp_dir.path().join("missing");
+        let files = strings(&["a", &format!("{}/", missing.display())]);
+        let destination = Operands::parse("mv", &files, None, false)
+            .unwrap()
+            .destination("mv")
+            .unwrap();
+        assert!(
+            destination
+                .final_target("mv", Path::new("a"), false)
+                .is_err()
+        );
+        assert_eq!(
+            destination
+                .final_target("mv", Path::new("a"), true)
+                .unwrap(),
+            missing
+        );
+    }
+}
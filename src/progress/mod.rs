@@ -0,0 +1,364 @@
+use crate::limits::TreeStats;
+use serde::Serialize;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 端末向けの行を書き換える間隔。
+const LINE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 非端末向けの進捗レコードを出力する間隔。
+const RECORD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 端末の行に表示する現在パスの最大文字数。
+const MAX_PATH_CHARS: usize = 60;
+
+/// 進捗の出力形式を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// 標準エラー出力が端末の場合、1行を上書きし続ける。
+    Line,
+    /// 標準エラー出力が端末でない場合、1件1行の JSON を定期的に出力する。
+    Records,
+}
+
+/// `--progress` 指定時に処理済みのファイル数・バイト数を標準エラー出力へ報告する。
+///
+/// 無効な場合は何もしないため、呼び出し側は有効・無効を区別せずに使える。
+pub struct Progress {
+    state: Option<Mutex<State>>,
+}
+
+struct State {
+    command: &'static str,
+    output: Output,
+    totals: Option<TreeStats>,
+    done: TreeStats,
+    current: String,
+    started: Instant,
+    last_emitted: Option<Instant>,
+}
+
+/// 非端末向けに出力する進捗レコード。
+#[derive(Debug, Serialize)]
+struct ProgressRecord<'a> {
+    event: &'static str,
+    command: &'static str,
+    files_done: u64,
+    bytes_done: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files_total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes_total: Option<u64>,
+    bytes_per_sec: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eta_secs: Option<u64>,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    path: &'a str,
+}
+
+impl Progress {
+    /// 何も出力しない進捗報告を生成する。
+    pub fn disabled() -> Self {
+        Self { state: None }
+    }
+
+    /// `enabled` が `true` の場合、標準エラー出力が端末かどうかに応じた形式で進捗を報告する。
+    pub fn new(command: &'static str, enabled: bool) -> Self {
+        if !enabled {
+            return Self::disabled();
+        }
+        let output = if io::stderr().is_terminal() {
+            Output::Line
+        } else {
+            Output::Records
+        };
+        Self::with_output(command, output)
+    }
+
+    fn with_output(command: &'static str, output: Output) -> Self {
+        Self {
+            state: Some(Mutex::new(State {
+                command,
+                output,
+                totals: None,
+                done: TreeStats::default(),
+                current: String::new(),
+                started: Instant::now(),
+                last_emitted: None,
+            })),
+        }
+    }
+
+    /// 進捗報告が有効かを返す。
+    pub fn is_enabled(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// 事前走査で求めた総量を設定し、経過時間の計測を開始する。
+    pub fn set_totals(&self, totals: TreeStats) {
+        self.update(|state| {
+            state.totals = Some(totals);
+            state.started = Instant::now();
+            if state.output == Output::Records {
+                emit_record(&state.record("start", Instant::now()));
+            }
+        });
+    }
+
+    /// 1エントリ分の処理完了を記録し、出力間隔を過ぎていれば進捗を出力する。
+    pub fn advance(&self, path: &Path, bytes: u64) {
+        self.advance_by(path, TreeStats { files: 1, bytes });
+    }
+
+    /// ディレクトリ全体など複数エントリ分の処理完了をまとめて記録する。
+    pub fn advance_by(&self, path: &Path, stats: TreeStats) {
+        self.update(|state| {
+            state.done.files += stats.files;
+            state.done.bytes += stats.bytes;
+            state.current = path.display().to_string();
+
+            let now = Instant::now();
+            let interval = match state.output {
+                Output::Line => LINE_INTERVAL,
+                Output::Records => RECORD_INTERVAL,
+            };
+            if state
+                .last_emitted
+                .is_some_and(|last| now.duration_since(last) < interval)
+            {
+                return;
+            }
+            state.last_emitted = Some(now);
+            match state.output {
+                Output::Line => emit_line(&state.line(now)),
+                Output::Records => emit_record(&state.record("progress", now)),
+            }
+        });
+    }
+
+    /// 最終的な進捗を出力する。端末の場合は行を確定して改行する。
+    pub fn finish(&self) {
+        self.update(|state| {
+            let now = Instant::now();
+            match state.output {
+                Output::Line => {
+                    emit_line(&state.line(now));
+                    eprintln!();
+                }
+                Output::Records => emit_record(&state.record("done", now)),
+            }
+        });
+    }
+
+    fn update<F: FnOnce(&mut State)>(&self, f: F) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        let mut state = state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut state);
+    }
+}
+
+impl State {
+    /// 経過時間から求めた1秒あたりの処理バイト数を返す。
+    fn bytes_per_sec(&self, now: Instant) -> u64 {
+        let elapsed = now.duration_since(self.started).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0;
+        }
+        (self.done.bytes as f64 / elapsed) as u64
+    }
+
+    /// 総量とこれまでの処理速度から残り時間（秒）を見積もる。
+    fn eta_secs(&self, now: Instant) -> Option<u64> {
+        let totals = self.totals?;
+        let elapsed = now.duration_since(self.started).as_secs_f64();
+        if totals.bytes > 0 && self.done.bytes > 0 {
+            let remaining = totals.bytes.saturating_sub(self.done.bytes) as f64;
+            return Some((remaining * elapsed / self.done.bytes as f64).round() as u64);
+        }
+        if self.done.files > 0 {
+            let remaining = totals.files.saturating_sub(self.done.files) as f64;
+            return Some((remaining * elapsed / self.done.files as f64).round() as u64);
+        }
+        None
+    }
+
+    fn record(&self, event: &'static str, now: Instant) -> ProgressRecord<'_> {
+        ProgressRecord {
+            event,
+            command: self.command,
+            files_done: self.done.files,
+            bytes_done: self.done.bytes,
+            files_total: self.totals.map(|totals| totals.files),
+            bytes_total: self.totals.map(|totals| totals.bytes),
+            bytes_per_sec: self.bytes_per_sec(now),
+            eta_secs: self.eta_secs(now),
+            path: &self.current,
+        }
+    }
+
+    fn line(&self, now: Instant) -> String {
+        let mut line = match self.totals {
+            Some(totals) => format!(
+                "{}: {}/{} files, {}/{}",
+                self.command,
+                self.done.files,
+                totals.files,
+                format_bytes(self.done.bytes),
+                format_bytes(totals.bytes)
+            ),
+            None => format!(
+                "{}: {} files, {}",
+                self.command,
+                self.done.files,
+                format_bytes(self.done.bytes)
+            ),
+        };
+        line.push_str(&format!(", {}/s", format_bytes(self.bytes_per_sec(now))));
+        if let Some(eta) = self.eta_secs(now) {
+            line.push_str(&format!(", ETA {}", format_duration(eta)));
+        }
+        if !self.current.is_empty() {
+            line.push_str(&format!(", {}", shorten_path(&self.current)));
+        }
+        line
+    }
+}
+
+fn emit_line(line: &str) {
+    let mut stderr = io::stderr().lock();
+    let _ = write!(stderr, "\r\x1b[K{line}");
+    let _ = stderr.flush();
+}
+
+fn emit_record(record: &ProgressRecord<'_>) {
+    if let Ok(json) = serde_json::to_string(record) {
+        eprintln!("{json}");
+    }
+}
+
+/// バイト数を2進接頭辞付きの読みやすい表記にする。
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// 秒数を `m:ss` または `h:mm:ss` 形式にする。
+fn format_duration(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// 長いパスは末尾を残して省略する。
+fn shorten_path(path: &str) -> String {
+    let chars = path.chars().count();
+    if chars <= MAX_PATH_CHARS {
+        return path.to_string();
+    }
+    let tail: String = path.chars().skip(chars - (MAX_PATH_CHARS - 1)).collect();
+    format!("…{tail}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(done: TreeStats, totals: Option<TreeStats>, started: Instant) -> State {
+        State {
+            command: "cp",
+            output: Output::Records,
+            totals,
+            done,
+            current: "src/data.bin".to_string(),
+            started,
+            last_emitted: None,
+        }
+    }
+
+    #[test]
+    fn line_shows_counts_throughput_eta_and_path() {
+        // 端末向けの行に件数・バイト数・速度・残り時間・現在パスが含まれることを確認する。
+        let started = Instant::now();
+        let now = started + Duration::from_secs(2);
+        let state = state_with(
+            TreeStats {
+                files: 1,
+                bytes: 2 * 1024 * 1024,
+            },
+            Some(TreeStats {
+                files: 4,
+                bytes: 8 * 1024 * 1024,
+            }),
+            started,
+        );
+
+        assert_eq!(
+            state.line(now),
+            "cp: 1/4 files, 2.0 MiB/8.0 MiB, 1.0 MiB/s, ETA 0:06, src/data.bin"
+        );
+    }
+
+    #[test]
+    fn record_omits_totals_when_not_prescanned() {
+        // 総量が未設定の場合、レコードから総量と残り時間を省略することを確認する。
+        let started = Instant::now();
+        let state = state_with(
+            TreeStats {
+                files: 3,
+                bytes: 10,
+            },
+            None,
+            started,
+        );
+
+        let json = serde_json::to_value(state.record("progress", started)).unwrap();
+
+        assert_eq!(json["event"], "progress");
+        assert_eq!(json["files_done"], 3);
+        assert!(json.get("files_total").is_none());
+        assert!(json.get("eta_secs").is_none());
+        assert_eq!(json["path"], "src/data.bin");
+    }
+
+    #[test]
+    fn disabled_progress_ignores_updates() {
+        // 無効な進捗報告は状態を持たず、更新しても何も起きないことを確認する。
+        let progress = Progress::disabled();
+        progress.advance(Path::new("file"), 1);
+        progress.finish();
+
+        assert!(!progress.is_enabled());
+    }
+
+    #[test]
+    fn format_helpers_produce_compact_units() {
+        // バイト数・時間・長いパスを読みやすい表記に変換することを確認する。
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+        assert_eq!(format_duration(75), "1:15");
+        assert_eq!(format_duration(3725), "1:02:05");
+        let long = "d/".repeat(40);
+        let short = shorten_path(&long);
+        assert_eq!(short.chars().count(), MAX_PATH_CHARS);
+        assert!(short.starts_with('…'));
+    }
+}
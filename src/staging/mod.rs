@@ -0,0 +1,290 @@
+use crate::dirfd::{Dir, RenameMode};
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 同一プロセス内で一時パス名が衝突しないようにする連番。
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// 一時パス名の生成を諦めるまでの試行回数。
+const MAX_ATTEMPTS: usize = 100;
+
+/// `dir` 直下に存在しない一時エントリ名を `build_name` で生成する。
+///
+/// `build_name` にはプロセス ID と連番からなる識別子を渡す。エントリ自体は作成しない。
+pub fn unique_name<F>(dir: &Dir, build_name: F) -> io::Result<OsString>
+where
+    F: Fn(&str) -> String,
+{
+    for _ in 0..MAX_ATTEMPTS {
+        let id = format!(
+            "{}-{}",
+            process::id(),
+            SEQUENCE.fetch_add(1, Ordering::Relaxed)
+        );
+        let candidate = OsString::from(build_name(&id));
+        if !dir.contains(&candidate) {
+            return Ok(candidate);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "too many temporary name collisions",
+    ))
+}
+
+/// `dir` 直下に所有者のみがアクセスできる隠しステージングディレクトリ（`.<prefix>-<id>`）を作成する。
+pub fn create_dir(dir: &Dir, prefix: &str) -> io::Result<OsString> {
+    for _ in 0..MAX_ATTEMPTS {
+        let candidate = unique_name(dir, |id| format!(".{prefix}-{id}"))?;
+        match dir.create_dir(&candidate, 0o700) {
+            Ok(()) => return Ok(candidate),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "too many staging directory name collisions",
+    ))
+}
+
+/// 開いた親ディレクトリ `dir` 直下のエントリを、パスの差し替えに影響されずにゴミ箱へ移動する。
+///
+/// エントリはまず `dir` に作成した隠しステージングディレクトリ（`.safecmd-trash-<id>`）へ
+/// ディスクリプタ基準の `RENAME_NOREPLACE` で移し、そのパスを `delete` へ渡す。`delete` が
+/// パスを辿る時点で親ディレクトリが差し替えられていても、ステージングディレクトリが存在しない
+/// 場所を指すだけで範囲外のエントリには届かない。システムのゴミ箱へ移動する `delete` は、
+/// 移動後に `record_original_path` で記録された元の場所を本来の親ディレクトリへ書き換える。
+/// 失敗時はエントリを元の名前へ戻し、ステージングディレクトリを削除する。
+pub fn trash_entry<F>(dir: &Dir, name: &OsStr, delete: F) -> Result<(), String>
+where
+    F: FnOnce(&Path) -> Result<(), String>,
+{
+    dir.verified_path(name).map_err(|e| e.to_string())?;
+    let staging_name = create_dir(dir, "safecmd-trash").map_err(|e| e.to_string())?;
+    let staging_dir = match dir.open_dir(&staging_name) {
+        Ok(staging_dir) => staging_dir,
+        Err(e) => {
+            let _ = dir.remove_dir(&staging_name);
+            return Err(e.to_string());
+        }
+    };
+    if let Err(e) = dir.rename(name, &staging_dir, name, RenameMode::NoReplace) {
+        let _ = dir.remove_dir(&staging_name);
+        return Err(e.to_string());
+    }
+
+    let result = staging_dir
+        .verified_path(name)
+        .map_err(|e| e.to_string())
+        .and_then(|staged| delete(&staged));
+    match result {
+        Ok(()) => {
+            let _ = dir.remove_dir(&staging_name);
+            Ok(())
+        }
+        Err(e) => {
+            if staging_dir
+                .rename(name, dir, name, RenameMode::NoReplace)
+                .is_err()
+            {
+                return Err(format!(
+                    "{e}; '{}' was kept at '{}'",
+                    Path::new(name).display(),
+                    staging_dir.path().join(name).display()
+                ));
+            }
+            let _ = dir.remove_dir(&staging_name);
+            Err(e)
+        }
+    }
+}
+
+/// `trash_entry` がゴミ箱へ移動した `staged` について、情報ファイルに記録された元の場所を
+/// ステージングディレクトリから本来の親ディレクトリへ書き換える。
+///
+/// freedesktop 形式のゴミ箱のみ対応する。他の環境ではステージングディレクトリが記録されたままになる。
+#[cfg(all(
+    unix,
+    not(target_os = "macos"),
+    not(target_os = "ios"),
+    not(target_os = "android")
+))]
+pub fn record_original_path(staged: &Path) -> io::Result<()> {
+    use std::fs;
+
+    let (Some(staging), Some(name)) = (staged.parent(), staged.file_name()) else {
+        return Ok(());
+    };
+    let staging = staging.canonicalize()?;
+    let Some(parent) = staging.parent() else {
+        return Ok(());
+    };
+    let item = trash::os_limited::list()
+        .map_err(io::Error::other)?
+        .into_iter()
+        .find(|item| item.original_parent == staging && item.name == name)
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+    let info_path = Path::new(&item.id);
+    let info = fs::read_to_string(info_path)?;
+    let rewritten: String = info
+        .lines()
+        .map(|line| {
+            if line.starts_with("Path=") {
+                format!("Path={}\n", encode_uri_path(&parent.join(name)))
+            } else {
+                format!("{line}\n")
+            }
+        })
+        .collect();
+    fs::write(info_path, rewritten)
+}
+
+#[cfg(not(all(
+    unix,
+    not(target_os = "macos"),
+    not(target_os = "ios"),
+    not(target_os = "android")
+)))]
+pub fn record_original_path(_staged: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// 情報ファイルの `Path=` 用に、パスの各要素を URI のパーセントエンコードで表す。
+#[cfg(all(
+    unix,
+    not(target_os = "macos"),
+    not(target_os = "ios"),
+    not(target_os = "android")
+))]
+fn encode_uri_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut encoded = String::new();
+    for byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'/' | b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(char::from(*byte))
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+    use tempfile::TempDir;
+
+    #[test]
+    fn trash_entry_passes_staged_path_and_removes_staging_directory() {
+        // エントリをステージングディレクトリへ移してから退避し、後に何も残さないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("data.txt"), "old").unwrap();
+        let trash_dir = TempDir::new().unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+
+        trash_entry(&dir, OsStr::new("data.txt"), |staged| {
+            let staging = staged.parent().unwrap();
+            assert_eq!(staging.parent().unwrap(), temp_dir.path());
+            assert!(
+                staging
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with(".safecmd-trash-")
+            );
+            fs::rename(staged, trash_dir.path().join("data.txt")).map_err(|e| e.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+        assert_eq!(
+            fs::read_to_string(trash_dir.path().join("data.txt")).unwrap(),
+            "old"
+        );
+    }
+
+    #[test]
+    fn trash_entry_restores_entry_when_delete_fails() {
+        // ゴミ箱への移動に失敗した場合、エントリを元の名前へ戻すことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("data.txt"), "old").unwrap();
+        let dir = Dir::open(temp_dir.path()).unwrap();
+
+        let err = trash_entry(&dir, OsStr::new("data.txt"), |_| {
+            Err("trash failed".to_string())
+        })
+        .unwrap_err();
+
+        assert_eq!(err, "trash failed");
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("data.txt")).unwrap(),
+            "old"
+        );
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn trash_entry_never_reaches_outside_while_parent_is_swapped_concurrently() {
+        // 親ディレクトリのパスを別スレッドでシンボリックリンクと入れ替え続けても、
+        // パスで退避する処理がリンク先の同名エントリへ届かないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let scope = temp_dir.path().join("scope");
+        let outside = temp_dir.path().join("outside");
+        let trash_dir = temp_dir.path().join("trash");
+        fs::create_dir_all(scope.join("dir")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        fs::create_dir(&trash_dir).unwrap();
+        let dir = Dir::open(&scope.join("dir")).unwrap();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let swapper = {
+            let stop = Arc::clone(&stop);
+            let scope = scope.clone();
+            let outside = outside.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    fs::rename(scope.join("dir"), scope.join("moved")).unwrap();
+                    std::os::unix::fs::symlink(&outside, scope.join("dir")).unwrap();
+                    thread::yield_now();
+                    fs::remove_file(scope.join("dir")).unwrap();
+                    fs::rename(scope.join("moved"), scope.join("dir")).unwrap();
+                    thread::yield_now();
+                }
+            })
+        };
+
+        for i in 0..200 {
+            let name = OsString::from(format!("victim-{i}.txt"));
+            fs::write(dir.entry_path(&name), "inside").unwrap();
+            fs::write(outside.join(&name), "outside").unwrap();
+            let _ = trash_entry(&dir, &name, |staged| {
+                fs::rename(staged, trash_dir.join(&name)).map_err(|e| e.to_string())
+            });
+        }
+        stop.store(true, Ordering::Relaxed);
+        swapper.join().unwrap();
+
+        for i in 0..200 {
+            let name = format!("victim-{i}.txt");
+            assert_eq!(
+                fs::read_to_string(outside.join(&name)).unwrap(),
+                "outside",
+                "{name}"
+            );
+            let kept = scope.join("dir").join(&name);
+            let trashed = trash_dir.join(&name);
+            let survivor = if kept.exists() { kept } else { trashed };
+            assert_eq!(fs::read_to_string(survivor).unwrap(), "inside", "{name}");
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::verify::{self, HashAlgorithm};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// `--update` で指定する、既存ターゲットを置き換える条件を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UpdateMode {
+    /// 常に置き換える（既定）
+    All,
+    /// 既存ターゲットは置き換えずにスキップする
+    None,
+    /// ターゲットがソースより古い場合のみ置き換える
+    Older,
+    /// サイズとハッシュを比較し、内容が異なる場合のみ置き換える
+    Content,
+}
+
+/// 既存ターゲットを置き換えずにスキップした理由を表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkipReason {
+    /// `--update=none` で既存ターゲットがあった。
+    Exists,
+    /// `--update=older` でターゲットがソース以降に更新されていた。
+    NotOlder,
+    /// `--update=content` で内容が一致した。
+    Identical,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exists => f.write_str("destination exists"),
+            Self::NotOlder => f.write_str("destination is not older"),
+            Self::Identical => f.write_str("content is identical"),
+        }
+    }
+}
+
+impl UpdateMode {
+    /// 既存ターゲットを置き換えるかを判定し、スキップする場合はその理由を返す。
+    ///
+    /// `source_meta`・`target_meta` はそれぞれ比較に使うメタデータで、`source`・`target` は
+    /// `content` の比較で読み込むパスとする。通常ファイル同士は内容、シンボリックリンク同士は
+    /// リンク先を比較し、種類が異なる場合は置き換える。
+    pub fn skip_reason(
+        self,
+        source: &Path,
+        source_meta: &fs::Metadata,
+        target: &Path,
+        target_meta: &fs::Metadata,
+    ) -> io::Result<Option<SkipReason>> {
+        match self {
+            Self::All => Ok(None),
+            Self::None => Ok(Some(SkipReason::Exists)),
+            Self::Older => {
+                let newer = source_meta.modified()? > target_meta.modified()?;
+                Ok((!newer).then_some(SkipReason::NotOlder))
+            }
+            Self::Content => {
+                let identical = same_content(source, source_meta, target, target_meta)?;
+                Ok(identical.then_some(SkipReason::Identical))
+            }
+        }
+    }
+}
+
+/// サイズを比較し、一致する場合のみハッシュを計算して内容の一致を判定する。
+fn same_content(
+    source: &Path,
+    source_meta: &fs::Metadata,
+    target: &Path,
+    target_meta: &fs::Metadata,
+) -> io::Result<bool> {
+    let (source_type, target_type) = (source_meta.file_type(), target_meta.file_type());
+    if source_type.is_symlink() && target_type.is_symlink() {
+        return Ok(fs::read_link(source)? == fs::read_link(target)?);
+    }
+    if !source_type.is_file() || !target_type.is_file() || source_meta.len() != target_meta.len() {
+        return Ok(false);
+    }
+    let source_digest = verify::hash_file(source, HashAlgorithm::Blake3)?;
+    let target_digest = verify::hash_file(target, HashAlgorithm::Blake3)?;
+    Ok(source_digest == target_digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[test]
+    fn older_skips_target_not_older_than_source() {
+        // older ではターゲットがソース以降に更新されている場合のみスキップすることを確認する。
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        fs::write(&source, b"a").unwrap();
+        fs::write(&target, b"b").unwrap();
+        let now = SystemTime::now();
+        fs::File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_modified(now - Duration::from_secs(60))
+            .unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&target)
+            .unwrap()
+            .set_modified(now)
+            .unwrap();
+        let meta = |path: &Path| fs::symlink_metadata(path).unwrap();
+
+        let skip = UpdateMode::Older
+            .skip_reason(&source, &meta(&source), &target, &meta(&target))
+            .unwrap();
+        assert_eq!(skip, Some(SkipReason::NotOlder));
+        let skip = UpdateMode::Older
+            .skip_reason(&target, &meta(&target), &source, &meta(&source))
+            .unwrap();
+        assert_eq!(skip, None);
+    }
+
+    #[test]
+    fn content_compares_size_and_hash() {
+        // content では同じサイズで内容が異なるファイルを置き換え、一致するファイルをスキップすることを確認する。
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("source");
+        let same = temp_dir.path().join("same");
+        let different = temp_dir.path().join("different");
+        fs::write(&source, b"abc").unwrap();
+        fs::write(&same, b"abc").unwrap();
+        fs::write(&different, b"abd").unwrap();
+        let meta = |path: &Path| fs::symlink_metadata(path).unwrap();
+
+        let skip = UpdateMode::Content
+            .skip_reason(&source, &meta(&source), &same, &meta(&same))
+            .unwrap();
+        assert_eq!(skip, Some(SkipReason::Identical));
+        let skip = UpdateMode::Content
+            .skip_reason(&source, &meta(&source), &different, &meta(&different))
+            .unwrap();
+        assert_eq!(skip, None);
+    }
+}
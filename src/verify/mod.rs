@@ -0,0 +1,226 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use sha2::Digest as _;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// 読み込み・比較に使うバッファサイズ。
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// `--verify` で使用するハッシュアルゴリズムを表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Blake3,
+    Sha256,
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Blake3 => f.write_str("blake3"),
+            Self::Sha256 => f.write_str("sha256"),
+        }
+    }
+}
+
+/// ハッシュ値をアルゴリズム名と16進文字列で保持する。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Digest {
+    pub algorithm: HashAlgorithm,
+    pub hex: String,
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.hex)
+    }
+}
+
+/// アルゴリズムごとの逐次ハッシュ計算を抽象化する。
+pub enum Hasher {
+    Blake3(Box<blake3::Hasher>),
+    Sha256(sha2::Sha256),
+}
+
+impl Hasher {
+    /// 指定アルゴリズムの空のハッシュ計算状態を生成する。
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::Sha256 => Self::Sha256(sha2::Sha256::new()),
+        }
+    }
+
+    /// 読み込んだバイト列をハッシュへ反映する。
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Blake3(hasher) => {
+                hasher.update(bytes);
+            }
+            Self::Sha256(hasher) => hasher.update(bytes),
+        }
+    }
+
+    /// 穴（読み込まずにゼロとして扱う領域）を `len` バイト分ハッシュへ反映する。
+    pub fn update_zeros(&mut self, mut len: u64) {
+        let zeros = [0u8; 8192];
+        while len > 0 {
+            let chunk = zeros.len().min(len as usize);
+            self.update(&zeros[..chunk]);
+            len -= chunk as u64;
+        }
+    }
+
+    /// `reader` を末尾まで読み込んでハッシュへ反映する。
+    pub fn update_reader<R: Read>(&mut self, reader: &mut R) -> io::Result<()> {
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                return Ok(());
+            }
+            self.update(&buffer[..read]);
+        }
+    }
+
+    /// ハッシュ計算を完了して結果を返す。
+    pub fn finalize(self) -> Digest {
+        match self {
+            Self::Blake3(hasher) => Digest {
+                algorithm: HashAlgorithm::Blake3,
+                hex: hasher.finalize().to_hex().to_string(),
+            },
+            Self::Sha256(hasher) => Digest {
+                algorithm: HashAlgorithm::Sha256,
+                hex: to_hex(&hasher.finalize()),
+            },
+        }
+    }
+}
+
+/// 検証失敗の理由を表す。
+#[derive(Debug)]
+pub enum VerifyError {
+    /// コピー先の再読み込みに失敗した。
+    Io(io::Error),
+    /// コピー元とコピー先のハッシュが一致しない。
+    Mismatch { expected: Digest, actual: Digest },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to re-read copy: {e}"),
+            Self::Mismatch { expected, actual } => {
+                write!(f, "checksum mismatch (expected {expected}, got {actual})")
+            }
+        }
+    }
+}
+
+/// `reader` から `writer` へ内容を書き出しながら、読み込んだ内容のハッシュを計算する。
+pub fn copy_hashed<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    algorithm: HashAlgorithm,
+) -> io::Result<(u64, Digest)> {
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut copied = 0;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+        copied += read as u64;
+    }
+    Ok((copied, hasher.finalize()))
+}
+
+/// ファイル全体のハッシュを計算する。
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> io::Result<Digest> {
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update_reader(&mut fs::File::open(path)?)?;
+    Ok(hasher.finalize())
+}
+
+/// fsync 済みのコピー先を読み直し、コピー元のハッシュと一致するかを検証する。
+pub fn verify_file(path: &Path, expected: &Digest) -> Result<(), VerifyError> {
+    let actual = hash_file(path, expected.algorithm).map_err(VerifyError::Io)?;
+    if &actual != expected {
+        return Err(VerifyError::Mismatch {
+            expected: expected.clone(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn hash_file_matches_known_digests() {
+        // 既知の入力に対して各アルゴリズムが標準のハッシュ値を返すことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("abc.txt");
+        fs::write(&path, "abc").unwrap();
+
+        assert_eq!(
+            hash_file(&path, HashAlgorithm::Sha256).unwrap().hex,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hash_file(&path, HashAlgorithm::Blake3).unwrap().hex,
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+    }
+
+    #[test]
+    fn copy_hashed_and_zero_updates_match_whole_file_hash() {
+        // 書き出しながらのハッシュと、穴をゼロとして反映したハッシュが全体のハッシュと一致することを確認する。
+        let mut content = b"head".to_vec();
+        content.extend(std::iter::repeat_n(0u8, 10_000));
+        let mut written = Vec::new();
+
+        let (copied, digest) =
+            copy_hashed(&mut content.as_slice(), &mut written, HashAlgorithm::Blake3).unwrap();
+        let mut with_hole = Hasher::new(HashAlgorithm::Blake3);
+        with_hole.update(b"head");
+        with_hole.update_zeros(10_000);
+
+        assert_eq!(copied, content.len() as u64);
+        assert_eq!(written, content);
+        assert_eq!(with_hole.finalize(), digest);
+    }
+
+    #[test]
+    fn verify_file_reports_mismatch() {
+        // 内容が異なる場合に期待値と実際の値を含む不一致エラーを返すことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("copy.txt");
+        fs::write(&path, "corrupted").unwrap();
+        let expected = Digest {
+            algorithm: HashAlgorithm::Sha256,
+            hex: "00".repeat(32),
+        };
+
+        let err = verify_file(&path, &expected).unwrap_err();
+
+        assert!(matches!(err, VerifyError::Mismatch { .. }));
+        assert!(err.to_string().contains("expected sha256:00"));
+    }
+}
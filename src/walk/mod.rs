@@ -0,0 +1,484 @@
+use crate::dirfd::Dir;
+use crate::progress::Progress;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+mod parallel;
+
+pub use parallel::{ParallelWalker, SharedVisitor};
+
+/// 走査中に見つけたエントリを表す。
+pub struct Entry<'a> {
+    pub path: &'a Path,
+    /// 走査ルートからの相対パス。ルート自身は空のパスとなる。
+    pub relative: &'a Path,
+    pub metadata: &'a fs::Metadata,
+    /// シンボリックリンクを解決した位置。辿らなかったリンクはリンク自身の位置となる。
+    ///
+    /// ルートのみ走査開始時に正規化し、配下は親の解決済みパスへ名前を連結して求める。
+    /// 辿ったリンクだけを正規化し直す。
+    pub resolved: &'a Path,
+    /// ルートから親ディレクトリまでの祖先ディレクトリの (デバイス, inode)。
+    pub ancestors: &'a [(u64, u64)],
+    /// エントリを含むディレクトリを走査中に開いたディスクリプタ。ルートでは `None` となる。
+    pub parent: Option<&'a Dir>,
+}
+
+impl Entry<'_> {
+    /// 走査ルート自身かを返す。
+    pub fn is_root(&self) -> bool {
+        self.relative.as_os_str().is_empty()
+    }
+
+    /// 内容や属性の読み込みに使うパスを返す。
+    ///
+    /// 親ディレクトリを開いている場合はそのディスクリプタ経由で指し、途中のパス要素を辿り直さない。
+    pub fn pinned_path(&self) -> PathBuf {
+        match (self.parent, self.path.file_name()) {
+            (Some(parent), Some(name)) => parent.entry_path(name),
+            _ => self.path.to_path_buf(),
+        }
+    }
+
+    /// 通常ファイルのエントリを読み込み用に開く。
+    ///
+    /// 親ディレクトリを開いている場合は、そのディスクリプタからリンクを辿らずに開く。
+    pub fn open_file(&self) -> io::Result<fs::File> {
+        match (self.parent, self.path.file_name()) {
+            (Some(parent), Some(name)) => parent.open_file(name),
+            _ => fs::File::open(self.path),
+        }
+    }
+
+    /// 祖先ディレクトリと同一のディレクトリ（リンクを辿った先での循環）かを返す。
+    pub fn reenters_ancestor(&self) -> bool {
+        self.metadata.is_dir() && self.ancestors.contains(&directory_id(self.metadata))
+    }
+}
+
+/// ビジターの処理結果として、エントリを処理対象に含めるかを表す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    /// 処理した。ディレクトリの場合は配下へ進む。
+    Continue,
+    /// スキップした。ディレクトリの場合は配下を走査せず、進捗にも数えない。
+    Skip,
+}
+
+/// 走査で見つけたエントリごとの処理を定義する。
+pub trait Visitor {
+    type Error;
+
+    /// ディレクトリへ入る前に呼ばれる。`Visit::Skip` を返すと配下を走査しない。
+    fn enter_dir(&mut self, entry: &Entry<'_>) -> Result<Visit, Self::Error>;
+
+    /// ディレクトリ配下の走査完了後に呼ばれる。
+    fn leave_dir(&mut self, _entry: &Entry<'_>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// ディレクトリ以外のエントリごとに呼ばれる。
+    fn visit(&mut self, entry: &Entry<'_>) -> Result<Visit, Self::Error>;
+
+    /// メタデータ取得やディレクトリ読み込みの失敗をビジターのエラー型へ変換する。
+    fn walk_error(&mut self, path: &Path, err: io::Error) -> Self::Error;
+}
+
+/// cp の再帰コピー・ゴミ箱フォールバックの複製・rm の事前走査で共有するツリー走査。
+///
+/// 配下はファイル名順に深さ優先で走査し、処理したディレクトリ以外のエントリを進捗として報告する。
+/// 各ディレクトリは親のディスクリプタから開き、配下のエントリはそこからの相対で調べる。
+pub struct Walker<'p> {
+    root: PathBuf,
+    follow_root: bool,
+    follow_links: bool,
+    progress: Option<&'p Progress>,
+}
+
+impl<'p> Walker<'p> {
+    /// シンボリックリンクを辿らずに `root` を走査する設定で生成する。
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            follow_root: false,
+            follow_links: false,
+            progress: None,
+        }
+    }
+
+    /// ルート自身がシンボリックリンクの場合にリンク先を走査するかを設定する。
+    pub fn follow_root(mut self, follow: bool) -> Self {
+        self.follow_root = follow;
+        self
+    }
+
+    /// 配下のシンボリックリンクを辿るかを設定する。循環の検出はビジター側で行う。
+    pub fn follow_links(mut self, follow: bool) -> Self {
+        self.follow_links = follow;
+        self
+    }
+
+    /// 処理したエントリを報告する進捗を設定する。
+    pub fn progress(mut self, progress: &'p Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// ルートから走査し、最初に発生したエラーで中断する。
+    pub fn run<V: Visitor>(&self, visitor: &mut V) -> Result<(), V::Error> {
+        let (metadata, resolved) =
+            root_of(&self.root, self.follow_root).map_err(|e| visitor.walk_error(&self.root, e))?;
+        let root = Entry {
+            path: &self.root,
+            relative: Path::new(""),
+            metadata: &metadata,
+            resolved: &resolved,
+            ancestors: &[],
+            parent: None,
+        };
+        self.walk_entry(&root, visitor)
+    }
+
+    fn walk_entry<V: Visitor>(&self, entry: &Entry<'_>, visitor: &mut V) -> Result<(), V::Error> {
+        if !entry.metadata.is_dir() {
+            if visitor.visit(entry)? == Visit::Continue
+                && let Some(progress) = self.progress
+            {
+                progress.advance(entry.path, entry.metadata.len());
+            }
+            return Ok(());
+        }
+
+        if visitor.enter_dir(entry)? == Visit::Skip {
+            return Ok(());
+        }
+        let (dir, names) = open_children(entry, self.follow_links)
+            .map_err(|e| visitor.walk_error(entry.path, e))?;
+        let chain = [entry.ancestors, &[directory_id(entry.metadata)]].concat();
+        for name in names {
+            let child = entry.path.join(&name);
+            let child_relative = entry.relative.join(&name);
+            let (child_metadata, child_resolved) =
+                child_of(&dir, &name, entry.resolved, self.follow_links)
+                    .map_err(|e| visitor.walk_error(&child, e))?;
+            let child_entry = Entry {
+                path: &child,
+                relative: &child_relative,
+                metadata: &child_metadata,
+                resolved: &child_resolved,
+                ancestors: &chain,
+                parent: Some(&dir),
+            };
+            self.walk_entry(&child_entry, visitor)?;
+        }
+        visitor.leave_dir(entry)
+    }
+}
+
+/// ルートのメタデータと解決済みの位置を取得する。
+///
+/// `.` や `/` のように名前を持たないルートはリンクになり得ないため、そのまま正規化する。
+fn root_of(root: &Path, follow: bool) -> io::Result<(fs::Metadata, PathBuf)> {
+    if follow {
+        return Ok((fs::metadata(root)?, root.canonicalize()?));
+    }
+    if root.file_name().is_none() {
+        return Ok((fs::symlink_metadata(root)?, root.canonicalize()?));
+    }
+    Ok((
+        fs::symlink_metadata(root)?,
+        canonicalize_link_location(root)?,
+    ))
+}
+
+/// ディレクトリのエントリを開き、直下の名前をファイル名順に返す。
+///
+/// ルート以外は走査中に開いた親ディレクトリから開き、リンクを辿らない走査では `O_NOFOLLOW` とする。
+/// 開いたディレクトリが走査で調べたものと異なる場合（確認後の差し替え）は失敗する。
+fn open_children(entry: &Entry<'_>, follow: bool) -> io::Result<(Dir, Vec<OsString>)> {
+    let dir = match (entry.parent, entry.path.file_name()) {
+        (Some(parent), Some(name)) if follow => Dir::open(&parent.entry_path(name))?,
+        (Some(parent), Some(name)) => parent.open_dir(name)?,
+        _ => Dir::open(entry.path)?,
+    };
+    if directory_id(&dir.metadata()?) != directory_id(entry.metadata) {
+        return Err(io::Error::other(format!(
+            "'{}' was replaced during the operation",
+            entry.path.display()
+        )));
+    }
+    let names = dir.entry_names()?;
+    Ok((dir, names))
+}
+
+/// 開いた親ディレクトリ直下のエントリについて、メタデータと解決済みの位置を取得する。
+///
+/// 位置は親の解決済みパスへ名前を連結して求め、辿ったリンクのみ正規化し直す。
+fn child_of(
+    parent: &Dir,
+    name: &OsStr,
+    parent_resolved: &Path,
+    follow: bool,
+) -> io::Result<(fs::Metadata, PathBuf)> {
+    let metadata = parent.symlink_metadata(name)?;
+    if follow && metadata.file_type().is_symlink() {
+        let link = parent.entry_path(name);
+        return Ok((fs::metadata(&link)?, link.canonicalize()?));
+    }
+    Ok((metadata, parent_resolved.join(name)))
+}
+
+/// シンボリックリンクを辿らず、リンク自身の配置場所を正規化する。
+pub fn canonicalize_link_location(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid path"))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+        _ => std::env::current_dir()?,
+    };
+    Ok(parent.join(file_name))
+}
+
+#[cfg(unix)]
+fn directory_id(meta: &fs::Metadata) -> (u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (meta.dev(), meta.ino())
+}
+
+#[cfg(not(unix))]
+fn directory_id(_meta: &fs::Metadata) -> (u64, u64) {
+    (0, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 呼び出し順を記録するビジター。`skip` に一致するディレクトリは配下を走査しない。
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        skip: Option<&'static str>,
+    }
+
+    impl Visitor for Recorder {
+        type Error = io::Error;
+
+        fn enter_dir(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+            let name = entry.relative.display().to_string();
+            self.events.push(format!("enter {name}"));
+            if self.skip == Some(name.as_str()) {
+                return Ok(Visit::Skip);
+            }
+            Ok(Visit::Continue)
+        }
+
+        fn leave_dir(&mut self, entry: &Entry<'_>) -> io::Result<()> {
+            self.events
+                .push(format!("leave {}", entry.relative.display()));
+            Ok(())
+        }
+
+        fn visit(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+            self.events
+                .push(format!("visit {}", entry.relative.display()));
+            Ok(Visit::Continue)
+        }
+
+        fn walk_error(&mut self, _path: &Path, err: io::Error) -> io::Error {
+            err
+        }
+    }
+
+    #[test]
+    fn walker_visits_children_in_name_order_between_enter_and_leave() {
+        // ディレクトリの enter/leave の間に配下をファイル名順で訪問することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::write(root.join("c.txt"), "c").unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b").join("inner.txt"), "inner").unwrap();
+        let mut recorder = Recorder::default();
+
+        Walker::new(&root).run(&mut recorder).unwrap();
+
+        assert_eq!(
+            recorder.events,
+            [
+                "enter ",
+                "visit a.txt",
+                "enter b",
+                "visit b/inner.txt",
+                "leave b",
+                "visit c.txt",
+                "leave ",
+            ]
+        );
+    }
+
+    #[test]
+    fn walker_does_not_descend_into_skipped_directories() {
+        // Skip を返したディレクトリの配下は走査せず leave も呼ばないことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        fs::create_dir_all(root.join("skipped")).unwrap();
+        fs::write(root.join("skipped").join("hidden.txt"), "x").unwrap();
+        let mut recorder = Recorder {
+            skip: Some("skipped"),
+            ..Recorder::default()
+        };
+
+        Walker::new(&root).run(&mut recorder).unwrap();
+
+        assert_eq!(recorder.events, ["enter ", "enter skipped", "leave "]);
+    }
+
+    #[test]
+    fn walker_visits_non_directory_root() {
+        // ルートがファイルの場合はそのファイルのみを訪問することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("single.txt");
+        fs::write(&file, "x").unwrap();
+        let mut recorder = Recorder::default();
+
+        Walker::new(&file).run(&mut recorder).unwrap();
+
+        assert_eq!(recorder.events, ["visit "]);
+    }
+
+    /// 解決済みの位置と祖先の再訪を記録し、再訪したディレクトリは配下を走査しないビジター。
+    #[cfg(unix)]
+    #[derive(Default)]
+    struct Resolutions {
+        resolved: Vec<(String, PathBuf)>,
+        reentered: Vec<String>,
+    }
+
+    #[cfg(unix)]
+    impl Visitor for Resolutions {
+        type Error = io::Error;
+
+        fn enter_dir(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+            let name = entry.relative.display().to_string();
+            if entry.reenters_ancestor() {
+                self.reentered.push(name);
+                return Ok(Visit::Skip);
+            }
+            self.resolved.push((name, entry.resolved.to_path_buf()));
+            Ok(Visit::Continue)
+        }
+
+        fn visit(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+            let name = entry.relative.display().to_string();
+            self.resolved.push((name, entry.resolved.to_path_buf()));
+            Ok(Visit::Continue)
+        }
+
+        fn walk_error(&mut self, _path: &Path, err: io::Error) -> io::Error {
+            err
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walker_resolves_entries_from_parent_and_detects_reentered_ancestors() {
+        // 辿らないリンクはリンク自身の位置、辿ったリンクはリンク先へ解決し、
+        // 祖先へ戻るリンクを再訪として検出することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(root.join("child")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        fs::write(outside.join("file.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("child").join("back")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let canonical = temp_dir.path().canonicalize().unwrap();
+
+        let mut physical = Resolutions::default();
+        Walker::new(&root).run(&mut physical).unwrap();
+        assert_eq!(
+            physical.resolved,
+            [
+                (String::new(), canonical.join("root")),
+                ("child".to_string(), canonical.join("root/child")),
+                ("child/back".to_string(), canonical.join("root/child/back")),
+                ("link".to_string(), canonical.join("root/link")),
+            ]
+        );
+        assert!(physical.reentered.is_empty());
+
+        let mut logical = Resolutions::default();
+        Walker::new(&root)
+            .follow_links(true)
+            .run(&mut logical)
+            .unwrap();
+        assert_eq!(logical.reentered, ["child/back"]);
+        assert_eq!(
+            logical.resolved.last().unwrap(),
+            &(
+                "link/file.txt".to_string(),
+                canonical.join("outside/file.txt")
+            )
+        );
+    }
+
+    /// 最初のファイルを訪問した時点で親ディレクトリのパスをリンクへ差し替え、以降の内容を記録するビジター。
+    #[cfg(unix)]
+    struct Swapper {
+        root: PathBuf,
+        outside: PathBuf,
+        contents: Vec<String>,
+    }
+
+    #[cfg(unix)]
+    impl Visitor for Swapper {
+        type Error = io::Error;
+
+        fn enter_dir(&mut self, _entry: &Entry<'_>) -> io::Result<Visit> {
+            Ok(Visit::Continue)
+        }
+
+        fn visit(&mut self, entry: &Entry<'_>) -> io::Result<Visit> {
+            if self.contents.is_empty() {
+                fs::rename(self.root.join("a"), self.root.join("moved"))?;
+                std::os::unix::fs::symlink(&self.outside, self.root.join("a"))?;
+            }
+            self.contents.push(io::read_to_string(entry.open_file()?)?);
+            Ok(Visit::Continue)
+        }
+
+        fn walk_error(&mut self, _path: &Path, err: io::Error) -> io::Error {
+            err
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walker_reads_children_through_parent_descriptor_after_path_swap() {
+        // 走査中に親ディレクトリのパスを範囲外へのリンクへ差し替えても、
+        // 開いたディスクリプタから元のディレクトリの内容を読むことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        for name in ["1.txt", "2.txt"] {
+            fs::write(root.join("a").join(name), format!("inside {name}")).unwrap();
+            fs::write(outside.join(name), format!("outside {name}")).unwrap();
+        }
+        let mut swapper = Swapper {
+            root: root.clone(),
+            outside,
+            contents: Vec::new(),
+        };
+
+        Walker::new(&root).run(&mut swapper).unwrap();
+
+        assert_eq!(swapper.contents, ["inside 1.txt", "inside 2.txt"]);
+    }
+}
@@ -0,0 +1,405 @@
+use crate::dirfd::Dir;
+use crate::progress::Progress;
+use crate::walk::{Entry, Visit, child_of, directory_id, open_children, root_of};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 複数のワーカースレッドから同時に呼ばれる走査処理を定義する。
+pub trait SharedVisitor: Sync {
+    type Error: Send;
+
+    /// ディレクトリの配下を走査する前に呼ばれる。`Visit::Skip` を返すと配下を走査しない。
+    fn enter_dir(&self, entry: &Entry<'_>) -> Result<Visit, Self::Error>;
+
+    /// 走査全体の完了後、入ったディレクトリについて深い階層から順に呼ばれる。
+    ///
+    /// 開いたディスクリプタを走査の完了まで保持しないよう、`Entry::parent` は `None` となる。
+    fn leave_dir(&self, _entry: &Entry<'_>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// ディレクトリ以外のエントリごとに呼ばれる。
+    fn visit(&self, entry: &Entry<'_>) -> Result<Visit, Self::Error>;
+
+    /// メタデータ取得やディレクトリ読み込みの失敗をビジターのエラー型へ変換する。
+    fn walk_error(&self, path: &Path, err: io::Error) -> Self::Error;
+}
+
+/// ワークスティーリングのスレッドプールでツリーを並列に走査する。
+///
+/// ディレクトリは `enter_dir` の完了後にのみ配下をタスクとして投入するため、
+/// 親ディレクトリの処理は常に子より先に終わる。
+/// 各ディレクトリは逐次走査と同じく親のディスクリプタから開き、子タスクへ共有して引き渡す。
+/// エラーが発生しても他のエントリの処理は続け、走査完了後に相対パス順へ並べて返す。
+pub struct ParallelWalker<'p> {
+    root: PathBuf,
+    follow_root: bool,
+    follow_links: bool,
+    jobs: usize,
+    progress: Option<&'p Progress>,
+}
+
+/// 走査中のタスク間で共有する状態。
+struct Shared<'v, V: SharedVisitor> {
+    visitor: &'v V,
+    follow_links: bool,
+    progress: Option<&'v Progress>,
+    /// `enter_dir` が `Visit::Continue` を返したディレクトリ。
+    entered: Mutex<Vec<Task>>,
+    errors: Mutex<Vec<(PathBuf, V::Error)>>,
+}
+
+/// 1エントリ分の走査状態。祖先の列と親ディレクトリのディスクリプタは兄弟エントリ間で共有する。
+struct Task {
+    path: PathBuf,
+    relative: PathBuf,
+    metadata: fs::Metadata,
+    resolved: PathBuf,
+    ancestors: Arc<[(u64, u64)]>,
+    parent: Option<Arc<Dir>>,
+}
+
+impl<'p> ParallelWalker<'p> {
+    /// シンボリックリンクを辿らずに `root` を `jobs` 個のワーカーで走査する設定で生成する。
+    pub fn new(root: &Path, jobs: usize) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            follow_root: false,
+            follow_links: false,
+            jobs: jobs.max(1),
+            progress: None,
+        }
+    }
+
+    /// ルート自身がシンボリックリンクの場合にリンク先を走査するかを設定する。
+    pub fn follow_root(mut self, follow: bool) -> Self {
+        self.follow_root = follow;
+        self
+    }
+
+    /// 配下のシンボリックリンクを辿るかを設定する。循環の検出はビジター側で行う。
+    pub fn follow_links(mut self, follow: bool) -> Self {
+        self.follow_links = follow;
+        self
+    }
+
+    /// 処理したエントリを報告する進捗を設定する。
+    pub fn progress(mut self, progress: &'p Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// ルートから走査し、発生したすべてのエラーを相対パス順に返す。
+    pub fn run<V: SharedVisitor>(&self, visitor: &V) -> Result<(), Vec<V::Error>> {
+        let (metadata, resolved) = root_of(&self.root, self.follow_root)
+            .map_err(|e| vec![visitor.walk_error(&self.root, e)])?;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.jobs)
+            .build()
+            .map_err(|e| vec![visitor.walk_error(&self.root, io::Error::other(e))])?;
+        let shared = Shared {
+            visitor,
+            follow_links: self.follow_links,
+            progress: self.progress,
+            entered: Mutex::new(Vec::new()),
+            errors: Mutex::new(Vec::new()),
+        };
+
+        pool.scope(|scope| {
+            let root = Task {
+                path: self.root.clone(),
+                relative: PathBuf::new(),
+                metadata,
+                resolved,
+                ancestors: Arc::from([]),
+                parent: None,
+            };
+            shared.walk_entry(scope, root);
+        });
+
+        let Shared {
+            entered, errors, ..
+        } = shared;
+        let mut errors = errors
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut entered = entered
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // 子孫は祖先より相対パスが大きいため、降順に処理すると深い階層から順になる。
+        entered.sort_by(|a, b| b.relative.cmp(&a.relative));
+        for dir in &entered {
+            if let Err(e) = visitor.leave_dir(&dir.entry()) {
+                errors.push((dir.relative.clone(), e));
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+        errors.sort_by(|a, b| a.0.cmp(&b.0));
+        Err(errors.into_iter().map(|(_, e)| e).collect())
+    }
+}
+
+impl Task {
+    fn entry(&self) -> Entry<'_> {
+        Entry {
+            path: &self.path,
+            relative: &self.relative,
+            metadata: &self.metadata,
+            resolved: &self.resolved,
+            ancestors: &self.ancestors,
+            parent: self.parent.as_deref(),
+        }
+    }
+}
+
+impl<'v, V: SharedVisitor> Shared<'v, V> {
+    fn walk_entry<'s>(&'s self, scope: &rayon::Scope<'s>, task: Task) {
+        let entry = task.entry();
+
+        if !task.metadata.is_dir() {
+            match self.visitor.visit(&entry) {
+                Ok(Visit::Continue) => {
+                    if let Some(progress) = self.progress {
+                        progress.advance(&task.path, task.metadata.len());
+                    }
+                }
+                Ok(Visit::Skip) => {}
+                Err(e) => self.record_error(task.relative, e),
+            }
+            return;
+        }
+
+        match self.visitor.enter_dir(&entry) {
+            Ok(Visit::Continue) => {}
+            Ok(Visit::Skip) => return,
+            Err(e) => return self.record_error(task.relative, e),
+        }
+        let opened = open_children(&entry, self.follow_links);
+        let (dir, names) = match opened {
+            Ok((dir, names)) => (Arc::new(dir), names),
+            Err(e) => {
+                let error = self.visitor.walk_error(&task.path, e);
+                self.record_error(task.relative.clone(), error);
+                self.record_entered(task);
+                return;
+            }
+        };
+
+        let chain: Arc<[(u64, u64)]> = [&task.ancestors[..], &[directory_id(&task.metadata)]]
+            .concat()
+            .into();
+        for name in names {
+            let child = task.path.join(&name);
+            let child_relative = task.relative.join(&name);
+            match child_of(&dir, &name, &task.resolved, self.follow_links) {
+                Ok((child_metadata, child_resolved)) => {
+                    let child_task = Task {
+                        path: child,
+                        relative: child_relative,
+                        metadata: child_metadata,
+                        resolved: child_resolved,
+                        ancestors: Arc::clone(&chain),
+                        parent: Some(Arc::clone(&dir)),
+                    };
+                    scope.spawn(move |scope| self.walk_entry(scope, child_task));
+                }
+                Err(e) => {
+                    let error = self.visitor.walk_error(&child, e);
+                    self.record_error(child_relative, error);
+                }
+            }
+        }
+        self.record_entered(task);
+    }
+
+    fn record_entered(&self, mut dir: Task) {
+        dir.parent = None;
+        self.entered
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(dir);
+    }
+
+    fn record_error(&self, relative: PathBuf, error: V::Error) {
+        self.errors
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push((relative, error));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    /// 親ディレクトリの `enter_dir` が子より先に呼ばれたかを検査しながら数えるビジター。
+    #[derive(Default)]
+    struct OrderChecker {
+        entered: Mutex<Vec<PathBuf>>,
+        left: Mutex<Vec<PathBuf>>,
+        files: AtomicUsize,
+        fail_on: Option<&'static str>,
+    }
+
+    impl OrderChecker {
+        fn parent_entered(&self, entry: &Entry<'_>) -> bool {
+            let parent = entry.relative.parent().unwrap_or(Path::new(""));
+            self.entered.lock().unwrap().iter().any(|p| p == parent)
+        }
+    }
+
+    impl SharedVisitor for OrderChecker {
+        type Error = String;
+
+        fn enter_dir(&self, entry: &Entry<'_>) -> Result<Visit, String> {
+            if !entry.is_root() && !self.parent_entered(entry) {
+                return Err(format!(
+                    "entered before parent: {}",
+                    entry.relative.display()
+                ));
+            }
+            self.entered
+                .lock()
+                .unwrap()
+                .push(entry.relative.to_path_buf());
+            Ok(Visit::Continue)
+        }
+
+        fn leave_dir(&self, entry: &Entry<'_>) -> Result<(), String> {
+            self.left.lock().unwrap().push(entry.relative.to_path_buf());
+            Ok(())
+        }
+
+        fn visit(&self, entry: &Entry<'_>) -> Result<Visit, String> {
+            if !self.parent_entered(entry) {
+                return Err(format!(
+                    "visited before parent: {}",
+                    entry.relative.display()
+                ));
+            }
+            if self
+                .fail_on
+                .is_some_and(|name| entry.path.file_name().is_some_and(|n| n == name))
+            {
+                return Err(entry.relative.display().to_string());
+            }
+            self.files.fetch_add(1, Ordering::Relaxed);
+            Ok(Visit::Continue)
+        }
+
+        fn walk_error(&self, path: &Path, err: io::Error) -> String {
+            format!("{}: {err}", path.display())
+        }
+    }
+
+    fn build_tree(root: &Path) {
+        for dir in 0..4 {
+            let nested = root.join(format!("d{dir}")).join("inner");
+            fs::create_dir_all(&nested).unwrap();
+            for file in 0..5 {
+                fs::write(nested.join(format!("f{file}.txt")), "x").unwrap();
+            }
+            fs::write(root.join(format!("d{dir}")).join("bad.txt"), "x").unwrap();
+        }
+    }
+
+    #[test]
+    fn parallel_walker_enters_parents_before_children_and_leaves_deepest_first() {
+        // 親ディレクトリを子より先に処理し、leave_dir は深い階層から呼ぶことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        build_tree(&root);
+        let checker = OrderChecker::default();
+
+        ParallelWalker::new(&root, 4).run(&checker).unwrap();
+
+        assert_eq!(checker.files.load(Ordering::Relaxed), 24);
+        let left = checker.left.lock().unwrap();
+        assert_eq!(left.len(), 9);
+        assert_eq!(left.last().unwrap(), Path::new(""));
+        let position = |p: &str| left.iter().position(|l| l == Path::new(p)).unwrap();
+        assert!(position("d0/inner") < position("d0"));
+    }
+
+    #[test]
+    fn parallel_walker_reports_every_error_in_path_order() {
+        // エラーが発生しても走査を続け、すべてのエラーを相対パス順に返すことを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        build_tree(&root);
+        let checker = OrderChecker {
+            fail_on: Some("bad.txt"),
+            ..OrderChecker::default()
+        };
+
+        let errors = ParallelWalker::new(&root, 4).run(&checker).unwrap_err();
+
+        assert_eq!(
+            errors,
+            ["d0/bad.txt", "d1/bad.txt", "d2/bad.txt", "d3/bad.txt"]
+        );
+        assert_eq!(checker.files.load(Ordering::Relaxed), 20);
+    }
+
+    /// 祖先へ戻るリンクで配下を走査しないことを確認するための、再訪を数えるビジター。
+    #[cfg(unix)]
+    #[derive(Default)]
+    struct CycleCounter {
+        reentered: Mutex<Vec<PathBuf>>,
+        files: AtomicUsize,
+    }
+
+    #[cfg(unix)]
+    impl SharedVisitor for CycleCounter {
+        type Error = String;
+
+        fn enter_dir(&self, entry: &Entry<'_>) -> Result<Visit, String> {
+            if entry.reenters_ancestor() {
+                self.reentered
+                    .lock()
+                    .unwrap()
+                    .push(entry.relative.to_path_buf());
+                return Ok(Visit::Skip);
+            }
+            Ok(Visit::Continue)
+        }
+
+        fn visit(&self, _entry: &Entry<'_>) -> Result<Visit, String> {
+            self.files.fetch_add(1, Ordering::Relaxed);
+            Ok(Visit::Continue)
+        }
+
+        fn walk_error(&self, path: &Path, err: io::Error) -> String {
+            format!("{}: {err}", path.display())
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parallel_walker_carries_ancestors_into_child_tasks() {
+        // 子タスクへ引き渡した祖先の列で、リンクを辿った先の循環を検出することを確認する。
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        build_tree(&root);
+        std::os::unix::fs::symlink(&root, root.join("d0").join("inner").join("back")).unwrap();
+        let counter = CycleCounter::default();
+
+        ParallelWalker::new(&root, 4)
+            .follow_links(true)
+            .run(&counter)
+            .unwrap();
+
+        assert_eq!(
+            *counter.reentered.lock().unwrap(),
+            [PathBuf::from("d0/inner/back")]
+        );
+        assert_eq!(counter.files.load(Ordering::Relaxed), 24);
+    }
+}
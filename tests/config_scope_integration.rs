@@ -327,6 +327,95 @@ fn cp_recursive_no_clobber_denies_writes_via_symlink_under_destination() {
     );
 }
 
+#[cfg(target_os = "linux")]
+#[test]
+fn cp_never_writes_outside_scope_while_destination_parent_is_swapped_for_symlink() {
+    // 検証後にコピー先の親ディレクトリが許可範囲外へのシンボリックリンクへ差し替えられ続けても、
+    // 範囲外へ書き込まないことを確認する。
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::symlink;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let workspace_dir = temp_path.join("workspace");
+    let outside_dir = temp_path.join("outside");
+    let dest_dir = workspace_dir.join("dest");
+    fs::create_dir_all(workspace_dir.join("tree").join("nested")).unwrap();
+    fs::create_dir(&outside_dir).unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+    // 検証と書き込みの間で差し替えが起きやすいよう、エントリの多いツリーを用意する。
+    for index in 0..200 {
+        fs::write(
+            workspace_dir
+                .join("tree")
+                .join("nested")
+                .join(format!("leaf-{index}.txt")),
+            "leaf",
+        )
+        .unwrap();
+    }
+    let config_path = write_config(temp_path, &[]);
+
+    // 差し替え後も書き込みが成功するよう、範囲外にもコピー先と同じ構造を用意しておく。
+    for attempt in 0..40 {
+        fs::create_dir_all(outside_dir.join(format!("tree-{attempt}")).join("nested")).unwrap();
+    }
+    let decoy = workspace_dir.join("decoy");
+    symlink(&outside_dir, &decoy).unwrap();
+
+    // コピー先ディレクトリと範囲外へのシンボリックリンクを RENAME_EXCHANGE で入れ替え続ける。
+    let stop = Arc::new(AtomicBool::new(false));
+    let swapper = {
+        let stop = Arc::clone(&stop);
+        let dest = CString::new(dest_dir.as_os_str().as_bytes()).unwrap();
+        let decoy = CString::new(decoy.as_os_str().as_bytes()).unwrap();
+        std::thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                // SAFETY: 両パスは NUL 終端済みの絶対パス。
+                let result = unsafe {
+                    libc::renameat2(
+                        libc::AT_FDCWD,
+                        dest.as_ptr(),
+                        libc::AT_FDCWD,
+                        decoy.as_ptr(),
+                        libc::RENAME_EXCHANGE,
+                    )
+                };
+                assert_eq!(result, 0, "{}", std::io::Error::last_os_error());
+                std::thread::yield_now();
+            }
+        })
+    };
+
+    for attempt in 0..40 {
+        Command::new(assert_cmd::cargo::cargo_bin!("cp"))
+            .env("SAFECMD_CONFIG_PATH", &config_path)
+            .env("SAFECMD_DISABLE_TEST_MODE", "1")
+            .current_dir(&workspace_dir)
+            .arg("-r")
+            .arg("tree")
+            .arg(format!("dest/tree-{attempt}"))
+            .output()
+            .expect("run cp");
+    }
+    stop.store(true, Ordering::Relaxed);
+    swapper.join().unwrap();
+
+    let leaked: Vec<_> = (0..40)
+        .flat_map(|attempt| {
+            fs::read_dir(outside_dir.join(format!("tree-{attempt}")).join("nested")).unwrap()
+        })
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert!(
+        leaked.is_empty(),
+        "cp wrote outside the allowed scope: {leaked:?}"
+    );
+}
+
 #[test]
 fn rm_continues_after_creating_default_config() {
     // 設定ファイル未作成時に自動生成後そのまま処理継続できることを確認する。
@@ -363,7 +452,7 @@ paths = ["relative/path"]
     )
     .unwrap();
 
-    let mut cmd = Command::cargo_bin("rm").unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rm"));
     cmd.env("SAFECMD_CONFIG_PATH", &config_path)
         .env("SAFECMD_DISABLE_TEST_MODE", "1")
         .current_dir(temp_path)
@@ -412,3 +501,798 @@ fn rm_recursive_continues_when_one_path_is_outside_allowed_scope() {
     );
     assert!(forbidden_dir.exists(), "forbidden directory should remain");
 }
+
+#[test]
+fn cp_denies_current_directory_when_implicit_cwd_scope_disabled() {
+    // implicit_cwd_scope=false の場合はカレント配下への書き込みも拒否されることを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    let workspace_dir = temp_path.join("workspace");
+    let external_dir = temp_path.join("external");
+    fs::create_dir(&workspace_dir).unwrap();
+    fs::create_dir(&external_dir).unwrap();
+
+    let source_file = external_dir.join("source.txt");
+    fs::write(&source_file, "content").unwrap();
+
+    let config_path = temp_path.join("config.toml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"implicit_cwd_scope = false
+
+[additional_allowed_directories]
+paths = ["{}"]
+"#,
+            external_dir.display()
+        ),
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cp"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&workspace_dir)
+        .arg(&source_file)
+        .arg("copied.txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("path is outside allowed scope"));
+
+    assert!(!workspace_dir.join("copied.txt").exists());
+}
+
+#[test]
+fn cp_allows_repository_root_when_implicit_cwd_scope_is_git_root() {
+    // implicit_cwd_scope="git-root" の場合はサブディレクトリからリポジトリ直下へ書き込めることを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+
+    let repo_dir = temp_path.join("repo");
+    let subdir = repo_dir.join("sub");
+    fs::create_dir_all(repo_dir.join(".git")).unwrap();
+    fs::create_dir_all(&subdir).unwrap();
+    fs::write(subdir.join("source.txt"), "content").unwrap();
+
+    let config_path = temp_path.join("config.toml");
+    fs::write(
+        &config_path,
+        r#"implicit_cwd_scope = "git-root"
+
+[additional_allowed_directories]
+paths = []
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cp"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&subdir)
+        .arg("source.txt")
+        .arg("../copied.txt")
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(repo_dir.join("copied.txt")).unwrap(),
+        "content"
+    );
+}
+
+/// git コマンドでリポジトリを初期化する。git が無い環境ではスキップする旨を表示して `false` を返す。
+fn init_repository(repo: &std::path::Path) -> bool {
+    if Command::new("git").arg("--version").output().is_err() {
+        eprintln!("skipping: git command is not available");
+        return false;
+    }
+    assert!(git(repo, &["init", "-q"]), "git init failed");
+    true
+}
+
+/// テスト用リポジトリで git コマンドを実行し、成功したかを返す。
+fn git(repo: &std::path::Path, args: &[&str]) -> bool {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=safecmd",
+            "-c",
+            "user.email=safecmd@example.com",
+        ])
+        .args(["-c", "commit.gpgsign=false"])
+        .args(args)
+        .current_dir(repo)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .status();
+    matches!(status, Ok(status) if status.success())
+}
+
+/// `[git] protect_dirty = true` を有効にした設定ファイルを作成する。
+fn write_protect_dirty_config(temp_path: &std::path::Path) -> std::path::PathBuf {
+    let config_path = temp_path.join("config.toml");
+    fs::write(
+        &config_path,
+        r#"[additional_allowed_directories]
+paths = []
+
+[git]
+protect_dirty = true
+"#,
+    )
+    .unwrap();
+    config_path
+}
+
+#[test]
+fn rm_refuses_modified_file_when_protect_dirty_enabled() {
+    // protect_dirty 有効時は未コミット変更のあるファイルを削除せず、--allow-dirty で許可されることを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let repo_dir = temp_path.join("repo");
+    fs::create_dir(&repo_dir).unwrap();
+    if !init_repository(&repo_dir) {
+        return;
+    }
+    let tracked = repo_dir.join("notes.txt");
+    fs::write(&tracked, "committed").unwrap();
+    assert!(git(&repo_dir, &["add", "notes.txt"]));
+    assert!(git(&repo_dir, &["commit", "-q", "-m", "init"]));
+    fs::write(&tracked, "work in progress").unwrap();
+
+    let config_path = write_protect_dirty_config(temp_path);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rm"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&repo_dir)
+        .arg("notes.txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has uncommitted changes"))
+        .stderr(predicate::str::contains("--allow-dirty"));
+    assert!(tracked.exists(), "dirty file must not be trashed");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rm"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&repo_dir)
+        .arg("--allow-dirty")
+        .arg("notes.txt");
+    if !assert_rm_success_or_skip(&mut cmd) {
+        return;
+    }
+    assert!(!tracked.exists());
+}
+
+#[test]
+fn rm_recursive_refuses_directory_with_untracked_file_when_protect_dirty_enabled() {
+    // protect_dirty 有効時は未追跡ファイルを含むディレクトリの再帰削除を拒否することを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let repo_dir = temp_path.join("repo");
+    let work_dir = repo_dir.join("work");
+    fs::create_dir_all(&work_dir).unwrap();
+    if !init_repository(&repo_dir) {
+        return;
+    }
+    fs::write(work_dir.join("draft.txt"), "draft").unwrap();
+
+    let config_path = write_protect_dirty_config(temp_path);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rm"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&repo_dir)
+        .arg("-r")
+        .arg("work")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is untracked"));
+    assert!(work_dir.join("draft.txt").exists());
+}
+
+#[test]
+fn cp_refuses_overwriting_dirty_target_when_protect_dirty_enabled() {
+    // protect_dirty 有効時は未コミット変更のある既存ターゲットへの上書きを拒否することを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let repo_dir = temp_path.join("repo");
+    fs::create_dir(&repo_dir).unwrap();
+    if !init_repository(&repo_dir) {
+        return;
+    }
+    fs::write(repo_dir.join("source.txt"), "new").unwrap();
+    fs::write(repo_dir.join("target.txt"), "untracked work").unwrap();
+
+    let config_path = write_protect_dirty_config(temp_path);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cp"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&repo_dir)
+        .arg("source.txt")
+        .arg("target.txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is untracked"));
+    assert_eq!(
+        fs::read_to_string(repo_dir.join("target.txt")).unwrap(),
+        "untracked work"
+    );
+}
+
+#[test]
+fn mv_refuses_overwriting_dirty_target_when_protect_dirty_enabled() {
+    // protect_dirty 有効時は mv でも未コミット内容のある既存ターゲットを保護し、ソースを元に戻すことを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let repo_dir = temp_path.join("repo");
+    fs::create_dir(&repo_dir).unwrap();
+    if !init_repository(&repo_dir) {
+        return;
+    }
+    fs::write(repo_dir.join("source.txt"), "new").unwrap();
+    fs::write(repo_dir.join("target.txt"), "untracked work").unwrap();
+
+    let config_path = write_protect_dirty_config(temp_path);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("mv"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&repo_dir)
+        .arg("source.txt")
+        .arg("target.txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is untracked"));
+    assert_eq!(
+        fs::read_to_string(repo_dir.join("target.txt")).unwrap(),
+        "untracked work"
+    );
+    assert!(repo_dir.join("source.txt").exists());
+}
+
+/// `[limits] max_files` を設定した設定ファイルを作成する。
+fn write_max_files_config(temp_path: &std::path::Path, max_files: u64) -> std::path::PathBuf {
+    let config_path = temp_path.join("config.toml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"[additional_allowed_directories]
+paths = []
+
+[limits]
+max_files = {max_files}
+"#
+        ),
+    )
+    .unwrap();
+    config_path
+}
+
+/// 指定件数のファイルを含むディレクトリを作成する。
+fn create_tree_with_files(dir: &std::path::Path, count: usize) {
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    for i in 0..count {
+        let parent = if i % 2 == 0 {
+            dir.to_path_buf()
+        } else {
+            dir.join("nested")
+        };
+        fs::write(parent.join(format!("file{i}.txt")), "data").unwrap();
+    }
+}
+
+#[test]
+fn rm_recursive_refuses_tree_above_max_files_without_exact_confirmation() {
+    // max_files 超過時は削除を拒否し、件数が一致しない確認値も受け付けないことを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let work_dir = temp_path.join("work");
+    let target = work_dir.join("big");
+    create_tree_with_files(&target, 3);
+    let config_path = write_max_files_config(temp_path, 2);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rm"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("-r")
+        .arg("big")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds [limits] max_files = 2"))
+        .stderr(predicate::str::contains("--yes-i-mean-it=3"));
+    assert!(target.exists());
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rm"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("-r")
+        .arg("--yes-i-mean-it=2")
+        .arg("big")
+        .assert()
+        .failure();
+    assert!(target.exists());
+}
+
+#[test]
+fn rm_recursive_allows_tree_above_max_files_with_exact_confirmation() {
+    // --yes-i-mean-it に正確な件数を渡した場合は上限超過でも削除できることを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let work_dir = temp_path.join("work");
+    let target = work_dir.join("big");
+    create_tree_with_files(&target, 3);
+    let config_path = write_max_files_config(temp_path, 2);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rm"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("-r")
+        .arg("--yes-i-mean-it=3")
+        .arg("big");
+    if !assert_rm_success_or_skip(&mut cmd) {
+        return;
+    }
+    assert!(!target.exists());
+}
+
+#[test]
+fn rm_dry_run_reports_prescan_without_removing() {
+    // --dry-run は事前走査結果を表示するだけで削除しないことを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let work_dir = temp_path.join("work");
+    let target = work_dir.join("tree");
+    create_tree_with_files(&target, 3);
+    let config_path = write_config(temp_path, &[]);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rm"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("-r")
+        .arg("--dry-run")
+        .arg("tree")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "would remove 'tree' (3 files, 12 bytes)",
+        ));
+    assert!(target.exists());
+}
+
+#[test]
+fn rm_dry_run_reports_limit_excess_without_failing() {
+    // --dry-run は上限超過でも失敗せず、削除予定の集計と超過の旨を表示することを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let work_dir = temp_path.join("work");
+    let target = work_dir.join("big");
+    create_tree_with_files(&target, 3);
+    let config_path = write_max_files_config(temp_path, 2);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rm"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("-r")
+        .arg("--dry-run")
+        .arg("big")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "would remove 'big' (3 files, 12 bytes)",
+        ))
+        .stdout(predicate::str::contains(
+            "rm: would refuse to remove 'big': 3 files, 12 bytes exceeds [limits] max_files = 2",
+        ));
+    assert!(target.exists());
+}
+
+#[test]
+fn cp_recursive_refuses_trashing_target_above_max_files() {
+    // cp が既存ターゲットを退避する際も max_files 超過なら拒否することを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let work_dir = temp_path.join("work");
+    fs::create_dir_all(work_dir.join("source")).unwrap();
+    fs::write(work_dir.join("source").join("new.txt"), "new").unwrap();
+    let existing = work_dir.join("dest").join("source");
+    create_tree_with_files(&existing, 3);
+    let config_path = write_max_files_config(temp_path, 2);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cp"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("-r")
+        .arg("source")
+        .arg("dest")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("refusing to overwrite"))
+        .stderr(predicate::str::contains("--yes-i-mean-it=3"));
+    assert!(existing.join("file0.txt").exists());
+    assert!(!existing.join("new.txt").exists());
+}
+
+#[test]
+fn cp_update_counts_all_replaced_entries_against_max_files() {
+    // --update で既存ディレクトリへ書き込む場合、置き換えるエントリの合計で max_files を判定し、
+    // 何もコピーしないうちに拒否することを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let work_dir = temp_path.join("work");
+    let source = work_dir.join("source");
+    let existing = work_dir.join("dest").join("source");
+    create_tree_with_files(&source, 4);
+    create_tree_with_files(&existing, 4);
+    for (i, parent) in [(0, &source), (1, &source.join("nested")), (2, &source)] {
+        fs::write(parent.join(format!("file{i}.txt")), "changed").unwrap();
+    }
+    let config_path = write_max_files_config(temp_path, 2);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("cp"))
+        .env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("-r")
+        .arg("--update=content")
+        .arg("source")
+        .arg("dest")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("refusing to overwrite"))
+        .stderr(predicate::str::contains("--yes-i-mean-it=3"));
+    for (i, parent) in [
+        (0, &existing),
+        (1, &existing.join("nested")),
+        (2, &existing),
+    ] {
+        assert_eq!(
+            fs::read_to_string(parent.join(format!("file{i}.txt"))).unwrap(),
+            "data"
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn cp_recursive_scope_check_depends_on_dereference_policy() {
+    // -P ではリンク自身、-L ではリンク先を許可範囲チェックの対象にすることを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let work_dir = temp_path.join("work");
+    let outside_dir = temp_path.join("outside");
+    fs::create_dir_all(work_dir.join("tree")).unwrap();
+    fs::create_dir_all(&outside_dir).unwrap();
+    fs::write(outside_dir.join("secret.txt"), "secret").unwrap();
+    std::os::unix::fs::symlink(
+        outside_dir.join("secret.txt"),
+        work_dir.join("tree").join("secret-link"),
+    )
+    .unwrap();
+    let config_path = write_config(temp_path, &[]);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cp"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("-r")
+        .arg("tree")
+        .arg("as-links")
+        .assert()
+        .success();
+    let copied_link = work_dir.join("as-links").join("secret-link");
+    assert!(
+        fs::symlink_metadata(&copied_link)
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cp"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("-rL")
+        .arg("tree")
+        .arg("dereferenced")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("path is outside allowed scope"));
+    assert!(!work_dir.join("dereferenced").join("secret-link").exists());
+}
+
+#[test]
+fn cp_and_mv_keep_backups_in_directories_configured_for_backup() {
+    // [[backup.scopes]] 配下の上書きはゴミ箱ではなく番号付きバックアップを残し、他の場所は従来どおりであることを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let work_dir = temp_path.join("work");
+    let backed_up = work_dir.join("backed-up");
+    fs::create_dir_all(&backed_up).unwrap();
+    fs::write(work_dir.join("source.txt"), "first").unwrap();
+    fs::write(work_dir.join("moved.txt"), "second").unwrap();
+    fs::write(backed_up.join("target.txt"), "original").unwrap();
+
+    let config_path = temp_path.join("config.toml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"[additional_allowed_directories]
+paths = []
+
+[backup]
+control = "none"
+
+[[backup.scopes]]
+path = "{}"
+control = "numbered"
+"#,
+            backed_up.display()
+        ),
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cp"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("source.txt")
+        .arg("backed-up/target.txt")
+        .assert()
+        .success();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("mv"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&work_dir)
+        .arg("moved.txt")
+        .arg("backed-up/target.txt")
+        .assert()
+        .success();
+
+    let read = |name: &str| fs::read_to_string(backed_up.join(name)).unwrap();
+    assert_eq!(read("target.txt"), "second");
+    assert_eq!(read("target.txt.~1~"), "original");
+    assert_eq!(read("target.txt.~2~"), "first");
+}
+
+#[test]
+fn config_with_relative_backup_scope_path_is_rejected() {
+    // [[backup.scopes]] の path が相対パスの場合は設定エラーになることを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("source.txt"), "data").unwrap();
+    let config_path = temp_path.join("config.toml");
+    fs::write(
+        &config_path,
+        r#"[additional_allowed_directories]
+paths = []
+
+[[backup.scopes]]
+path = "relative"
+control = "simple"
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cp"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(temp_path)
+        .arg("source.txt")
+        .arg("copy.txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("backup.scopes[0].path"));
+}
+
+#[test]
+fn cp_symbolic_link_refuses_link_pointing_outside_scope() {
+    // cp -s はリンクの置き場所だけでなくリンク先も許可範囲内である場合のみ作成することを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let workspace_dir = temp_path.join("workspace");
+    let forbidden_dir = temp_path.join("forbidden");
+    fs::create_dir(&workspace_dir).unwrap();
+    fs::create_dir(&forbidden_dir).unwrap();
+    fs::write(forbidden_dir.join("secret.txt"), "secret").unwrap();
+    fs::write(workspace_dir.join("notes.txt"), "notes").unwrap();
+    let config_path = write_config(temp_path, &[]);
+
+    let cp = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cp"));
+        cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+            .env("SAFECMD_DISABLE_TEST_MODE", "1")
+            .current_dir(&workspace_dir)
+            .arg("-s");
+        cmd
+    };
+
+    cp().arg(forbidden_dir.join("secret.txt"))
+        .arg("secret-link")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("outside allowed scope"));
+    assert!(fs::symlink_metadata(workspace_dir.join("secret-link")).is_err());
+
+    cp().arg("notes.txt").arg("notes-link").assert().success();
+    assert_eq!(
+        fs::read_link(workspace_dir.join("notes-link")).unwrap(),
+        workspace_dir.canonicalize().unwrap().join("notes.txt")
+    );
+}
+
+#[test]
+fn ln_checks_link_location_and_optionally_symlink_target() {
+    // ln はリンクの置き場所を常に確認し、confine_symlink_targets 有効時のみリンク先も確認することを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let workspace_dir = temp_path.join("workspace");
+    let forbidden_dir = temp_path.join("forbidden");
+    fs::create_dir(&workspace_dir).unwrap();
+    fs::create_dir(&forbidden_dir).unwrap();
+    let config_path = write_config(temp_path, &[]);
+
+    let ln = |config_path: &std::path::Path| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ln"));
+        cmd.env("SAFECMD_CONFIG_PATH", config_path)
+            .env("SAFECMD_DISABLE_TEST_MODE", "1")
+            .current_dir(&workspace_dir)
+            .arg("-s");
+        cmd
+    };
+
+    ln(&config_path)
+        .arg("target")
+        .arg(forbidden_dir.join("link"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("path is outside allowed scope"));
+    assert!(fs::symlink_metadata(forbidden_dir.join("link")).is_err());
+
+    ln(&config_path)
+        .arg(&forbidden_dir)
+        .arg("escape")
+        .assert()
+        .success();
+
+    let mut confined = fs::read_to_string(&config_path).unwrap();
+    confined.push_str("\n[ln]\nconfine_symlink_targets = true\n");
+    fs::write(&config_path, confined).unwrap();
+    ln(&config_path)
+        .arg("../forbidden")
+        .arg("escape-again")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "link target is outside allowed scope",
+        ));
+    assert!(fs::symlink_metadata(workspace_dir.join("escape-again")).is_err());
+
+    ln(&config_path)
+        .arg("inside")
+        .arg("dangling")
+        .assert()
+        .success();
+}
+
+#[test]
+fn rmdir_parents_stops_at_scope_root() {
+    // rmdir -p は許可スコープのルートに達したら親ディレクトリを削除せずに停止することを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let scope_dir = temp_path.join("scope");
+    let nested = scope_dir.join("a/b");
+    fs::create_dir_all(&nested).unwrap();
+    let config_path = write_config(temp_path, std::slice::from_ref(&scope_dir));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rmdir"));
+    cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(temp_path.join(".config"))
+        .arg("-p")
+        .arg(&nested);
+    let Some(output) = run_rm_or_skip_for_trash(&mut cmd) else {
+        return;
+    };
+
+    assert!(
+        output.status.success(),
+        "rmdir failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!scope_dir.join("a").exists());
+    assert!(scope_dir.exists(), "scope root must not be removed");
+}
+
+#[test]
+fn rmdir_refuses_directory_outside_scope() {
+    // rmdir は許可範囲外の空ディレクトリを削除しないことを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let workspace_dir = temp_path.join("workspace");
+    let forbidden_dir = temp_path.join("forbidden");
+    fs::create_dir(&workspace_dir).unwrap();
+    fs::create_dir(&forbidden_dir).unwrap();
+    let config_path = write_config(temp_path, &[]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("rmdir"))
+        .env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&workspace_dir)
+        .arg(&forbidden_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("path is outside allowed scope"));
+    assert!(forbidden_dir.exists());
+}
+
+#[test]
+fn rmdir_refuses_scope_root_and_dot_operands() {
+    // rmdir はカレントディレクトリ（スコープのルート）自身や `.`/`..` のオペランドを削除しないことを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let workspace_dir = temp_path.join("workspace");
+    let nested_dir = workspace_dir.join("nested");
+    fs::create_dir_all(&nested_dir).unwrap();
+    let config_path = write_config(temp_path, &[]);
+
+    let rmdir = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rmdir"));
+        cmd.env("SAFECMD_CONFIG_PATH", &config_path)
+            .env("SAFECMD_DISABLE_TEST_MODE", "1")
+            .current_dir(&workspace_dir);
+        cmd
+    };
+
+    fs::remove_dir(&nested_dir).unwrap();
+    rmdir()
+        .arg(&workspace_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("root of an allowed scope"));
+    for operand in [".", "./", "nested/.."] {
+        fs::create_dir_all(&nested_dir).unwrap();
+        rmdir()
+            .arg(operand)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "rmdir: failed to remove '{operand}': Invalid argument"
+            )));
+    }
+    assert!(workspace_dir.exists());
+    assert!(nested_dir.exists());
+}
+
+#[test]
+fn write_refuses_path_outside_scope() {
+    // safecmd write は許可範囲外のファイルを置き換えないことを確認する。
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let workspace_dir = temp_path.join("workspace");
+    let forbidden_file = temp_path.join("forbidden.txt");
+    fs::create_dir(&workspace_dir).unwrap();
+    fs::write(&forbidden_file, "keep").unwrap();
+    let config_path = write_config(temp_path, &[]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("safecmd"))
+        .env("SAFECMD_CONFIG_PATH", &config_path)
+        .env("SAFECMD_DISABLE_TEST_MODE", "1")
+        .current_dir(&workspace_dir)
+        .arg("write")
+        .arg(&forbidden_file)
+        .stdin(std::process::Stdio::null())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("path is outside allowed scope"));
+    assert_eq!(fs::read_to_string(&forbidden_file).unwrap(), "keep");
+}
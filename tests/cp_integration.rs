@@ -1,5 +1,4 @@
 use assert_cmd::prelude::*;
-use predicates;
 use std::fs::{self, File};
 use std::io::Write;
 use std::process::Command;
@@ -81,6 +80,45 @@ fn overwrite_existing_file() {
     assert_eq!(content, "New content", "content was not overwritten");
 }
 
+#[cfg(target_os = "linux")]
+#[test]
+fn overwritten_target_is_trashed_under_its_original_path() {
+    // 上書きで退避した古いターゲットが、ゴミ箱に本来の場所と名前で記録されることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("source.txt");
+    let target_path = temp_dir.path().join("trashed-original-path.txt");
+    fs::write(&source_path, "New content").expect("write source file");
+    fs::write(&target_path, "Old content").expect("write target file");
+
+    let output = cp_command()
+        .arg(&source_path)
+        .arg(&target_path)
+        .output()
+        .expect("run cp");
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("failed to move existing file to trash") {
+            return;
+        }
+        panic!("cp failed unexpectedly: {stderr}");
+    }
+
+    assert_eq!(fs::read_to_string(&target_path).unwrap(), "New content");
+    let parent = temp_dir
+        .path()
+        .canonicalize()
+        .expect("canonicalize tmp dir");
+    let trashed: Vec<_> = trash::os_limited::list()
+        .expect("list trash")
+        .into_iter()
+        .filter(|item| item.original_parent == parent)
+        .collect();
+    let names: Vec<_> = trashed.iter().map(|item| item.name.clone()).collect();
+    let _ = trash::os_limited::purge_all(trashed);
+    assert_eq!(names, ["trashed-original-path.txt"]);
+}
+
 #[test]
 fn force_flag_is_accepted_for_overwrite() {
     // -f を指定しても通常の安全な上書きコピーが実行されることを確認する。
@@ -247,13 +285,13 @@ fn missing_arguments_fails() {
             "the following required arguments were not provided",
         ));
 
-    // 引数1つだけでも同様に clap の必須引数エラーになることを確認する。
+    // -t なしで引数1つだけの場合はコピー先がないエラーになることを確認する。
     cp_command()
         .arg("file.txt")
         .assert()
         .failure()
         .stderr(predicates::str::contains(
-            "2 values required by '<FILES> <FILES>...'; only 1 was provided",
+            "cp: missing destination file operand after 'file.txt'",
         ));
 }
 
@@ -656,3 +694,1078 @@ fn recursive_no_clobber_reports_type_conflict_with_existing_directory() {
         .assert()
         .failure();
 }
+
+#[test]
+fn recursive_copy_with_respect_gitignore_skips_ignored_entries() {
+    // --respect-gitignore 指定時は test_gitignore と同じ除外設定に一致するエントリをコピーしないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("project");
+    let target_dir = temp_dir.path().join("copied");
+    fs::create_dir_all(source_dir.join("build")).expect("create build dir");
+    fs::create_dir_all(source_dir.join("cache")).expect("create cache dir");
+    fs::create_dir_all(source_dir.join("src")).expect("create src dir");
+    fs::copy(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test_gitignore")
+            .join(".gitignore"),
+        source_dir.join(".gitignore"),
+    )
+    .expect("copy gitignore fixture");
+    fs::write(source_dir.join("build").join("output.bin"), "binary").expect("write build");
+    fs::write(source_dir.join("cache").join("entry"), "cached").expect("write cache");
+    fs::write(source_dir.join("src").join("main.rs"), "fn main() {}").expect("write src");
+
+    cp_command()
+        .arg("-r")
+        .arg("--respect-gitignore")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success();
+
+    assert!(target_dir.join(".gitignore").exists());
+    assert!(target_dir.join("src").join("main.rs").exists());
+    assert!(
+        !target_dir.join("build").exists(),
+        "ignored build directory should not be copied"
+    );
+    assert!(
+        !target_dir.join("cache").exists(),
+        "ignored cache directory should not be copied"
+    );
+}
+
+#[test]
+fn recursive_copy_applies_exclude_and_include_patterns() {
+    // --exclude で除外したエントリのうち --include に一致するものはコピーされることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("project");
+    let target_dir = temp_dir.path().join("copied");
+    fs::create_dir_all(source_dir.join("logs")).expect("create logs dir");
+    fs::write(source_dir.join("logs").join("debug.log"), "debug").expect("write debug");
+    fs::write(source_dir.join("logs").join("keep.log"), "keep").expect("write keep");
+    fs::write(source_dir.join("main.rs"), "fn main() {}").expect("write main");
+
+    cp_command()
+        .arg("-r")
+        .arg("--exclude")
+        .arg("*.log")
+        .arg("--include")
+        .arg("keep.log")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success();
+
+    assert!(target_dir.join("main.rs").exists());
+    assert!(target_dir.join("logs").join("keep.log").exists());
+    assert!(!target_dir.join("logs").join("debug.log").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn archive_preserves_mode_timestamps_symlinks_and_hardlinks() {
+    // -a でモード・ナノ秒精度の時刻・シンボリックリンク・ハードリンクを保持することを確認する。
+    use filetime::FileTime;
+    use std::os::unix::fs::{MetadataExt, PermissionsExt, symlink};
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("source");
+    let target_dir = temp_dir.path().join("archive");
+    fs::create_dir_all(source_dir.join("nested")).expect("create nested dir");
+    let script = source_dir.join("nested").join("run.sh");
+    fs::write(&script, "#!/bin/sh\n").expect("write script");
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o750)).expect("chmod script");
+    fs::hard_link(&script, source_dir.join("run-link.sh")).expect("create hardlink");
+    symlink("nested/run.sh", source_dir.join("latest")).expect("create symlink");
+
+    let file_time = FileTime::from_unix_time(1_600_000_000, 123_456_789);
+    let dir_time = FileTime::from_unix_time(1_500_000_000, 987_654_321);
+    filetime::set_file_times(&script, file_time, file_time).expect("set file times");
+    filetime::set_file_times(source_dir.join("nested"), dir_time, dir_time).expect("set dir times");
+
+    cp_command()
+        .arg("-a")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success();
+
+    let copied_script = target_dir.join("nested").join("run.sh");
+    let script_meta = fs::metadata(&copied_script).expect("stat copied script");
+    assert_eq!(script_meta.permissions().mode() & 0o777, 0o750);
+    assert_eq!(
+        FileTime::from_last_modification_time(&script_meta),
+        file_time
+    );
+
+    let dir_meta = fs::metadata(target_dir.join("nested")).expect("stat copied dir");
+    assert_eq!(
+        FileTime::from_last_modification_time(&dir_meta),
+        dir_time,
+        "directory mtime should be applied after its children are copied"
+    );
+
+    let link_meta = fs::symlink_metadata(target_dir.join("latest")).expect("stat copied link");
+    assert!(link_meta.file_type().is_symlink());
+    assert_eq!(
+        fs::read_link(target_dir.join("latest")).expect("read copied link"),
+        std::path::Path::new("nested/run.sh")
+    );
+
+    let linked_meta = fs::metadata(target_dir.join("run-link.sh")).expect("stat copied hardlink");
+    assert_eq!(linked_meta.ino(), script_meta.ino());
+}
+
+#[cfg(unix)]
+#[test]
+fn preserve_flag_keeps_only_requested_attributes() {
+    // --preserve=mode はモードのみ保持し、-p は時刻も保持することを確認する。
+    use filetime::FileTime;
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("source.txt");
+    fs::write(&source, "data").expect("write source");
+    fs::set_permissions(&source, fs::Permissions::from_mode(0o600)).expect("chmod source");
+    let source_time = FileTime::from_unix_time(1_400_000_000, 0);
+    filetime::set_file_times(&source, source_time, source_time).expect("set source times");
+
+    let mode_only = temp_dir.path().join("mode-only.txt");
+    cp_command()
+        .arg("--preserve=mode")
+        .arg(&source)
+        .arg(&mode_only)
+        .assert()
+        .success();
+    let mode_only_meta = fs::metadata(&mode_only).expect("stat mode-only copy");
+    assert_eq!(mode_only_meta.permissions().mode() & 0o777, 0o600);
+    assert_ne!(
+        FileTime::from_last_modification_time(&mode_only_meta),
+        source_time
+    );
+
+    let with_times = temp_dir.path().join("with-times.txt");
+    cp_command()
+        .arg("-p")
+        .arg(&source)
+        .arg(&with_times)
+        .assert()
+        .success();
+    let with_times_meta = fs::metadata(&with_times).expect("stat -p copy");
+    assert_eq!(
+        FileTime::from_last_modification_time(&with_times_meta),
+        source_time
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn preserve_xattr_copies_extended_attributes() {
+    // --preserve=xattr で拡張属性を複製することを確認する（非対応ファイルシステムではスキップ）。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("source.txt");
+    let target = temp_dir.path().join("target.txt");
+    fs::write(&source, "data").expect("write source");
+    if xattr::set(&source, "user.safecmd.test", b"kept").is_err() {
+        return;
+    }
+
+    cp_command()
+        .arg("--preserve=xattr")
+        .arg(&source)
+        .arg(&target)
+        .assert()
+        .success();
+
+    assert_eq!(
+        xattr::get(&target, "user.safecmd.test").expect("read xattr"),
+        Some(b"kept".to_vec())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn recursive_copy_keeps_symlinks_as_links_by_default() {
+    // -r の既定（-P）では配下のシンボリックリンクを辿らずリンクとして複製することを確認する。
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("source");
+    let target_dir = temp_dir.path().join("copied");
+    fs::create_dir_all(source_dir.join("data")).expect("create data dir");
+    fs::write(source_dir.join("data").join("file.txt"), "data").expect("write file");
+    symlink("data/file.txt", source_dir.join("file-link")).expect("create file link");
+    symlink("data", source_dir.join("dir-link")).expect("create dir link");
+    symlink("missing", source_dir.join("dangling")).expect("create dangling link");
+
+    cp_command()
+        .arg("-r")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success();
+
+    for name in ["file-link", "dir-link", "dangling"] {
+        let meta = fs::symlink_metadata(target_dir.join(name)).expect("stat copied link");
+        assert!(meta.file_type().is_symlink(), "{name} should stay a link");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn dereference_flag_copies_link_targets() {
+    // -L ではツリー内のリンクを辿り、リンク先の内容を複製することを確認する。
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("source");
+    let target_dir = temp_dir.path().join("copied");
+    fs::create_dir_all(source_dir.join("data")).expect("create data dir");
+    fs::write(source_dir.join("data").join("file.txt"), "data").expect("write file");
+    symlink("data/file.txt", source_dir.join("file-link")).expect("create file link");
+    symlink("data", source_dir.join("dir-link")).expect("create dir link");
+
+    cp_command()
+        .arg("-rL")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success();
+
+    let file_meta = fs::symlink_metadata(target_dir.join("file-link")).expect("stat file");
+    assert!(file_meta.file_type().is_file());
+    let dir_meta = fs::symlink_metadata(target_dir.join("dir-link")).expect("stat dir");
+    assert!(dir_meta.file_type().is_dir());
+    assert!(target_dir.join("dir-link").join("file.txt").is_file());
+}
+
+#[cfg(unix)]
+#[test]
+fn command_line_dereference_follows_only_arguments() {
+    // -H ではコマンドライン引数のリンクのみ辿り、配下のリンクはリンクのまま複製することを確認する。
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let real_dir = temp_dir.path().join("real");
+    fs::create_dir_all(&real_dir).expect("create real dir");
+    fs::write(real_dir.join("file.txt"), "data").expect("write file");
+    symlink("file.txt", real_dir.join("inner-link")).expect("create inner link");
+    let arg_link = temp_dir.path().join("arg-link");
+    symlink(&real_dir, &arg_link).expect("create argument link");
+    let target_dir = temp_dir.path().join("copied");
+
+    cp_command()
+        .arg("-rH")
+        .arg(&arg_link)
+        .arg(&target_dir)
+        .assert()
+        .success();
+
+    let target_meta = fs::symlink_metadata(&target_dir).expect("stat target");
+    assert!(target_meta.file_type().is_dir());
+    let inner_meta = fs::symlink_metadata(target_dir.join("inner-link")).expect("stat inner");
+    assert!(inner_meta.file_type().is_symlink());
+}
+
+#[cfg(unix)]
+#[test]
+fn dereference_flag_detects_symlink_cycles() {
+    // -L で祖先ディレクトリを指すリンクを辿った場合、循環としてエラーにすることを確認する。
+    use predicates::prelude::*;
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("source");
+    fs::create_dir_all(source_dir.join("child")).expect("create child dir");
+    symlink("..", source_dir.join("child").join("parent")).expect("create cycle link");
+    symlink("self", source_dir.join("self")).expect("create self link");
+
+    cp_command()
+        .arg("-rL")
+        .arg(&source_dir)
+        .arg(temp_dir.path().join("copied"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("symbolic link cycle detected"));
+}
+
+#[test]
+fn reflink_always_clones_or_reports_unsupported_filesystem() {
+    // --reflink=always は複製できる場合のみ成功し、非対応ファイルシステムでは明確なエラーを返すことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("source.bin");
+    let target = temp_dir.path().join("target.bin");
+    fs::write(&source, vec![7u8; 64 * 1024]).expect("write source");
+
+    let output = cp_command()
+        .arg("--reflink=always")
+        .arg(&source)
+        .arg(&target)
+        .output()
+        .expect("run cp");
+
+    if output.status.success() {
+        assert_eq!(
+            fs::read(&target).expect("read target"),
+            vec![7u8; 64 * 1024]
+        );
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("failed to clone"), "stderr: {stderr}");
+        assert!(!target.exists(), "failed clone should not leave a target");
+    }
+}
+
+#[test]
+fn reflink_never_copies_recursively() {
+    // --reflink=never でも再帰コピーが通常どおり完了することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("source");
+    let target_dir = temp_dir.path().join("copied");
+    fs::create_dir_all(source_dir.join("nested")).expect("create nested dir");
+    fs::write(source_dir.join("nested").join("data.txt"), "data").expect("write data");
+
+    cp_command()
+        .arg("-r")
+        .arg("--reflink=never")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(target_dir.join("nested").join("data.txt")).expect("read copy"),
+        "data"
+    );
+}
+
+#[test]
+fn sparse_copy_reports_logical_and_physical_bytes_as_json() {
+    // --json の出力に論理サイズと物理サイズが含まれ、穴あきファイルの穴が保たれることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("disk.img");
+    let target = temp_dir.path().join("copy.img");
+    let file = File::create(&source).expect("create source");
+    file.set_len(16 * 1024 * 1024).expect("extend source");
+    drop(file);
+
+    let output = cp_command()
+        .arg("--sparse=auto")
+        .arg("--json")
+        .arg(&source)
+        .arg(&target)
+        .output()
+        .expect("run cp");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let record: serde_json::Value =
+        serde_json::from_str(stdout.lines().next().expect("json line")).expect("parse json");
+    assert_eq!(record["kind"], "file");
+    assert_eq!(record["logical_bytes"], 16 * 1024 * 1024);
+    let physical = record["physical_bytes"].as_u64().expect("physical bytes");
+    assert!(physical < 16 * 1024 * 1024, "holes should be preserved");
+    assert_eq!(
+        fs::metadata(&target).expect("stat target").len(),
+        16 * 1024 * 1024
+    );
+}
+
+#[test]
+fn verbose_flag_prints_each_copied_entry() {
+    // -v でコピーしたディレクトリとファイルを GNU 形式で表示することを確認する。
+    use predicates::prelude::*;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("source");
+    let target_dir = temp_dir.path().join("copied");
+    fs::create_dir_all(&source_dir).expect("create source dir");
+    fs::write(source_dir.join("file.txt"), "12345").expect("write file");
+
+    cp_command()
+        .arg("-rv")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "'{}' -> '{}'",
+            source_dir.display(),
+            target_dir.display()
+        )))
+        .stdout(predicate::str::contains("file.txt' (5 bytes,"));
+}
+
+#[test]
+fn overwrite_leaves_no_temporary_files_in_destination_directory() {
+    // 既存ファイルの置き換え後、コピー先ディレクトリに一時ファイルやステージング領域が残らないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("source.txt");
+    let dest_dir = temp_dir.path().join("dest");
+    fs::create_dir(&dest_dir).expect("create dest dir");
+    let target = dest_dir.join("target.txt");
+    fs::write(&source, "new content").expect("write source");
+    fs::write(&target, "old content").expect("write target");
+
+    let output = cp_command()
+        .arg(&source)
+        .arg(&target)
+        .output()
+        .expect("run cp");
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("failed to move existing file to trash") {
+            return;
+        }
+        panic!("cp failed unexpectedly: {stderr}");
+    }
+
+    assert_eq!(fs::read_to_string(&target).unwrap(), "new content");
+    let names: Vec<_> = fs::read_dir(&dest_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, vec![std::ffi::OsString::from("target.txt")]);
+}
+
+#[test]
+fn failed_copy_keeps_existing_destination_intact() {
+    // コピーに失敗した場合、既存のコピー先は元の内容のまま残り一時ファイルも残らないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("source.bin");
+    let dest_dir = temp_dir.path().join("dest");
+    fs::create_dir(&dest_dir).expect("create dest dir");
+    let target = dest_dir.join("target.bin");
+    fs::write(&source, vec![1u8; 64 * 1024]).expect("write source");
+    fs::write(&target, "old content").expect("write target");
+
+    // reflink 非対応のファイルシステムでは --reflink=always のコピーが必ず失敗する。
+    let output = cp_command()
+        .arg("--reflink=always")
+        .arg(&source)
+        .arg(&target)
+        .output()
+        .expect("run cp");
+
+    if output.status.success() {
+        return;
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("failed to clone"), "stderr: {stderr}");
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old content");
+    assert_eq!(fs::read_dir(&dest_dir).unwrap().count(), 1);
+}
+
+#[test]
+fn verify_flag_reports_source_checksum_as_json() {
+    // --verify 指定時、コピー元のハッシュを JSON に含めて検証済みのコピーを作成することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("abc.txt");
+    let target = temp_dir.path().join("copy.txt");
+    fs::write(&source, "abc").expect("write source");
+
+    let output = cp_command()
+        .arg("--verify=sha256")
+        .arg("--json")
+        .arg(&source)
+        .arg(&target)
+        .output()
+        .expect("run cp");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let record: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("parse json record");
+    assert_eq!(record["checksum"]["algorithm"], "sha256");
+    assert_eq!(
+        record["checksum"]["hex"],
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(fs::read_to_string(&target).unwrap(), "abc");
+}
+
+#[test]
+fn verify_flag_defaults_to_blake3_in_verbose_output() {
+    // 値なしの --verify は blake3 を使い、-v の出力にハッシュを含めることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("src");
+    fs::create_dir(&source_dir).expect("create source dir");
+    fs::write(source_dir.join("abc.txt"), "abc").expect("write source");
+
+    let output = cp_command()
+        .arg("-r")
+        .arg("--verify")
+        .arg("-v")
+        .arg(&source_dir)
+        .arg(temp_dir.path().join("dst"))
+        .output()
+        .expect("run cp");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout
+            .contains("[blake3:6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85]"),
+        "stdout: {stdout}"
+    );
+}
+
+/// 標準エラー出力に含まれる進捗レコードを JSON として読み取る。
+fn progress_records(stderr: &[u8]) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(stderr)
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+#[test]
+fn progress_flag_emits_records_with_prescanned_totals_when_not_a_terminal() {
+    // 非端末の --progress は事前走査の総量を含む start と、全件完了の done レコードを出力することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("src");
+    fs::create_dir_all(source_dir.join("nested")).expect("create source dirs");
+    fs::write(source_dir.join("a.txt"), "12345").expect("write a");
+    fs::write(source_dir.join("nested").join("b.txt"), "123").expect("write b");
+    let single = temp_dir.path().join("single.txt");
+    fs::write(&single, "12").expect("write single");
+    let target_dir = temp_dir.path().join("dst");
+    fs::create_dir(&target_dir).expect("create target dir");
+
+    let output = cp_command()
+        .arg("-r")
+        .arg("--progress")
+        .arg(&source_dir)
+        .arg(&single)
+        .arg(&target_dir)
+        .output()
+        .expect("run cp");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let records = progress_records(&output.stderr);
+    let start = records.first().expect("start record");
+    assert_eq!(start["event"], "start");
+    assert_eq!(start["command"], "cp");
+    assert_eq!(start["files_total"], 3);
+    assert_eq!(start["bytes_total"], 10);
+    let done = records.last().expect("done record");
+    assert_eq!(done["event"], "done");
+    assert_eq!(done["files_done"], 3);
+    assert_eq!(done["bytes_done"], 10);
+}
+
+#[test]
+fn jobs_flag_copies_tree_in_parallel_with_same_result() {
+    // --jobs で並列コピーした場合も、逐次コピーと同じ構造・内容になることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("src");
+    for dir in 0..6 {
+        let nested = source_dir.join(format!("d{dir}")).join("inner");
+        fs::create_dir_all(&nested).expect("create nested dir");
+        for file in 0..10 {
+            fs::write(nested.join(format!("f{file}.txt")), format!("{dir}-{file}"))
+                .expect("write file");
+        }
+    }
+    let parallel = temp_dir.path().join("parallel");
+    let sequential = temp_dir.path().join("sequential");
+
+    cp_command()
+        .arg("-r")
+        .arg("--jobs")
+        .arg("4")
+        .arg(&source_dir)
+        .arg(&parallel)
+        .assert()
+        .success();
+    cp_command()
+        .arg("-r")
+        .arg(&source_dir)
+        .arg(&sequential)
+        .assert()
+        .success();
+
+    for dir in 0..6 {
+        for file in 0..10 {
+            let relative = std::path::Path::new(&format!("d{dir}"))
+                .join("inner")
+                .join(format!("f{file}.txt"));
+            assert_eq!(
+                fs::read_to_string(parallel.join(&relative)).expect("read parallel copy"),
+                fs::read_to_string(sequential.join(&relative)).expect("read sequential copy")
+            );
+        }
+    }
+}
+
+#[test]
+fn jobs_flag_reports_all_errors_in_path_order() {
+    // 並列コピーでは失敗したエントリをすべてパス順に報告し、他のエントリのコピーは続けることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("src");
+    let target_dir = temp_dir.path().join("dst");
+    for name in ["b", "a", "c"] {
+        let dir = source_dir.join(name);
+        fs::create_dir_all(&dir).expect("create dir");
+        fs::write(dir.join("ok.txt"), name).expect("write ok");
+        fs::write(dir.join("conflict.txt"), name).expect("write conflict");
+        // -n でも型衝突はエラーになるため、コピー先に同名ディレクトリを置く。
+        fs::create_dir_all(target_dir.join("src").join(name).join("conflict.txt"))
+            .expect("create conflicting dir");
+    }
+
+    let output = cp_command()
+        .arg("-rn")
+        .arg("--jobs=3")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .output()
+        .expect("run cp");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let failed: Vec<_> = stderr
+        .lines()
+        .filter_map(|line| {
+            ["a", "b", "c"]
+                .into_iter()
+                .find(|name| line.contains(&format!("/{name}/conflict.txt")))
+        })
+        .collect();
+    assert_eq!(failed, ["a", "b", "c"], "stderr: {stderr}");
+    for name in ["a", "b", "c"] {
+        assert_eq!(
+            fs::read_to_string(target_dir.join("src").join(name).join("ok.txt")).expect("read ok"),
+            name
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn jobs_flag_detects_symlink_cycles_with_dereference() {
+    // 並列コピーでも -L で祖先を指すリンクを循環として報告することを確認する。
+    use predicates::prelude::*;
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("source");
+    fs::create_dir_all(source_dir.join("child")).expect("create child dir");
+    symlink("..", source_dir.join("child").join("parent")).expect("create cycle link");
+
+    cp_command()
+        .arg("-rL")
+        .arg("--jobs=2")
+        .arg(&source_dir)
+        .arg(temp_dir.path().join("copied"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("symbolic link cycle detected"));
+}
+
+#[test]
+fn backup_numbered_keeps_each_overwritten_version() {
+    // --backup=numbered で上書きのたびに file.~N~ が増え、ゴミ箱を使わないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("source.txt");
+    let target_path = temp_dir.path().join("target.txt");
+    fs::write(&target_path, b"v0").expect("write target");
+
+    for version in ["v1", "v2"] {
+        fs::write(&source_path, version).expect("write source");
+        cp_command()
+            .arg("--backup=numbered")
+            .arg(&source_path)
+            .arg(&target_path)
+            .assert()
+            .success();
+    }
+
+    let read = |name: &str| fs::read_to_string(temp_dir.path().join(name)).expect("read file");
+    assert_eq!(read("target.txt"), "v2");
+    assert_eq!(read("target.txt.~1~"), "v0");
+    assert_eq!(read("target.txt.~2~"), "v1");
+}
+
+#[test]
+fn backup_short_flag_with_suffix_keeps_simple_backup() {
+    // -b -S で指定した接尾辞の単純バックアップが作られることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("source.txt");
+    let target_path = temp_dir.path().join("target.txt");
+    fs::write(&source_path, b"new").expect("write source");
+    fs::write(&target_path, b"old").expect("write target");
+
+    cp_command()
+        .arg("-b")
+        .arg("-S")
+        .arg(".bak")
+        .arg(&source_path)
+        .arg(&target_path)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&target_path).expect("read target"),
+        "new"
+    );
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("target.txt.bak")).expect("read backup"),
+        "old"
+    );
+}
+
+#[test]
+fn backup_recursive_keeps_replaced_directory_beside_target() {
+    // 再帰コピーで置き換える既存ディレクトリがバックアップ名で残ることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("tree");
+    let copy_root = temp_dir.path().join("copy");
+    let target_dir = copy_root.join("tree");
+    fs::create_dir(&source_dir).expect("create source");
+    fs::create_dir_all(&target_dir).expect("create target");
+    fs::write(source_dir.join("new.txt"), b"new").expect("write source file");
+    fs::write(target_dir.join("old.txt"), b"old").expect("write target file");
+
+    cp_command()
+        .arg("-r")
+        .arg("--backup=simple")
+        .arg(&source_dir)
+        .arg(&copy_root)
+        .assert()
+        .success();
+
+    assert!(target_dir.join("new.txt").exists());
+    assert!(!target_dir.join("old.txt").exists());
+    assert_eq!(
+        fs::read_to_string(copy_root.join("tree~").join("old.txt")).expect("read backup"),
+        "old"
+    );
+}
+
+#[test]
+fn update_older_skips_target_newer_than_source() {
+    // -u でソースより新しい既存ターゲットを置き換えず、-v にスキップ理由を出すことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("source.txt");
+    let target_path = temp_dir.path().join("target.txt");
+    fs::write(&source_path, b"old source").expect("write source");
+    fs::write(&target_path, b"newer target").expect("write target");
+    File::options()
+        .write(true)
+        .open(&source_path)
+        .expect("open source")
+        .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(60))
+        .expect("set source mtime");
+
+    cp_command()
+        .arg("-u")
+        .arg("-v")
+        .arg(&source_path)
+        .arg(&target_path)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("(destination is not older)"));
+
+    assert_eq!(
+        fs::read_to_string(&target_path).expect("read target"),
+        "newer target"
+    );
+}
+
+#[test]
+fn update_content_skips_identical_files_inside_recursive_copy() {
+    // 再帰コピーの --update=content で内容が同じファイルをスキップし、既存ディレクトリへ追加コピーすることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("tree");
+    let copy_root = temp_dir.path().join("copy");
+    let target_dir = copy_root.join("tree");
+    fs::create_dir(&source_dir).expect("create source");
+    fs::create_dir_all(&target_dir).expect("create target");
+    fs::write(source_dir.join("same.txt"), b"same").expect("write same");
+    fs::write(source_dir.join("added.txt"), b"added").expect("write added");
+    fs::write(target_dir.join("same.txt"), b"same").expect("write target same");
+    fs::write(target_dir.join("kept.txt"), b"kept").expect("write target only");
+
+    let output = cp_command()
+        .arg("-r")
+        .arg("--update=content")
+        .arg("--json")
+        .arg(&source_dir)
+        .arg(&copy_root)
+        .output()
+        .expect("run cp");
+    assert!(output.status.success(), "cp failed: {output:?}");
+
+    let records: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("parse json"))
+        .collect();
+    let skipped: Vec<_> = records
+        .iter()
+        .filter(|record| record.get("skipped").is_some())
+        .collect();
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0]["skipped"], "identical");
+    assert!(
+        skipped[0]["source"]
+            .as_str()
+            .expect("source")
+            .ends_with("same.txt")
+    );
+    assert_eq!(
+        fs::read_to_string(target_dir.join("added.txt")).expect("read added"),
+        "added"
+    );
+    assert!(target_dir.join("kept.txt").exists());
+}
+
+#[test]
+fn target_directory_flag_takes_sources_last() {
+    // -t で指定したディレクトリへ、後ろに並べたすべてのソースをコピーすることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let dest_dir = temp_dir.path().join("dest");
+    let source1 = temp_dir.path().join("a.txt");
+    let source2 = temp_dir.path().join("b.txt");
+    fs::create_dir(&dest_dir).expect("create dest");
+    fs::write(&source1, b"a").expect("write a");
+    fs::write(&source2, b"b").expect("write b");
+
+    cp_command()
+        .arg("-t")
+        .arg(&dest_dir)
+        .arg(&source1)
+        .arg(&source2)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("a.txt")).expect("read a"),
+        "a"
+    );
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("b.txt")).expect("read b"),
+        "b"
+    );
+}
+
+#[test]
+fn no_target_directory_copies_directory_onto_missing_path() {
+    // -T で既存ディレクトリの中ではなく、指定したパスそのものへコピーし、余分なオペランドを拒否することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("tree");
+    let target_dir = temp_dir.path().join("copy");
+    fs::create_dir(&source_dir).expect("create source");
+    fs::write(source_dir.join("file.txt"), b"data").expect("write file");
+
+    cp_command()
+        .arg("-rT")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success();
+    assert!(target_dir.join("file.txt").exists());
+    assert!(!target_dir.join("tree").exists());
+
+    cp_command()
+        .arg("-T")
+        .arg(source_dir.join("file.txt"))
+        .arg(&target_dir)
+        .arg(temp_dir.path().join("extra"))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("cp: extra operand"));
+}
+
+#[test]
+fn trailing_slash_on_missing_target_rejects_file_source() {
+    // 存在しないコピー先の末尾に `/` がある場合、ファイルはコピーせずエラーにすることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("a.txt");
+    fs::write(&source_path, b"a").expect("write source");
+    let target = format!("{}/", temp_dir.path().join("missing").display());
+
+    cp_command()
+        .arg(&source_path)
+        .arg(&target)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("is not a directory"));
+
+    assert!(!temp_dir.path().join("missing").exists());
+}
+
+#[test]
+fn parents_recreates_source_layout_under_target() {
+    // --parents でソースの相対パス構造をコピー先ディレクトリの下に再現し、-v に作成したディレクトリを出すことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    fs::create_dir_all(temp_dir.path().join("src/a")).expect("create src/a");
+    fs::create_dir(temp_dir.path().join("tests")).expect("create tests");
+    fs::create_dir(temp_dir.path().join("scratch")).expect("create scratch");
+    fs::write(temp_dir.path().join("src/a/b.rs"), b"b").expect("write b.rs");
+    fs::write(temp_dir.path().join("tests/x.rs"), b"x").expect("write x.rs");
+
+    cp_command()
+        .current_dir(temp_dir.path())
+        .arg("--parents")
+        .arg("-v")
+        .arg("src/a/b.rs")
+        .arg("tests/x.rs")
+        .arg("scratch")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("'src/a' -> 'scratch/src/a'"));
+
+    let scratch = temp_dir.path().join("scratch");
+    assert_eq!(
+        fs::read_to_string(scratch.join("src/a/b.rs")).expect("read b.rs"),
+        "b"
+    );
+    assert_eq!(
+        fs::read_to_string(scratch.join("tests/x.rs")).expect("read x.rs"),
+        "x"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn parents_with_preserve_copies_directory_mode() {
+    // --parents と -p の併用で、作成した中間ディレクトリへコピー元ディレクトリのモードを適用することを確認する。
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("conf");
+    fs::create_dir(&source_dir).expect("create conf");
+    fs::create_dir(temp_dir.path().join("out")).expect("create out");
+    fs::write(source_dir.join("app.toml"), b"x").expect("write file");
+    fs::set_permissions(&source_dir, fs::Permissions::from_mode(0o750)).expect("chmod conf");
+
+    cp_command()
+        .current_dir(temp_dir.path())
+        .arg("--parents")
+        .arg("-p")
+        .arg("conf/app.toml")
+        .arg("out")
+        .assert()
+        .success();
+
+    let mode = fs::metadata(temp_dir.path().join("out/conf"))
+        .expect("stat copied dir")
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o750);
+}
+
+#[test]
+fn parents_requires_directory_target() {
+    // --parents でコピー先がディレクトリでない場合はエラーにすることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("a.txt");
+    fs::write(&source_path, b"a").expect("write source");
+
+    cp_command()
+        .arg("--parents")
+        .arg(&source_path)
+        .arg(temp_dir.path().join("missing"))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "with --parents, the destination must be a directory",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn link_flag_hard_links_files_in_recursive_copy() {
+    // -l で再帰コピーのファイルを内容のコピーではなくハードリンクとして作成することを確認する。
+    use std::os::unix::fs::MetadataExt;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("tree");
+    let target_dir = temp_dir.path().join("sandbox");
+    fs::create_dir_all(source_dir.join("sub")).expect("create source");
+    fs::write(source_dir.join("sub/file.txt"), b"data").expect("write file");
+
+    cp_command()
+        .arg("-rl")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success();
+
+    let source_meta = fs::metadata(source_dir.join("sub/file.txt")).expect("stat source");
+    let target_meta = fs::metadata(target_dir.join("sub/file.txt")).expect("stat target");
+    assert_eq!(source_meta.ino(), target_meta.ino());
+    assert!(
+        fs::symlink_metadata(target_dir.join("sub"))
+            .expect("stat sub")
+            .is_dir()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn link_flag_keeps_overwritten_target_as_backup() {
+    // -l でも既存ターゲットは通常のコピーと同じく退避してから置き換えることを確認する。
+    use std::os::unix::fs::MetadataExt;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("source.txt");
+    let target_path = temp_dir.path().join("target.txt");
+    fs::write(&source_path, b"new").expect("write source");
+    fs::write(&target_path, b"old").expect("write target");
+
+    cp_command()
+        .arg("-l")
+        .arg("--backup=simple")
+        .arg(&source_path)
+        .arg(&target_path)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::metadata(&target_path).expect("stat target").ino(),
+        fs::metadata(&source_path).expect("stat source").ino()
+    );
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("target.txt~")).expect("read backup"),
+        "old"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn symbolic_link_flag_links_to_absolute_source_path() {
+    // -s で相対パスのソースでも、コピー元の絶対パスを指すシンボリックリンクを作成することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    fs::write(temp_dir.path().join("a.txt"), b"a").expect("write source");
+    fs::create_dir(temp_dir.path().join("links")).expect("create links");
+
+    cp_command()
+        .current_dir(temp_dir.path())
+        .arg("-s")
+        .arg("-v")
+        .arg("a.txt")
+        .arg("links")
+        .assert()
+        .success();
+
+    let link = temp_dir.path().join("links/a.txt");
+    assert_eq!(
+        fs::read_link(&link).expect("read link"),
+        temp_dir
+            .path()
+            .canonicalize()
+            .expect("canonical")
+            .join("a.txt")
+    );
+    assert_eq!(fs::read_to_string(&link).expect("read through link"), "a");
+}
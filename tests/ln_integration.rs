@@ -0,0 +1,204 @@
+#![cfg(unix)]
+
+use assert_cmd::prelude::*;
+use std::fs;
+use std::os::unix::fs::{MetadataExt, symlink};
+use std::process::Command;
+use tempfile::tempdir;
+
+/// ln バイナリ実行時に明示テストモードを付与したコマンドを生成する。
+fn ln_command() -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ln"));
+    cmd.env("SAFECMD_TEST_MODE", "1");
+    cmd
+}
+
+#[test]
+fn hard_link_shares_inode_with_source() {
+    // オプションなしではハードリンクを作成することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("a.txt");
+    let link = temp_dir.path().join("b.txt");
+    fs::write(&source, b"a").expect("write source");
+
+    ln_command().arg(&source).arg(&link).assert().success();
+
+    assert_eq!(
+        fs::metadata(&source).expect("stat source").ino(),
+        fs::metadata(&link).expect("stat link").ino()
+    );
+}
+
+#[test]
+fn hard_link_to_directory_fails() {
+    // ディレクトリへのハードリンクは拒否することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("dir");
+    fs::create_dir(&source).expect("create dir");
+
+    ln_command()
+        .arg(&source)
+        .arg(temp_dir.path().join("link"))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "hard link not allowed for directory",
+        ));
+}
+
+#[test]
+fn symbolic_link_keeps_source_text() {
+    // -s ではリンク元の文字列をそのままリンク内容とし、-v で作成したリンクを表示することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let link = temp_dir.path().join("link");
+
+    ln_command()
+        .arg("-sv")
+        .arg("missing/target")
+        .arg(&link)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("-> 'missing/target'"));
+
+    assert_eq!(
+        fs::read_link(&link).expect("read link"),
+        std::path::Path::new("missing/target")
+    );
+}
+
+#[test]
+fn existing_link_name_fails_without_force() {
+    // -f なしでは既存のリンク名を置き換えずに失敗することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let link = temp_dir.path().join("link");
+    fs::write(&link, b"keep").expect("write existing");
+
+    ln_command()
+        .arg("-s")
+        .arg("target")
+        .arg(&link)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("File exists"));
+
+    assert_eq!(fs::read_to_string(&link).expect("read existing"), "keep");
+}
+
+#[test]
+fn force_replaces_existing_file_after_trashing_it() {
+    // -sf で既存ファイルをゴミ箱へ移動してからシンボリックリンクへ置き換えることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let link = temp_dir.path().join("link");
+    fs::write(&link, b"old").expect("write existing");
+
+    let output = ln_command()
+        .arg("-sf")
+        .arg("target")
+        .arg(&link)
+        .output()
+        .expect("run ln");
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("failed to move existing file to trash") {
+            return;
+        }
+        panic!("ln failed unexpectedly: {stderr}");
+    }
+
+    assert_eq!(
+        fs::read_link(&link).expect("read link"),
+        std::path::Path::new("target")
+    );
+}
+
+#[test]
+fn force_refuses_to_replace_directory() {
+    // -T -f でもリンク名の既存ディレクトリは置き換えないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let dir = temp_dir.path().join("dir");
+    fs::create_dir(&dir).expect("create dir");
+
+    ln_command()
+        .arg("-sfT")
+        .arg("target")
+        .arg(&dir)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("cannot overwrite directory"));
+
+    assert!(fs::symlink_metadata(&dir).expect("stat dir").is_dir());
+}
+
+#[test]
+fn relative_flag_links_relative_to_link_location() {
+    // -r でリンクの置き場所から見た相対パスをリンク内容にすることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    fs::create_dir_all(temp_dir.path().join("src")).expect("create src");
+    fs::create_dir_all(temp_dir.path().join("out/links")).expect("create out");
+    fs::write(temp_dir.path().join("src/lib.rs"), b"lib").expect("write source");
+
+    ln_command()
+        .current_dir(temp_dir.path())
+        .arg("-sr")
+        .arg("src/lib.rs")
+        .arg("out/links/lib.rs")
+        .assert()
+        .success();
+
+    let link = temp_dir.path().join("out/links/lib.rs");
+    assert_eq!(
+        fs::read_link(&link).expect("read link"),
+        std::path::Path::new("../../src/lib.rs")
+    );
+    assert_eq!(fs::read_to_string(&link).expect("read through link"), "lib");
+}
+
+#[test]
+fn no_dereference_treats_symlink_to_directory_as_file() {
+    // -n ではディレクトリへのシンボリックリンクの中へ作成せず、リンク名そのものとして扱うことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let dir = temp_dir.path().join("dir");
+    let link = temp_dir.path().join("current");
+    fs::create_dir(&dir).expect("create dir");
+    symlink(&dir, &link).expect("create link");
+
+    ln_command()
+        .arg("-sn")
+        .arg("other")
+        .arg(&link)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("File exists"));
+    assert!(!dir.join("other").exists());
+
+    ln_command()
+        .arg("-s")
+        .arg("other")
+        .arg(&link)
+        .assert()
+        .success();
+    assert!(fs::symlink_metadata(dir.join("other")).is_ok());
+}
+
+#[test]
+fn target_directory_creates_links_for_every_operand() {
+    // -t で指定ディレクトリへ各リンク元と同じ名前のリンクを作成することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let links = temp_dir.path().join("links");
+    fs::create_dir(&links).expect("create links");
+
+    ln_command()
+        .arg("-s")
+        .arg("-t")
+        .arg(&links)
+        .arg("/etc/hosts")
+        .arg("/etc/passwd")
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_link(links.join("hosts")).expect("read hosts"),
+        std::path::Path::new("/etc/hosts")
+    );
+    assert!(fs::symlink_metadata(links.join("passwd")).is_ok());
+}
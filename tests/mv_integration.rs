@@ -285,3 +285,587 @@ fn directory_move_over_existing_non_empty_directory_fails() {
     assert!(source_dir.exists(), "source dir should remain on failure");
     assert!(existing_file.exists(), "existing file should remain");
 }
+
+#[cfg(unix)]
+#[test]
+fn cross_device_move_with_verify_copies_tree_then_trashes_source() {
+    // 別デバイスへの移動では検証付きで複製してからソースをゴミ箱へ移動することを確認する。
+    use std::os::unix::fs::MetadataExt;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let Ok(other_device) = tempfile::tempdir_in("/dev/shm") else {
+        return;
+    };
+    let source_dev = fs::metadata(temp_dir.path()).expect("stat tmp").dev();
+    if fs::metadata(other_device.path()).expect("stat shm").dev() == source_dev {
+        return;
+    }
+    let source_dir = temp_dir.path().join("dataset");
+    fs::create_dir_all(source_dir.join("nested")).expect("create source tree");
+    fs::write(
+        source_dir.join("nested").join("part.bin"),
+        vec![5u8; 100_000],
+    )
+    .expect("write source file");
+    symlink("nested/part.bin", source_dir.join("latest")).expect("create symlink");
+    let target_dir = other_device.path().join("dataset");
+
+    let output = mv_command()
+        .arg("--verify=sha256")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .output()
+        .expect("run mv");
+
+    assert_eq!(
+        fs::read(target_dir.join("nested").join("part.bin")).expect("read moved file"),
+        vec![5u8; 100_000]
+    );
+    assert_eq!(
+        fs::read_link(target_dir.join("latest")).expect("read moved link"),
+        std::path::Path::new("nested/part.bin")
+    );
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("failed to move source to trash") {
+            return;
+        }
+        panic!("mv failed unexpectedly: {stderr}");
+    }
+    assert!(!source_dir.exists(), "source should be trashed after copy");
+    assert_eq!(
+        fs::read_dir(other_device.path())
+            .expect("list target")
+            .count(),
+        1,
+        "staging path should not remain"
+    );
+}
+
+#[test]
+fn progress_flag_reports_renamed_tree_totals() {
+    // rename で移動したツリーは事前走査の総量をまとめて done に反映することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("dataset");
+    fs::create_dir_all(source_dir.join("nested")).expect("create source tree");
+    fs::write(source_dir.join("a.txt"), "123").expect("write a");
+    fs::write(source_dir.join("nested").join("b.txt"), "4567").expect("write b");
+    let target_dir = temp_dir.path().join("moved");
+
+    let output = mv_command()
+        .arg("--progress")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .output()
+        .expect("run mv");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    let records: Vec<serde_json::Value> = stderr
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    assert_eq!(records.first().unwrap()["event"], "start");
+    assert_eq!(records.first().unwrap()["files_total"], 2);
+    assert_eq!(records.last().unwrap()["event"], "done");
+    assert_eq!(records.last().unwrap()["files_done"], 2);
+    assert_eq!(records.last().unwrap()["bytes_done"], 7);
+    assert!(target_dir.join("nested").join("b.txt").exists());
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn exchange_swaps_directory_and_file() {
+    // --exchange でディレクトリとファイルが入れ替わり、どちらもゴミ箱へ送られないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let dir_path = temp_dir.path().join("tree");
+    let file_path = temp_dir.path().join("note.txt");
+    fs::create_dir(&dir_path).expect("create dir");
+    fs::write(dir_path.join("inner.txt"), b"inner").expect("write inner file");
+    fs::write(&file_path, b"file content").expect("write file");
+
+    let output = mv_command()
+        .arg("--exchange")
+        .arg(&dir_path)
+        .arg(&file_path)
+        .output()
+        .expect("run mv");
+    if !output.status.success()
+        && String::from_utf8_lossy(&output.stderr).contains("atomic exchange is not supported")
+    {
+        return;
+    }
+
+    assert!(output.status.success(), "mv --exchange failed: {output:?}");
+    assert!(file_path.is_dir(), "directory was not moved to target path");
+    assert_eq!(
+        fs::read_to_string(file_path.join("inner.txt")).expect("read inner file"),
+        "inner"
+    );
+    assert_eq!(
+        fs::read_to_string(&dir_path).expect("read swapped file"),
+        "file content"
+    );
+}
+
+#[test]
+fn exchange_requires_existing_target() {
+    // --exchange で移動先が存在しない場合はエラーになり、ソースが残ることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("source.txt");
+    let target_path = temp_dir.path().join("missing.txt");
+    fs::write(&source_path, b"content").expect("write source");
+
+    mv_command()
+        .arg("--exchange")
+        .arg(&source_path)
+        .arg(&target_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot exchange"));
+
+    assert!(source_path.exists(), "source was moved");
+    assert!(!target_path.exists(), "target was created");
+}
+
+#[test]
+fn exchange_conflicts_with_no_clobber() {
+    // --exchange と -n を同時に指定すると引数エラーになることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("a.txt");
+    let target_path = temp_dir.path().join("b.txt");
+    fs::write(&source_path, b"a").expect("write source");
+    fs::write(&target_path, b"b").expect("write target");
+
+    mv_command()
+        .arg("--exchange")
+        .arg("-n")
+        .arg(&source_path)
+        .arg(&target_path)
+        .assert()
+        .failure();
+
+    assert_eq!(fs::read_to_string(&source_path).expect("read a"), "a");
+    assert_eq!(fs::read_to_string(&target_path).expect("read b"), "b");
+}
+
+#[test]
+fn merge_moves_tree_into_non_empty_directory() {
+    // --merge で衝突しないエントリが移り、衝突ファイルは置き換えられ、空になったソースが消えることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("src");
+    let parent = temp_dir.path().join("dst");
+    let target = parent.join("src");
+    fs::create_dir_all(source.join("shared/new")).expect("create source tree");
+    fs::create_dir_all(target.join("shared")).expect("create target tree");
+    fs::write(source.join("only-source.txt"), b"source").expect("write source file");
+    fs::write(source.join("shared/conflict.txt"), b"new").expect("write conflict source");
+    fs::write(source.join("shared/new/leaf.txt"), b"leaf").expect("write leaf");
+    fs::write(target.join("only-target.txt"), b"target").expect("write target file");
+    fs::write(target.join("shared/conflict.txt"), b"old").expect("write conflict target");
+
+    let output = mv_command()
+        .arg("--merge")
+        .arg(&source)
+        .arg(&parent)
+        .output()
+        .expect("run mv");
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("Error during a `trash` operation")
+            || stderr.contains("failed to move existing file to trash")
+        {
+            return;
+        }
+    }
+
+    assert!(output.status.success(), "mv --merge failed: {output:?}");
+    assert!(!source.exists(), "emptied source directory was not removed");
+    let read = |path: &str| fs::read_to_string(target.join(path)).expect("read merged file");
+    assert_eq!(read("only-source.txt"), "source");
+    assert_eq!(read("only-target.txt"), "target");
+    assert_eq!(read("shared/conflict.txt"), "new");
+    assert_eq!(read("shared/new/leaf.txt"), "leaf");
+}
+
+#[test]
+fn merge_with_no_clobber_keeps_conflicting_files_in_source() {
+    // --merge -n で衝突ファイルは移動せずソースに残し、他のエントリは移すことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("src");
+    let parent = temp_dir.path().join("dst");
+    let target = parent.join("src");
+    fs::create_dir(&source).expect("create source");
+    fs::create_dir_all(&target).expect("create target");
+    fs::write(source.join("conflict.txt"), b"new").expect("write conflict source");
+    fs::write(source.join("fresh.txt"), b"fresh").expect("write fresh source");
+    fs::write(target.join("conflict.txt"), b"old").expect("write conflict target");
+
+    mv_command()
+        .arg("--merge")
+        .arg("-n")
+        .arg(&source)
+        .arg(&parent)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(target.join("conflict.txt")).expect("read target conflict"),
+        "old"
+    );
+    assert_eq!(
+        fs::read_to_string(source.join("conflict.txt")).expect("read source conflict"),
+        "new"
+    );
+    assert_eq!(
+        fs::read_to_string(target.join("fresh.txt")).expect("read fresh"),
+        "fresh"
+    );
+    assert!(
+        !source.join("fresh.txt").exists(),
+        "fresh file was not moved"
+    );
+}
+
+#[test]
+fn merge_resumes_entry_left_in_staging_by_interrupted_run() {
+    // 中断で移動先に残ったマーカー付きの一時退避ディレクトリから、再実行時に元の名前で配置することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("src");
+    let parent = temp_dir.path().join("dst");
+    let target = parent.join("src");
+    let staging = target.join(".safecmd-mv-merge.1-0");
+    fs::create_dir(&source).expect("create source");
+    fs::create_dir_all(staging.join("entries")).expect("create staging");
+    fs::write(staging.join("marker"), b"").expect("write marker");
+    fs::write(source.join("rest.txt"), b"rest").expect("write remaining source");
+    fs::write(staging.join("entries/staged.txt"), b"staged").expect("write staged entry");
+
+    mv_command()
+        .arg("--merge")
+        .arg(&source)
+        .arg(&parent)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(target.join("staged.txt")).expect("read recovered entry"),
+        "staged"
+    );
+    assert!(!staging.exists(), "staging directory was not removed");
+    assert_eq!(
+        fs::read_to_string(target.join("rest.txt")).expect("read moved entry"),
+        "rest"
+    );
+    assert!(!source.exists(), "emptied source directory was not removed");
+}
+
+#[test]
+fn merge_leaves_user_entries_with_staging_prefix_alone() {
+    // 一時退避先と同じ接頭辞でもマーカーのない利用者のエントリは片付けの対象にせず、
+    // マージ後に一時退避ディレクトリを残さないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("src");
+    let parent = temp_dir.path().join("dst");
+    let target = parent.join("src");
+    fs::create_dir(&source).expect("create source");
+    fs::create_dir_all(target.join(".safecmd-mv-merge.notes")).expect("create user dir");
+    fs::write(target.join(".safecmd-mv-merge-draft.txt"), b"draft").expect("write user file");
+    fs::write(source.join("moved.txt"), b"moved").expect("write source file");
+
+    mv_command()
+        .arg("--merge")
+        .arg(&source)
+        .arg(&parent)
+        .assert()
+        .success();
+
+    assert!(target.join(".safecmd-mv-merge.notes").is_dir());
+    assert_eq!(
+        fs::read_to_string(target.join(".safecmd-mv-merge-draft.txt")).expect("read user file"),
+        "draft"
+    );
+    assert!(!target.join("draft.txt").exists());
+    let mut names: Vec<_> = fs::read_dir(&target)
+        .expect("read target")
+        .map(|entry| entry.expect("entry").file_name())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            ".safecmd-mv-merge-draft.txt",
+            ".safecmd-mv-merge.notes",
+            "moved.txt"
+        ]
+    );
+}
+
+#[test]
+fn merge_moves_entries_with_maximum_length_names() {
+    // 255 バイトの名前のエントリも一時退避の名前の長さに影響されずマージできることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("src");
+    let parent = temp_dir.path().join("dst");
+    let target = parent.join("src");
+    let long_name = "n".repeat(255);
+    fs::create_dir(&source).expect("create source");
+    fs::create_dir_all(&target).expect("create target");
+    fs::write(source.join(&long_name), b"long").expect("write long-named file");
+    fs::write(target.join("kept.txt"), b"kept").expect("write target file");
+
+    mv_command()
+        .arg("--merge")
+        .arg(&source)
+        .arg(&parent)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(target.join(&long_name)).expect("read long-named file"),
+        "long"
+    );
+    assert!(!source.exists(), "emptied source directory was not removed");
+}
+
+#[test]
+fn directory_move_over_non_empty_directory_without_merge_still_fails() {
+    // --merge なしでは従来どおり非空ディレクトリへの移動がエラーになることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("src");
+    let target = temp_dir.path().join("dst");
+    fs::create_dir_all(source.join("inner")).expect("create source");
+    fs::create_dir_all(target.join("src/existing")).expect("create target");
+
+    mv_command()
+        .arg(&source)
+        .arg(&target)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Directory not empty"));
+
+    assert!(source.join("inner").exists(), "source was modified");
+}
+
+#[test]
+fn merge_into_own_subdirectory_fails() {
+    // ソース自身の配下へのマージはエラーになり、何も移動しないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source = temp_dir.path().join("src");
+    fs::create_dir_all(source.join("src")).expect("create nested dir");
+    fs::write(source.join("file.txt"), b"data").expect("write file");
+
+    mv_command()
+        .arg("--merge")
+        .arg(&source)
+        .arg(&source)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("subdirectory of itself"));
+
+    assert!(source.join("file.txt").exists(), "source entry was moved");
+}
+
+#[test]
+fn backup_simple_keeps_overwritten_target_beside_destination() {
+    // --backup=simple で上書きされたターゲットが target~ として残ることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("source.txt");
+    let target_path = temp_dir.path().join("target.txt");
+    fs::write(&source_path, b"new").expect("write source");
+    fs::write(&target_path, b"old").expect("write target");
+
+    mv_command()
+        .arg("--backup=simple")
+        .arg(&source_path)
+        .arg(&target_path)
+        .assert()
+        .success();
+
+    assert!(!source_path.exists(), "source still exists");
+    assert_eq!(
+        fs::read_to_string(&target_path).expect("read target"),
+        "new"
+    );
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("target.txt~")).expect("read backup"),
+        "old"
+    );
+}
+
+#[test]
+fn backup_conflicts_with_no_clobber() {
+    // --backup と -n を同時に指定すると引数エラーになることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("a.txt");
+    let target_path = temp_dir.path().join("b.txt");
+    fs::write(&source_path, b"a").expect("write source");
+
+    mv_command()
+        .arg("--backup")
+        .arg("-n")
+        .arg(&source_path)
+        .arg(&target_path)
+        .assert()
+        .failure();
+
+    assert!(source_path.exists(), "source was moved");
+}
+
+#[test]
+fn update_older_keeps_newer_target_and_source() {
+    // -u でソースより新しい既存ターゲットがあれば移動せず、ソースも残すことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("source.txt");
+    let target_path = temp_dir.path().join("target.txt");
+    fs::write(&source_path, b"old source").expect("write source");
+    fs::write(&target_path, b"newer target").expect("write target");
+    File::options()
+        .write(true)
+        .open(&source_path)
+        .expect("open source")
+        .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(60))
+        .expect("set source mtime");
+
+    mv_command()
+        .arg("-u")
+        .arg(&source_path)
+        .arg(&target_path)
+        .assert()
+        .success();
+
+    assert!(source_path.exists(), "source was moved");
+    assert_eq!(
+        fs::read_to_string(&target_path).expect("read target"),
+        "newer target"
+    );
+}
+
+#[test]
+fn verbose_update_reports_skipped_and_moved_sources() {
+    // -v 指定時は --update でスキップしたソースを理由付きで、移動したソースを renamed として表示することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let dest_dir = temp_dir.path().join("dest");
+    let same = temp_dir.path().join("same.txt");
+    let fresh = temp_dir.path().join("fresh.txt");
+    fs::create_dir(&dest_dir).expect("create dest");
+    fs::write(&same, b"same").expect("write same");
+    fs::write(dest_dir.join("same.txt"), b"same").expect("write existing same");
+    fs::write(&fresh, b"fresh").expect("write fresh");
+
+    let output = mv_command()
+        .arg("-v")
+        .arg("--update=content")
+        .arg(&same)
+        .arg(&fresh)
+        .arg(&dest_dir)
+        .output()
+        .expect("run mv");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!(
+            "skipped '{}' -> '{}' (content is identical)",
+            same.display(),
+            dest_dir.join("same.txt").display()
+        )),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!(
+            "renamed '{}' -> '{}'",
+            fresh.display(),
+            dest_dir.join("fresh.txt").display()
+        )),
+        "{stdout}"
+    );
+    assert!(same.exists(), "skipped source should stay in place");
+}
+
+#[test]
+fn target_directory_flag_moves_every_operand() {
+    // -t 指定時はすべてのオペランドをソースとして指定ディレクトリへ移動することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let dest_dir = temp_dir.path().join("dest");
+    let source1 = temp_dir.path().join("a.txt");
+    let source2 = temp_dir.path().join("b.txt");
+    fs::create_dir(&dest_dir).expect("create dest");
+    fs::write(&source1, b"a").expect("write a");
+    fs::write(&source2, b"b").expect("write b");
+
+    mv_command()
+        .arg("--target-directory")
+        .arg(&dest_dir)
+        .arg(&source1)
+        .arg(&source2)
+        .assert()
+        .success();
+
+    assert!(
+        !source1.exists() && !source2.exists(),
+        "sources still exist"
+    );
+    assert!(dest_dir.join("a.txt").exists());
+    assert!(dest_dir.join("b.txt").exists());
+}
+
+#[test]
+fn no_target_directory_does_not_move_into_existing_directory() {
+    // -T 指定時は既存ディレクトリの中へ移動せず、ディレクトリ自体を置き換え対象として扱うことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_dir = temp_dir.path().join("src");
+    let target_dir = temp_dir.path().join("dst");
+    fs::create_dir(&source_dir).expect("create source");
+    fs::create_dir(&target_dir).expect("create target");
+    fs::write(source_dir.join("file.txt"), b"data").expect("write file");
+
+    mv_command()
+        .arg("-T")
+        .arg(&source_dir)
+        .arg(&target_dir)
+        .assert()
+        .success();
+
+    assert!(!source_dir.exists(), "source still exists");
+    assert!(target_dir.join("file.txt").exists());
+    assert!(!target_dir.join("src").exists());
+}
+
+#[test]
+fn trailing_slash_target_must_be_directory() {
+    // `mv a b/` は b がディレクトリでなければ失敗し、ディレクトリのソースなら b へ移動することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let source_path = temp_dir.path().join("a.txt");
+    let existing_file = temp_dir.path().join("b");
+    fs::write(&source_path, b"a").expect("write source");
+    fs::write(&existing_file, b"b").expect("write target");
+
+    for target in [&existing_file, &temp_dir.path().join("missing")] {
+        mv_command()
+            .arg(&source_path)
+            .arg(format!("{}/", target.display()))
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("is not a directory"));
+    }
+    assert!(source_path.exists(), "source was moved");
+    assert_eq!(
+        fs::read_to_string(&existing_file).expect("read target"),
+        "b"
+    );
+
+    let source_dir = temp_dir.path().join("dir");
+    let renamed = temp_dir.path().join("renamed");
+    fs::create_dir(&source_dir).expect("create source dir");
+    mv_command()
+        .arg(&source_dir)
+        .arg(format!("{}/", renamed.display()))
+        .assert()
+        .success();
+    assert!(renamed.is_dir());
+}
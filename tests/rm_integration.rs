@@ -1,5 +1,4 @@
 use assert_cmd::prelude::*;
-use predicates;
 use std::fs::{self, File};
 use std::process::Command;
 use tempfile::tempdir;
@@ -44,6 +43,38 @@ fn file_is_trashed() {
     assert!(!file_path.exists(), "file still exists at original path");
 }
 
+#[cfg(target_os = "linux")]
+#[test]
+fn trashed_file_keeps_its_original_path() {
+    // ステージングディレクトリを経由して移動しても、ゴミ箱には本来の場所と名前が記録されることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let file_path = temp_dir.path().join("original-path.txt");
+    File::create(&file_path).expect("create file");
+
+    let mut cmd = rm_command();
+    cmd.arg(&file_path);
+    if !assert_rm_success_or_skip(&mut cmd) {
+        return;
+    }
+
+    let parent = temp_dir
+        .path()
+        .canonicalize()
+        .expect("canonicalize tmp dir");
+    let trashed: Vec<_> = trash::os_limited::list()
+        .expect("list trash")
+        .into_iter()
+        .filter(|item| item.original_parent.starts_with(&parent))
+        .collect();
+    let entries: Vec<_> = trashed
+        .iter()
+        .map(|item| (item.original_parent.clone(), item.name.clone()))
+        .collect();
+    let _ = trash::os_limited::purge_all(trashed);
+    assert_eq!(entries, [(parent, "original-path.txt".into())]);
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
 #[test]
 fn directory_without_flags_fails() {
     // create a directory
@@ -310,3 +341,36 @@ fn combined_flags_frd() {
         "directory was not removed with -frd"
     );
 }
+
+#[test]
+fn progress_flag_reports_trashed_tree_totals() {
+    // --progress は事前走査したツリーの総量を start に、削除後の件数を done に出力することを確認する。
+    let dir = tempdir().expect("create tmp dir");
+    let tree = dir.path().join("tree");
+    fs::create_dir_all(tree.join("nested")).expect("create tree");
+    fs::write(tree.join("a.txt"), "1234").expect("write a");
+    fs::write(tree.join("nested").join("b.txt"), "12").expect("write b");
+
+    let output = rm_command()
+        .arg("-r")
+        .arg("--progress")
+        .arg(&tree)
+        .output()
+        .expect("run rm");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() && stderr.contains("Error during a `trash` operation") {
+        return;
+    }
+
+    assert!(output.status.success(), "stderr: {stderr}");
+    let records: Vec<serde_json::Value> = stderr
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    assert_eq!(records.first().unwrap()["event"], "start");
+    assert_eq!(records.first().unwrap()["files_total"], 2);
+    assert_eq!(records.last().unwrap()["event"], "done");
+    assert_eq!(records.last().unwrap()["files_done"], 2);
+    assert_eq!(records.last().unwrap()["bytes_done"], 6);
+    assert!(!tree.exists());
+}
@@ -0,0 +1,129 @@
+use assert_cmd::prelude::*;
+use std::fs::{self, File};
+use std::process::Command;
+use tempfile::tempdir;
+
+/// rmdir バイナリ実行時に明示テストモードを付与したコマンドを生成する。
+fn rmdir_command() -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("rmdir"));
+    cmd.env("SAFECMD_TEST_MODE", "1");
+    cmd
+}
+
+/// rmdir 実行結果を確認し、trash が使えない環境では成功系テストをスキップ扱いにする。
+fn assert_rmdir_success_or_skip(cmd: &mut Command) -> bool {
+    let output = cmd.output().expect("run rmdir");
+    if output.status.success() {
+        return true;
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("Error during a `trash` operation") {
+        return false;
+    }
+
+    panic!("rmdir failed unexpectedly: {stderr}");
+}
+
+#[test]
+fn empty_directory_is_trashed() {
+    // 空ディレクトリをゴミ箱へ移動し、-v で処理したディレクトリを表示することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let dir = temp_dir.path().join("empty");
+    fs::create_dir(&dir).expect("create dir");
+
+    let output = rmdir_command()
+        .arg("-v")
+        .arg(&dir)
+        .output()
+        .expect("run rmdir");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("Error during a `trash` operation") {
+        return;
+    }
+    assert!(output.status.success(), "rmdir failed: {stderr}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("removing directory"));
+    assert!(!dir.exists());
+}
+
+#[test]
+fn non_empty_directory_fails() {
+    // 空でないディレクトリは削除せずエラーにすることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let dir = temp_dir.path().join("full");
+    fs::create_dir(&dir).expect("create dir");
+    File::create(dir.join("file.txt")).expect("create file");
+
+    rmdir_command()
+        .arg(&dir)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Directory not empty"));
+    assert!(dir.join("file.txt").exists());
+}
+
+#[test]
+fn ignore_fail_on_non_empty_succeeds_without_removing() {
+    // --ignore-fail-on-non-empty では空でないディレクトリを残したまま成功扱いにすることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let dir = temp_dir.path().join("full");
+    fs::create_dir(&dir).expect("create dir");
+    File::create(dir.join("file.txt")).expect("create file");
+
+    rmdir_command()
+        .arg("--ignore-fail-on-non-empty")
+        .arg(&dir)
+        .assert()
+        .success();
+    assert!(dir.join("file.txt").exists());
+}
+
+#[test]
+fn file_operand_fails_as_not_a_directory() {
+    // ディレクトリ以外のオペランドは Not a directory で拒否することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let file = temp_dir.path().join("file.txt");
+    File::create(&file).expect("create file");
+
+    rmdir_command()
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Not a directory"));
+    assert!(file.exists());
+}
+
+#[test]
+fn parents_flag_removes_empty_ancestors_named_in_operand() {
+    // -p でオペランドに含まれる空の親ディレクトリまで削除することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    fs::create_dir_all(temp_dir.path().join("a/b/c")).expect("create dirs");
+
+    let mut cmd = rmdir_command();
+    cmd.current_dir(temp_dir.path()).arg("-p").arg("a/b/c");
+    if !assert_rmdir_success_or_skip(&mut cmd) {
+        return;
+    }
+
+    assert!(!temp_dir.path().join("a").exists());
+}
+
+#[test]
+fn parents_flag_stops_quietly_at_non_empty_ancestor_with_ignore_fail() {
+    // -p と --ignore-fail-on-non-empty では空でない親ディレクトリで停止し、成功扱いにすることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    fs::create_dir_all(temp_dir.path().join("a/b")).expect("create dirs");
+    File::create(temp_dir.path().join("a/keep.txt")).expect("create file");
+
+    let mut cmd = rmdir_command();
+    cmd.current_dir(temp_dir.path())
+        .arg("-p")
+        .arg("--ignore-fail-on-non-empty")
+        .arg("a/b");
+    if !assert_rmdir_success_or_skip(&mut cmd) {
+        return;
+    }
+
+    assert!(!temp_dir.path().join("a/b").exists());
+    assert!(temp_dir.path().join("a/keep.txt").exists());
+}
@@ -0,0 +1,115 @@
+#![cfg(unix)]
+
+use assert_cmd::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+/// safecmd バイナリ実行時に明示テストモードを付与したコマンドを生成する。
+fn safecmd_command() -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("safecmd"));
+    cmd.env("SAFECMD_TEST_MODE", "1");
+    cmd
+}
+
+#[test]
+fn subcommand_dispatches_to_command() {
+    // `safecmd ln ...` のサブコマンド形式で各コマンドを実行できることを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let link = temp_dir.path().join("link");
+
+    safecmd_command()
+        .arg("ln")
+        .arg("-s")
+        .arg("target")
+        .arg(&link)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_link(&link).expect("read link"),
+        Path::new("target")
+    );
+}
+
+#[test]
+fn install_creates_links_that_dispatch_on_argv0() {
+    // install で作成したリンク経由の起動では、リンク名のコマンドとして動作することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let bin_dir = temp_dir.path().join("bin");
+
+    safecmd_command()
+        .arg("install")
+        .arg("--dir")
+        .arg(&bin_dir)
+        .assert()
+        .success();
+
+    for name in ["rm", "rmdir", "cp", "mv", "ln"] {
+        let target = fs::read_link(bin_dir.join(name)).expect("read installed link");
+        assert_eq!(target.file_name().expect("link target name"), "safecmd");
+    }
+    assert!(fs::symlink_metadata(bin_dir.join("write")).is_err());
+
+    let source = temp_dir.path().join("a.txt");
+    let copy = temp_dir.path().join("b.txt");
+    fs::write(&source, b"data").expect("write source");
+    Command::new(bin_dir.join("cp"))
+        .env("SAFECMD_TEST_MODE", "1")
+        .arg(&source)
+        .arg(&copy)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&copy).expect("read copy"), "data");
+}
+
+#[test]
+fn install_is_idempotent_and_keeps_unrelated_entries_without_force() {
+    // 既に safecmd を指すリンクはそのまま成功し、別のファイルは --force なしでは置き換えないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let bin_dir = temp_dir.path().join("bin");
+    fs::create_dir(&bin_dir).expect("create bin dir");
+    fs::write(bin_dir.join("mv"), b"#!/bin/sh\n").expect("write existing mv");
+
+    safecmd_command()
+        .arg("install")
+        .arg("--dir")
+        .arg(&bin_dir)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("use --force"));
+    assert_eq!(
+        fs::read_to_string(bin_dir.join("mv")).expect("read existing mv"),
+        "#!/bin/sh\n"
+    );
+    assert!(fs::read_link(bin_dir.join("rm")).is_ok());
+
+    let output = safecmd_command()
+        .arg("install")
+        .arg("--dir")
+        .arg(&bin_dir)
+        .arg("--force")
+        .output()
+        .expect("run install --force");
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("failed to move existing file to trash") {
+            return;
+        }
+        panic!("install --force failed unexpectedly: {stderr}");
+    }
+    assert!(fs::read_link(bin_dir.join("mv")).is_ok());
+
+    let output = safecmd_command()
+        .arg("install")
+        .arg("--dir")
+        .arg(&bin_dir)
+        .output()
+        .expect("run install again");
+    assert!(output.status.success());
+    assert!(
+        output.stdout.is_empty(),
+        "links already pointing to safecmd should be left as they are"
+    );
+}
@@ -0,0 +1,98 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use tempfile::tempdir;
+
+/// `safecmd write` を明示テストモードで実行し、`input` を標準入力へ渡した結果を返す。
+fn run_write(args: &[&std::ffi::OsStr], input: &[u8]) -> Output {
+    let mut child = Command::new(assert_cmd::cargo::cargo_bin!("safecmd"))
+        .env("SAFECMD_TEST_MODE", "1")
+        .arg("write")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn safecmd");
+    // 入力を読まずに終了する失敗系では書き込みが EPIPE になるため、結果は無視する。
+    let _ = child.stdin.take().expect("open stdin").write_all(input);
+    child.wait_with_output().expect("wait safecmd")
+}
+
+#[test]
+fn write_creates_file_from_stdin() {
+    // 存在しないファイルを標準入力の内容で作成することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let target = temp_dir.path().join("out.txt");
+
+    let output = run_write(&[target.as_os_str()], b"hello\n");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(fs::read_to_string(&target).expect("read target"), "hello\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn write_replaces_existing_file_and_keeps_its_mode() {
+    // 既存ファイルを置き換え、パーミッションを引き継ぐことを確認する。
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempdir().expect("create tmp dir");
+    let target = temp_dir.path().join("script.sh");
+    fs::write(&target, "old").expect("write target");
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o750)).expect("chmod target");
+
+    let output = run_write(&[target.as_os_str()], b"new");
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("failed to move existing file to trash") {
+            return;
+        }
+        panic!("write failed unexpectedly: {stderr}");
+    }
+
+    assert_eq!(fs::read_to_string(&target).expect("read target"), "new");
+    assert_eq!(
+        fs::metadata(&target)
+            .expect("stat target")
+            .permissions()
+            .mode()
+            & 0o777,
+        0o750
+    );
+    assert_eq!(
+        fs::read_dir(temp_dir.path()).expect("list dir").count(),
+        1,
+        "no temporary entries should be left beside the target"
+    );
+}
+
+#[test]
+fn write_no_clobber_refuses_existing_file() {
+    // -n 指定時は既存ファイルを置き換えずに失敗することを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+    let target = temp_dir.path().join("out.txt");
+    fs::write(&target, "old").expect("write target");
+
+    let output = run_write(&["-n".as_ref(), target.as_os_str()], b"new");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("File exists"));
+    assert_eq!(fs::read_to_string(&target).expect("read target"), "old");
+}
+
+#[test]
+fn write_refuses_directory_target() {
+    // ディレクトリは置き換えないことを確認する。
+    let temp_dir = tempdir().expect("create tmp dir");
+
+    let output = run_write(&[temp_dir.path().as_os_str()], b"new");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot overwrite directory"));
+    assert!(temp_dir.path().is_dir());
+}